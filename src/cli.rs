@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::sequencer::bundle::{self, Bundle};
+use crate::sequencer::device::DevicesCollection;
+
+const USAGE: &str = "Usage:
+    twitchbtevents run [--record]
+    twitchbtevents export <bundle.json> [sequence id...]
+    twitchbtevents import <bundle.json> [--map <bundle device id>=<local device id>...]";
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Listens without the UI until interrupted
+    Run { record_events: bool },
+    Export {
+        path: PathBuf,
+        sequence_ids: Vec<String>, // Empty exports every sequence
+    },
+    Import {
+        path: PathBuf,
+        mapping: HashMap<String, String>,
+    },
+}
+
+impl Command {
+    /// None when there are no arguments and the UI should start
+    pub fn parse(args: &[String]) -> Result<Option<Self>, Box<dyn Error>> {
+        let (command, rest) = match args.split_first() {
+            Some(split) => split,
+            None => return Ok(None),
+        };
+
+        if command == "run" {
+            return match rest {
+                [] => Ok(Some(Command::Run {
+                    record_events: false,
+                })),
+                [flag] if flag == "--record" => Ok(Some(Command::Run {
+                    record_events: true,
+                })),
+                _ => Err(USAGE.into()),
+            };
+        }
+
+        let (path, rest) = rest.split_first().ok_or(USAGE)?;
+        let path = PathBuf::from(path);
+
+        match command.as_str() {
+            "export" => Ok(Some(Command::Export {
+                path,
+                sequence_ids: rest.to_vec(),
+            })),
+            "import" => {
+                let mut mapping = HashMap::new();
+                let mut rest = rest.iter();
+
+                while let Some(arg) = rest.next() {
+                    if arg != "--map" {
+                        return Err(USAGE.into());
+                    }
+
+                    let (from, to) = rest
+                        .next()
+                        .and_then(|pair| pair.split_once('='))
+                        .ok_or(USAGE)?;
+                    mapping.insert(String::from(from), String::from(to));
+                }
+
+                Ok(Some(Command::Import { path, mapping }))
+            }
+            _ => Err(USAGE.into()),
+        }
+    }
+}
+
+pub async fn run(command: Command, devices: &DevicesCollection) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Run { .. } => {
+            unreachable!("Headless mode needs more than devices, main starts it")
+        }
+        Command::Export { path, sequence_ids } => {
+            let sequences: Vec<_> = bundle::read_sequences()
+                .await?
+                .into_iter()
+                .filter(|sequence| sequence_ids.is_empty() || sequence_ids.contains(&sequence.id))
+                .collect();
+
+            if sequences.is_empty() {
+                return Err("No sequences to export".into());
+            }
+
+            let count = sequences.len();
+            Bundle::export(sequences, devices).write(&path).await?;
+            println!("Exported {} sequences to {}", count, path.display());
+        }
+        Command::Import { path, mapping } => {
+            let bundle = Bundle::read(&path).await?;
+
+            let mut resolved = bundle.default_mapping(devices);
+            resolved.extend(checked_mapping(&bundle, mapping, devices)?);
+
+            for bundle_device in bundle.devices.iter() {
+                if !resolved.contains_key(&bundle_device.id) {
+                    let local_id = prompt_for_device(bundle_device, devices)?;
+                    resolved.insert(bundle_device.id.clone(), local_id);
+                }
+            }
+
+            let written = bundle::write_sequences(&bundle.import(&resolved)?).await?;
+            for (sequence, path) in written {
+                println!("Imported {} to {}", sequence.name, path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Mappings given with --map, each must name a device of the bundle and a local device which can
+/// run every action the sequences use on it
+fn checked_mapping(
+    bundle: &Bundle,
+    mapping: HashMap<String, String>,
+    devices: &DevicesCollection,
+) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    for (from, to) in mapping.iter() {
+        let bundle_device = bundle
+            .devices
+            .iter()
+            .find(|bundle_device| &bundle_device.id == from)
+            .ok_or_else(|| format!("The bundle has no device {}", from))?;
+        let device = devices
+            .get(to)
+            .ok_or_else(|| format!("There is no local device {}", to))?;
+
+        if !bundle_device.is_satisfied_by(device) {
+            return Err(format!(
+                "{} can not stand in for {}, it needs to be a {} with the actions {:?}",
+                to,
+                from,
+                bundle_device.device_type.as_deref().unwrap_or("device"),
+                bundle_device.action_ids
+            )
+            .into());
+        }
+    }
+
+    Ok(mapping)
+}
+
+/// The mapping step, asks which local device should stand in for one the bundle expects
+fn prompt_for_device(
+    bundle_device: &bundle::BundleDevice,
+    devices: &DevicesCollection,
+) -> Result<String, Box<dyn Error>> {
+    let candidates = bundle_device.compatible_devices(devices);
+    if candidates.is_empty() {
+        return Err(format!(
+            "No local device can run the actions {:?} of {} ({})",
+            bundle_device.action_ids, bundle_device.name, bundle_device.id
+        )
+        .into());
+    }
+
+    println!(
+        "{} ({}) is not configured here, choose a replacement:",
+        bundle_device.name, bundle_device.id
+    );
+    for (i, id) in candidates.iter().enumerate() {
+        println!("  {}) {} ({})", i + 1, devices[id].get_name(), id);
+    }
+
+    loop {
+        print!("> ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Err("Import cancelled".into());
+        }
+
+        let input = input.trim();
+        let chosen = match input.parse::<usize>() {
+            Ok(number) => candidates.get(number.wrapping_sub(1)),
+            Err(_) => candidates.iter().find(|id| id.as_str() == input),
+        };
+
+        match chosen {
+            Some(id) => return Ok(id.clone()),
+            None => println!("Enter a number from 1 to {}", candidates.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::bundle::BundleDevice;
+    use crate::sequencer::devices::overlay::Overlay;
+
+    fn bundle_with(device: BundleDevice) -> Bundle {
+        Bundle {
+            version: 1,
+            devices: vec![device],
+            sequences: vec![],
+        }
+    }
+
+    fn overlay_device(action_ids: &[&str]) -> BundleDevice {
+        BundleDevice {
+            id: String::from("their-overlay"),
+            name: String::from("Their Overlay"),
+            device_type: Some(String::from("OVERLAY")),
+            action_ids: action_ids.iter().map(|id| String::from(*id)).collect(),
+        }
+    }
+
+    fn local_devices() -> DevicesCollection {
+        let devices = crate::sequencer::devices::timer::setup(HashMap::new());
+        let mut devices = crate::sequencer::devices::variables::setup(devices);
+        devices.insert(
+            String::from("overlay"),
+            Box::new(Overlay::new(
+                String::from("overlay"),
+                String::from("Overlay"),
+            )),
+        );
+
+        devices
+    }
+
+    fn map(from: &str, to: &str) -> HashMap<String, String> {
+        HashMap::from([(String::from(from), String::from(to))])
+    }
+
+    #[test]
+    fn mapping_to_a_compatible_device_is_kept() {
+        let bundle = bundle_with(overlay_device(&["showText"]));
+
+        let mapping = checked_mapping(&bundle, map("their-overlay", "overlay"), &local_devices());
+
+        assert_eq!(mapping.unwrap(), map("their-overlay", "overlay"));
+    }
+
+    #[test]
+    fn mapping_to_a_device_of_another_type_is_refused() {
+        let bundle = bundle_with(overlay_device(&["showText"]));
+
+        assert!(checked_mapping(&bundle, map("their-overlay", "timer"), &local_devices()).is_err());
+    }
+
+    #[test]
+    fn mapping_to_a_device_missing_an_action_is_refused() {
+        let bundle = bundle_with(overlay_device(&["showText", "confetti"]));
+
+        assert!(
+            checked_mapping(&bundle, map("their-overlay", "overlay"), &local_devices()).is_err()
+        );
+    }
+
+    #[test]
+    fn mapping_unknown_devices_is_refused() {
+        let bundle = bundle_with(overlay_device(&["showText"]));
+
+        assert!(
+            checked_mapping(&bundle, map("their-overlay", "missing"), &local_devices()).is_err()
+        );
+        assert!(checked_mapping(&bundle, map("missing", "overlay"), &local_devices()).is_err());
+    }
+}
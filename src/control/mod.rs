@@ -0,0 +1,229 @@
+pub mod server;
+
+use core::fmt;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tracing::info;
+
+use crate::sequencer::device::DevicesCollection;
+use crate::sequencer::progress::{Progress, ProgressSender, Status};
+use crate::sequencer::reaction_sequence::ReactionSequence;
+use crate::sequencer::variable_store::VariableStore;
+use crate::sequencer::{PlayLock, QueueEvent};
+use crate::triggers::TriggerSource;
+
+const REQUESTS_CAPACITY: usize = 16;
+
+/// Asks whoever runs the listeners, the UI or headless mode, to change what they are doing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControlRequest {
+    StartListeners,
+    StopListeners,
+    Pause,
+    Resume,
+}
+
+#[derive(Debug, Clone)]
+pub enum ControlError {
+    UnknownSequence(String),
+    NotListening, // Nothing is there to run the listeners
+    Failed(String),
+}
+
+impl fmt::Display for ControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlError::UnknownSequence(id) => write!(f, "There is no sequence {}", id),
+            ControlError::NotListening => write!(f, "Nothing is listening for requests"),
+            ControlError::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl Error for ControlError {}
+
+/// What the listeners are doing, as reported by the control API
+#[derive(Debug, Clone, Default)]
+pub struct ListenerState {
+    pub running: bool,
+    pub paused: bool,
+    pub sequences: Vec<ReactionSequence>, // As last loaded, saved or started
+    pub trigger_sources: BTreeMap<String, Status>,
+    pub devices: BTreeMap<String, Status>,
+}
+
+/// Shared by the control API and whoever runs the listeners.
+/// Every run publishes to the same progress so subscribers see all of them
+#[derive(Debug, Clone)]
+pub struct Control {
+    requests: broadcast::Sender<ControlRequest>,
+    state: Arc<Mutex<ListenerState>>,
+    progress: ProgressSender,
+    devices: DevicesCollection,
+    variable_store: VariableStore,
+    play_lock: PlayLock, // Shared with the listeners' queue
+}
+
+impl Control {
+    pub fn new(
+        devices: DevicesCollection,
+        trigger_ids: Vec<String>,
+        variable_store: VariableStore,
+    ) -> Self {
+        let (requests, _) = broadcast::channel(REQUESTS_CAPACITY);
+        let state = ListenerState {
+            trigger_sources: trigger_ids
+                .into_iter()
+                .map(|id| (id, Status::Stopped))
+                .collect(),
+            devices: devices
+                .keys()
+                .map(|id| (id.clone(), Status::Ready))
+                .collect(),
+            ..ListenerState::default()
+        };
+
+        Control {
+            requests,
+            state: Arc::new(Mutex::new(state)),
+            progress: ProgressSender::new(),
+            devices,
+            variable_store,
+            play_lock: PlayLock::default(),
+        }
+    }
+
+    pub fn play_lock(&self) -> PlayLock {
+        self.play_lock.clone()
+    }
+
+    pub fn progress(&self) -> &ProgressSender {
+        &self.progress
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ControlRequest> {
+        self.requests.subscribe()
+    }
+
+    pub fn request(&self, request: ControlRequest) -> Result<(), ControlError> {
+        self.requests
+            .send(request)
+            .map(|_| ())
+            .map_err(|_| ControlError::NotListening)
+    }
+
+    pub fn state(&self) -> ListenerState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn set_sequences(&self, sequences: Vec<ReactionSequence>) {
+        self.state.lock().unwrap().sequences = sequences;
+    }
+
+    pub fn set_running(&self, running: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.running = running;
+        state.paused = false;
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.state.lock().unwrap().paused = paused;
+    }
+
+    /// Keeps the statuses of trigger sources and devices up to date
+    fn track(&self, progress: &Progress) {
+        let mut state = self.state.lock().unwrap();
+        match progress {
+            Progress::TriggerSource(id, status) => {
+                state.trigger_sources.insert(id.clone(), status.clone());
+            }
+            Progress::Device(id, status) => {
+                state.devices.insert(id.clone(), status.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// Plays a sequence whether or not listeners are running, paused or the sequence is enabled, so
+    /// it can be tried out. It skips the queue but waits for a sequence the queue is playing to finish
+    pub fn fire(&self, sequence_id: &str, payload: Value) -> Result<(), ControlError> {
+        let sequence = self
+            .state()
+            .sequences
+            .into_iter()
+            .find(|sequence| sequence.id == sequence_id)
+            .ok_or_else(|| ControlError::UnknownSequence(String::from(sequence_id)))?;
+
+        let trigger_source =
+            TriggerSource::try_from_str(&sequence.trigger.trigger_id).ok_or_else(|| {
+                ControlError::Failed(format!(
+                    "{} has an unknown trigger source {}",
+                    sequence.name, sequence.trigger.trigger_id
+                ))
+            })?;
+
+        let event = QueueEvent {
+            trigger_source,
+            trigger_event_id: sequence.trigger.trigger_event_id.clone(),
+            payload,
+        };
+
+        info!(sequence = %sequence.name, "Fired through the control API");
+        let control = self.clone();
+        tokio::spawn(async move {
+            let _playing = control.play_lock.lock().await;
+            sequence
+                .play(
+                    &control.devices,
+                    &control.variable_store,
+                    &event,
+                    &control.progress,
+                )
+                .await;
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::time;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn fired_sequence_waits_for_the_queue() {
+        let control = Control::new(Default::default(), vec![], VariableStore::default());
+        control.set_sequences(vec![serde_json::from_value(json!({
+            "version": 1,
+            "id": "raid",
+            "name": "Raid",
+            "enabled": true,
+            "trigger": { "triggerId": "simulator", "triggerEventId": "raid" },
+            "sequence": [],
+        }))
+        .unwrap()]);
+        let mut progress = control.progress().subscribe();
+
+        let play_lock = control.play_lock();
+        let playing = play_lock.lock().await; // As the queue does while it plays
+        control.fire("raid", json!({})).unwrap();
+
+        let started = time::timeout(Duration::from_millis(50), progress.recv()).await;
+        assert!(started.is_err(), "played while the queue was playing");
+
+        drop(playing);
+        let started = time::timeout(Duration::from_secs(5), progress.recv()).await;
+        assert!(matches!(
+            started,
+            Ok(Ok(Progress::SequenceStarted(id, _, _))) if id == "raid"
+        ));
+    }
+}
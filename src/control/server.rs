@@ -0,0 +1,356 @@
+//! Localhost HTTP API for driving the app from other tools.
+//! Every request needs the token, in an `Authorization: Bearer` header or a `token` query parameter.
+//!
+//! - `GET /api/sequences` lists the sequences
+//! - `POST /api/sequences/{id}/fire` plays a sequence, the JSON body is its payload
+//! - `POST /api/listeners/{start|stop|pause|resume}`
+//! - `GET /api/status` reports whether listeners run and the status of trigger sources and devices
+//! - `GET /api/feed` is a WebSocket of JSON messages for events and sequence progress
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use futures_util::{select, FutureExt, SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tracing::{debug, info};
+use warp::http::StatusCode;
+use warp::hyper::body::Bytes;
+use warp::ws::{Message, WebSocket, Ws};
+use warp::{Filter, Rejection};
+
+use super::{Control, ControlError, ControlRequest};
+use crate::overlay;
+use crate::sequencer::progress::{Progress, Status};
+
+const MAX_PAYLOAD_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+/// Serves the API until the app exits
+pub async fn serve(port: u16, token: String, control: Control) -> Result<(), Box<dyn Error>> {
+    if token.is_empty() {
+        return Err("The control API needs a token".into());
+    }
+
+    tokio::spawn(track_statuses(control.clone()));
+
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+    let (address, server) =
+        warp::serve(routes(Arc::new(token), control)).try_bind_ephemeral(address)?;
+
+    info!("Control API listening on {}", address);
+    server.await;
+
+    Ok(())
+}
+
+fn routes(
+    token: Arc<String>,
+    control: Control,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let with_control = warp::any().map(move || control.clone());
+
+    let sequences_route = warp::get()
+        .and(warp::path!("api" / "sequences"))
+        .and(with_control.clone())
+        .map(|control: Control| warp::reply::json(&control.state().sequences));
+
+    let fire_route = warp::post()
+        .and(warp::path!("api" / "sequences" / String / "fire"))
+        .and(warp::body::content_length_limit(MAX_PAYLOAD_BYTES))
+        .and(warp::body::bytes())
+        .and(with_control.clone())
+        .map(fire);
+
+    let listeners_route = warp::post()
+        .and(warp::path!("api" / "listeners" / String))
+        .and(with_control.clone())
+        .map(listeners);
+
+    let status_route = warp::get()
+        .and(warp::path!("api" / "status"))
+        .and(with_control.clone())
+        .map(|control: Control| warp::reply::json(&status(&control)));
+
+    let feed_route = warp::path!("api" / "feed")
+        .and(warp::ws())
+        .and(with_control)
+        .map(|ws: Ws, control: Control| ws.on_upgrade(move |socket| feed(socket, control)));
+
+    authorized(token)
+        .and(
+            sequences_route
+                .or(fire_route)
+                .or(listeners_route)
+                .or(status_route)
+                .or(feed_route),
+        )
+        .recover(rejected)
+}
+
+fn authorized(token: Arc<String>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::query::<HashMap<String, String>>())
+        .and_then(
+            move |header: Option<String>, query: HashMap<String, String>| {
+                let token = token.clone();
+                async move {
+                    let given = header
+                        .as_deref()
+                        .and_then(|header| header.strip_prefix("Bearer "))
+                        .or_else(|| query.get("token").map(String::as_str));
+
+                    match given {
+                        Some(given) if crate::secret::matches(given, &token) => Ok(()),
+                        _ => Err(warp::reject::custom(Unauthorized)),
+                    }
+                }
+            },
+        )
+        .untuple_one()
+}
+
+async fn rejected(rejection: Rejection) -> Result<StatusCode, Rejection> {
+    if rejection.find::<Unauthorized>().is_some() {
+        Ok(StatusCode::UNAUTHORIZED)
+    } else if rejection.is_not_found() {
+        Ok(StatusCode::NOT_FOUND)
+    } else {
+        Err(rejection)
+    }
+}
+
+fn fire(sequence_id: String, body: Bytes, control: Control) -> StatusCode {
+    let payload = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(payload) => payload,
+            Err(_) => return StatusCode::BAD_REQUEST,
+        }
+    };
+
+    match control.fire(&sequence_id, payload) {
+        Ok(()) => StatusCode::ACCEPTED,
+        Err(ControlError::UnknownSequence(_)) => StatusCode::NOT_FOUND,
+        Err(_) => StatusCode::UNPROCESSABLE_ENTITY,
+    }
+}
+
+fn listeners(action: String, control: Control) -> StatusCode {
+    let request = match action.as_str() {
+        "start" => ControlRequest::StartListeners,
+        "stop" => ControlRequest::StopListeners,
+        "pause" => ControlRequest::Pause,
+        "resume" => ControlRequest::Resume,
+        _ => return StatusCode::NOT_FOUND,
+    };
+
+    match control.request(request) {
+        Ok(()) => StatusCode::ACCEPTED,
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+fn status(control: &Control) -> Value {
+    let state = control.state();
+
+    json!({
+        "running": state.running,
+        "paused": state.paused,
+        "trigger_sources": statuses(&state.trigger_sources),
+        "devices": statuses(&state.devices),
+    })
+}
+
+fn statuses(by_id: &BTreeMap<String, Status>) -> Value {
+    by_id
+        .iter()
+        .map(|(id, status)| (id.clone(), Value::String(status.to_string())))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+async fn track_statuses(control: Control) {
+    let mut progress = control.progress().subscribe();
+
+    loop {
+        match progress.recv().await {
+            Ok(progress) => control.track(&progress),
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Sends the current status, then every update until the client goes away. What clients send is ignored
+async fn feed(socket: WebSocket, control: Control) {
+    let (mut sender, mut reciever) = socket.split();
+    let mut progress = control.progress().subscribe();
+
+    let initial = json!({ "type": "status", "status": status(&control) });
+    if sender
+        .send(Message::text(initial.to_string()))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    loop {
+        let mut update = Box::pin(progress.recv().fuse());
+
+        let message = select! {
+            message = reciever.next().fuse() => match message {
+                Some(Ok(message)) if !message.is_close() => continue,
+                _ => break,
+            },
+            update = update => match update {
+                Ok(update) => progress_message(&update),
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    json!({ "type": "missed", "count": count })
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+
+        if sender
+            .send(Message::text(message.to_string()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+
+    debug!("Control API feed closed");
+}
+
+fn progress_message(progress: &Progress) -> Value {
+    match progress {
+        Progress::TriggerSource(id, status) => {
+            json!({ "type": "trigger_source", "id": id, "status": status.to_string() })
+        }
+        Progress::Device(id, status) => {
+            json!({ "type": "device", "id": id, "status": status.to_string() })
+        }
+        Progress::EventReceived(event) => json!({
+            "type": "event_received",
+            "trigger_source": event.trigger_source.as_str(),
+            "trigger_event_id": event.trigger_event_id,
+            "payload": event.payload,
+        }),
+        Progress::EventTaken => json!({ "type": "event_taken" }),
+        Progress::SequenceStarted(id, name, payload) => json!({
+            "type": "sequence_started",
+            "id": id,
+            "name": name,
+            "payload": payload,
+        }),
+        Progress::StepStarted(id, step) => {
+            json!({ "type": "step_started", "id": id, "step": step })
+        }
+        Progress::SequenceFinished(id) => json!({ "type": "sequence_finished", "id": id }),
+        Progress::Paused(paused) => json!({ "type": "paused", "paused": paused }),
+        Progress::Overlay(message) => {
+            json!({ "type": "overlay", "message": overlay::message_json(message) })
+        }
+        Progress::Error(error) => json!({ "type": "error", "message": error }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::variable_store::VariableStore;
+
+    const TOKEN: &str = "hunter2";
+
+    fn control() -> Control {
+        let control = Control::new(Default::default(), vec![], VariableStore::default());
+        control.set_sequences(vec![serde_json::from_value(json!({
+            "version": 1,
+            "id": "raid",
+            "name": "Raid",
+            "enabled": true,
+            "trigger": { "triggerId": "simulator", "triggerEventId": "raid" },
+            "sequence": [],
+        }))
+        .unwrap()]);
+
+        control
+    }
+
+    async fn status_of(request: warp::test::RequestBuilder) -> StatusCode {
+        request
+            .reply(&routes(Arc::new(String::from(TOKEN)), control()))
+            .await
+            .status()
+    }
+
+    #[tokio::test]
+    async fn missing_token_is_unauthorized() {
+        let status = status_of(warp::test::request().path("/api/status")).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn wrong_token_is_unauthorized() {
+        let header = warp::test::request()
+            .path("/api/status")
+            .header("authorization", "Bearer hunter3");
+        let query = warp::test::request().path("/api/status?token=hunter3");
+
+        assert_eq!(status_of(header).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status_of(query).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn token_in_header_or_query_is_accepted() {
+        let header = warp::test::request()
+            .path("/api/status")
+            .header("authorization", format!("Bearer {}", TOKEN));
+        let query = warp::test::request().path(&format!("/api/status?token={}", TOKEN));
+
+        assert_eq!(status_of(header).await, StatusCode::OK);
+        assert_eq!(status_of(query).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn firing_an_unknown_sequence_is_not_found() {
+        let request = warp::test::request()
+            .method("POST")
+            .path("/api/sequences/missing/fire")
+            .header("authorization", format!("Bearer {}", TOKEN));
+
+        assert_eq!(status_of(request).await, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn firing_a_sequence_is_accepted() {
+        let request = warp::test::request()
+            .method("POST")
+            .path("/api/sequences/raid/fire")
+            .header("authorization", format!("Bearer {}", TOKEN))
+            .body(r#"{"user_name": "viewer"}"#);
+
+        assert_eq!(status_of(request).await, StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test]
+    async fn bad_payload_is_a_bad_request() {
+        let request = warp::test::request()
+            .method("POST")
+            .path("/api/sequences/raid/fire")
+            .header("authorization", format!("Bearer {}", TOKEN))
+            .body("{\"user_name\":");
+
+        assert_eq!(status_of(request).await, StatusCode::BAD_REQUEST);
+    }
+}
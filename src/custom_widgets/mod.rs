@@ -13,3 +13,10 @@ pub mod horizontal_scrollable {
             iced::Renderer,
         >;
 }
+
+pub mod modal {
+    //! Show content above the rest of the window.
+    /// A widget which shows its content over a dimmed underlay.
+    pub type Modal<'a, Message> =
+        crate::custom_widgets::native::modal::Modal<'a, Message, iced::Renderer>;
+}
@@ -22,8 +22,8 @@ pub mod style {
     pub use iced_style::scrollable::{Scrollbar, Scroller};
 }
 
-/// A widget that can vertically display an infinite amount of content with a
-/// scrollbar.
+/// A widget that can horizontally display an infinite amount of content with a
+/// scrollbar, optionally letting its children be reordered by dragging them.
 #[allow(missing_debug_implementations)]
 pub struct HorizontalScrollable<'a, Message, Renderer>
 where
@@ -31,6 +31,7 @@ where
     Renderer::Theme: StyleSheet,
 {
     state: &'a mut State,
+    width: Length,
     height: Length,
     max_height: u32,
     scrollbar_height: u16,
@@ -38,6 +39,7 @@ where
     scroller_width: u16,
     content: Row<'a, Message, Renderer>,
     on_scroll: Option<Box<dyn Fn(f32) -> Message + 'a>>,
+    on_reorder: Option<Box<dyn Fn(usize, usize) -> Message + 'a>>,
     style: <Renderer::Theme as StyleSheet>::Style,
 }
 
@@ -50,6 +52,7 @@ where
     pub fn new(state: &'a mut State) -> Self {
         HorizontalScrollable {
             state,
+            width: Length::Fill,
             height: Length::Shrink,
             max_height: u32::MAX,
             scrollbar_height: 10,
@@ -57,6 +60,7 @@ where
             scroller_width: 10,
             content: Row::new(),
             on_scroll: None,
+            on_reorder: None,
             style: Default::default(),
         }
     }
@@ -79,7 +83,7 @@ where
 
     /// Sets the width of the [`HorizontalScrollable`].
     pub fn width(mut self, width: Length) -> Self {
-        self.content = self.content.width(width);
+        self.width = width;
         self
     }
 
@@ -137,6 +141,16 @@ where
         self
     }
 
+    /// Sets a function to call when a child is dragged to a new position.
+    ///
+    /// The function takes the index the child was dragged from and the index
+    /// it should have once moved. Dragging starts on any part of a child which
+    /// does not handle the press itself, e.g. the background of a card.
+    pub fn on_reorder(mut self, f: impl Fn(usize, usize) -> Message + 'a) -> Self {
+        self.on_reorder = Some(Box::new(f));
+        self
+    }
+
     /// Sets the style of the [`HorizontalScrollable`] .
     pub fn style(mut self, style: impl Into<<Renderer::Theme as StyleSheet>::Style>) -> Self {
         self.style = style.into();
@@ -164,8 +178,8 @@ pub fn layout<Renderer>(
     let limits = limits.width(width).height(height);
 
     let child_limits = layout::Limits::new(
-        Size::new(limits.min().width, 0.0),
-        Size::new(limits.max().width, f32::INFINITY),
+        Size::new(0.0, limits.min().height),
+        Size::new(f32::INFINITY, limits.max().height),
     );
 
     let content = layout_content(renderer, &child_limits);
@@ -187,6 +201,7 @@ pub fn update<Message>(
     scrollbar_margin: u16,
     scroller_width: u16,
     on_scroll: &Option<Box<dyn Fn(f32) -> Message + '_>>,
+    on_reorder: &Option<Box<dyn Fn(usize, usize) -> Message + '_>>,
     update_content: impl FnOnce(
         Event,
         Layout<'_>,
@@ -214,12 +229,31 @@ pub fn update<Message>(
         .map(|scrollbar| scrollbar.is_mouse_over(cursor_position))
         .unwrap_or(false);
 
+    let content_x = cursor_position.x + state.offset(bounds, content_bounds) as f32;
+
+    if let Some(on_reorder) = on_reorder {
+        if let Some(from) = state.dragged_child {
+            match event {
+                Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                | Event::Touch(touch::Event::FingerLifted { .. }) => {
+                    state.dragged_child = None;
+                    shell.publish(on_reorder(from, drop_index(content, from, content_x)));
+
+                    return event::Status::Captured;
+                }
+                Event::Touch(touch::Event::FingerLost { .. }) => {
+                    state.dragged_child = None;
+
+                    return event::Status::Captured;
+                }
+                _ => {}
+            }
+        }
+    }
+
     let event_status = {
         let cursor_position = if is_mouse_over && !is_mouse_over_scrollbar {
-            Point::new(
-                cursor_position.x,
-                cursor_position.y + state.offset(bounds, content_bounds) as f32,
-            )
+            Point::new(content_x, cursor_position.y)
         } else {
             // TODO: Make `cursor_position` an `Option<Point>` so we can encode
             // cursor availability.
@@ -235,16 +269,30 @@ pub fn update<Message>(
         return event::Status::Captured;
     }
 
+    // Presses the children ignored start a drag
+    if on_reorder.is_some() && is_mouse_over && !is_mouse_over_scrollbar {
+        if let Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+        | Event::Touch(touch::Event::FingerPressed { .. }) = event
+        {
+            if let Some(index) = child_at(content, content_x) {
+                state.dragged_child = Some(index);
+
+                return event::Status::Captured;
+            }
+        }
+    }
+
     if is_mouse_over {
         match event {
             Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                // Plain mouse wheels only scroll vertically, so either axis moves the row
                 match delta {
-                    mouse::ScrollDelta::Lines { y, .. } => {
+                    mouse::ScrollDelta::Lines { x, y } => {
                         // TODO: Configurable speed (?)
-                        state.scroll(y * 60.0, bounds, content_bounds);
+                        state.scroll(if x != 0.0 { x } else { y } * 60.0, bounds, content_bounds);
                     }
-                    mouse::ScrollDelta::Pixels { y, .. } => {
-                        state.scroll(y, bounds, content_bounds);
+                    mouse::ScrollDelta::Pixels { x, y } => {
+                        state.scroll(if x != 0.0 { x } else { y }, bounds, content_bounds);
                     }
                 }
 
@@ -259,7 +307,7 @@ pub fn update<Message>(
                     }
                     touch::Event::FingerMoved { .. } => {
                         if let Some(scroll_box_touched_at) = state.scroll_box_touched_at {
-                            let delta = cursor_position.y - scroll_box_touched_at.y;
+                            let delta = cursor_position.x - scroll_box_touched_at.x;
 
                             state.scroll(delta, bounds, content_bounds);
 
@@ -361,13 +409,15 @@ pub fn mouse_interaction(
         .map(|scrollbar| scrollbar.is_mouse_over(cursor_position))
         .unwrap_or(false);
 
-    if is_mouse_over_scrollbar || state.is_scroller_grabbed() {
+    if state.is_dragging() {
+        mouse::Interaction::Grabbing
+    } else if is_mouse_over_scrollbar || state.is_scroller_grabbed() {
         mouse::Interaction::Idle
     } else {
         let offset = state.offset(bounds, content_bounds);
 
         let cursor_position = if is_mouse_over && !is_mouse_over_scrollbar {
-            Point::new(cursor_position.x, cursor_position.y + offset as f32)
+            Point::new(cursor_position.x + offset as f32, cursor_position.y)
         } else {
             Point::new(cursor_position.x, -1.0)
         };
@@ -376,7 +426,7 @@ pub fn mouse_interaction(
             content_layout,
             cursor_position,
             &Rectangle {
-                y: bounds.y + offset as f32,
+                x: bounds.x + offset as f32,
                 ..bounds
             },
         )
@@ -418,12 +468,34 @@ pub fn draw<Renderer>(
         .map(|scrollbar| scrollbar.is_mouse_over(cursor_position))
         .unwrap_or(false);
 
+    let content_x = cursor_position.x + offset as f32;
     let cursor_position = if is_mouse_over && !is_mouse_over_scrollbar {
-        Point::new(cursor_position.x + offset as f32, cursor_position.y)
+        Point::new(content_x, cursor_position.y)
     } else {
         Point::new(cursor_position.x, -1.0)
     };
 
+    // Dragging shows where the child would land
+    let draw_content = |renderer: &mut Renderer,
+                        layout: Layout<'_>,
+                        cursor_position: Point,
+                        viewport: &Rectangle| {
+        draw_content(renderer, layout, cursor_position, viewport);
+
+        if let Some(from) = state.dragged_child {
+            let marker = drop_marker(layout, from, content_x);
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: marker,
+                    border_radius: 0.0,
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                theme.dragging(style).scroller.color,
+            );
+        }
+    };
+
     if let Some(scrollbar) = scrollbar {
         renderer.with_layer(bounds, |renderer| {
             renderer.with_translation(Vector::new(-(offset as f32), 0.0), |renderer| {
@@ -506,32 +578,32 @@ fn scrollbar(
 ) -> Option<Scrollbar> {
     let offset = state.offset(bounds, content_bounds);
 
-    if content_bounds.height > bounds.height {
-        let outer_width = scrollbar_height.max(scroller_width) + 2 * scrollbar_margin;
+    if content_bounds.width > bounds.width {
+        let outer_height = scrollbar_height.max(scroller_width) + 2 * scrollbar_margin;
 
         let outer_bounds = Rectangle {
-            x: bounds.x + bounds.width - outer_width as f32,
-            y: bounds.y,
-            width: outer_width as f32,
-            height: bounds.height,
+            x: bounds.x,
+            y: bounds.y + bounds.height - outer_height as f32,
+            width: bounds.width,
+            height: outer_height as f32,
         };
 
         let scrollbar_bounds = Rectangle {
-            x: bounds.x + bounds.width - f32::from(outer_width / 2 + scrollbar_height / 2),
-            y: bounds.y,
-            width: scrollbar_height as f32,
-            height: bounds.height,
+            x: bounds.x,
+            y: bounds.y + bounds.height - f32::from(outer_height / 2 + scrollbar_height / 2),
+            width: bounds.width,
+            height: scrollbar_height as f32,
         };
 
-        let ratio = bounds.height / content_bounds.height;
-        let scroller_height = bounds.height * ratio;
-        let y_offset = offset as f32 * ratio;
+        let ratio = bounds.width / content_bounds.width;
+        let scroller_length = bounds.width * ratio;
+        let x_offset = offset as f32 * ratio;
 
         let scroller_bounds = Rectangle {
-            x: bounds.x + bounds.width - f32::from(outer_width / 2 + scroller_width / 2),
-            y: scrollbar_bounds.y + y_offset,
-            width: scroller_width as f32,
-            height: scroller_height,
+            x: scrollbar_bounds.x + x_offset,
+            y: bounds.y + bounds.height - f32::from(outer_height / 2 + scroller_width / 2),
+            width: scroller_length,
+            height: scroller_width as f32,
         };
 
         Some(Scrollbar {
@@ -546,6 +618,54 @@ fn scrollbar(
     }
 }
 
+/// The child under `x`, in content coordinates
+fn child_at(content: Layout<'_>, x: f32) -> Option<usize> {
+    content.children().position(|child| {
+        let bounds = child.bounds();
+        x >= bounds.x && x < bounds.x + bounds.width
+    })
+}
+
+/// The index the dragged child ends up at when dropped at `x`, in content coordinates
+fn drop_index(content: Layout<'_>, from: usize, x: f32) -> usize {
+    let before = content
+        .children()
+        .filter(|child| {
+            let bounds = child.bounds();
+            bounds.x + bounds.width / 2.0 < x
+        })
+        .count();
+
+    if before > from {
+        before - 1
+    } else {
+        before
+    }
+}
+
+/// A thin bar in the gap the dragged child would be dropped into
+fn drop_marker(content: Layout<'_>, from: usize, x: f32) -> Rectangle {
+    let content_bounds = content.bounds();
+    let to = drop_index(content, from, x);
+    let gap = if to > from { to + 1 } else { to };
+
+    let marker_x = match content.children().nth(gap) {
+        Some(child) => child.bounds().x - 3.0,
+        None => content
+            .children()
+            .last()
+            .map(|child| child.bounds().x + child.bounds().width + 1.0)
+            .unwrap_or(content_bounds.x),
+    };
+
+    Rectangle {
+        x: marker_x,
+        y: content_bounds.y,
+        width: 2.0,
+        height: content_bounds.height,
+    }
+}
+
 fn notify_on_scroll<Message>(
     state: &State,
     on_scroll: &Option<Box<dyn Fn(f32) -> Message + '_>>,
@@ -571,7 +691,7 @@ where
     Renderer::Theme: StyleSheet,
 {
     fn width(&self) -> Length {
-        Widget::<Message, Renderer>::width(&self.content)
+        self.width
     }
 
     fn height(&self) -> Length {
@@ -608,6 +728,7 @@ where
             self.scrollbar_margin,
             self.scroller_width,
             &self.on_scroll,
+            &self.on_reorder,
             |event, layout, cursor_position, clipboard, shell| {
                 self.content
                     .on_event(event, layout, cursor_position, renderer, clipboard, shell)
@@ -676,7 +797,7 @@ where
                 let content_layout = layout.children().next().unwrap();
                 let content_bounds = content_layout.bounds();
                 let offset = state.offset(bounds, content_bounds);
-                overlay.translate(Vector::new(offset as f32, 0.0))
+                overlay.translate(Vector::new(-(offset as f32), 0.0))
             })
     }
 }
@@ -686,6 +807,7 @@ where
 pub struct State {
     scroller_grabbed_at: Option<f32>,
     scroll_box_touched_at: Option<Point>,
+    dragged_child: Option<usize>,
     offset: Offset,
 }
 
@@ -694,6 +816,7 @@ impl Default for State {
         Self {
             scroller_grabbed_at: None,
             scroll_box_touched_at: None,
+            dragged_child: None,
             offset: Offset::Absolute(0.0),
         }
     }
@@ -710,41 +833,41 @@ impl Offset {
     fn absolute(self, bounds: Rectangle, content_bounds: Rectangle) -> f32 {
         match self {
             Self::Absolute(absolute) => {
-                let hidden_content = (content_bounds.height - bounds.height).max(0.0);
+                let hidden_content = (content_bounds.width - bounds.width).max(0.0);
 
                 absolute.min(hidden_content)
             }
             Self::Relative(percentage) => {
-                ((content_bounds.height - bounds.height) * percentage).max(0.0)
+                ((content_bounds.width - bounds.width) * percentage).max(0.0)
             }
         }
     }
 }
 
 impl State {
-    /// Creates a new [`State`] with the scrollbar located at the top.
+    /// Creates a new [`State`] with the scrollbar located at the left.
     pub fn new() -> Self {
         State::default()
     }
 
     /// Apply a scrolling offset to the current [`State`], given the bounds of
     /// the [`HorizontalScrollable`] and its contents.
-    pub fn scroll(&mut self, delta_y: f32, bounds: Rectangle, content_bounds: Rectangle) {
-        if bounds.height >= content_bounds.height {
+    pub fn scroll(&mut self, delta_x: f32, bounds: Rectangle, content_bounds: Rectangle) {
+        if bounds.width >= content_bounds.width {
             return;
         }
 
         self.offset = Offset::Absolute(
-            (self.offset.absolute(bounds, content_bounds) - delta_y)
+            (self.offset.absolute(bounds, content_bounds) - delta_x)
                 .max(0.0)
-                .min(content_bounds.height - bounds.height),
+                .min(content_bounds.width - bounds.width),
         );
     }
 
     /// Scrolls the [`HorizontalScrollable`] to a relative amount.
     ///
-    /// `0` represents scrollbar at the top, while `1` represents scrollbar at
-    /// the bottom.
+    /// `0` represents scrollbar at the left, while `1` represents scrollbar at
+    /// the right.
     pub fn scroll_to(&mut self, percentage: f32, bounds: Rectangle, content_bounds: Rectangle) {
         self.snap_to(percentage);
         self.unsnap(bounds, content_bounds);
@@ -752,8 +875,8 @@ impl State {
 
     /// Snaps the scroll position to a relative amount.
     ///
-    /// `0` represents scrollbar at the top, while `1` represents scrollbar at
-    /// the bottom.
+    /// `0` represents scrollbar at the left, while `1` represents scrollbar at
+    /// the right.
     pub fn snap_to(&mut self, percentage: f32) {
         self.offset = Offset::Relative(percentage.max(0.0).min(1.0));
     }
@@ -775,6 +898,11 @@ impl State {
         self.scroller_grabbed_at.is_some()
     }
 
+    /// Returns whether a child is currently being dragged or not.
+    pub fn is_dragging(&self) -> bool {
+        self.dragged_child.is_some()
+    }
+
     /// Returns whether the scroll box is currently touched or not.
     pub fn is_scroll_box_touched(&self) -> bool {
         self.scroll_box_touched_at.is_some()
@@ -803,7 +931,7 @@ impl Scrollbar {
     fn grab_scroller(&self, cursor_position: Point) -> Option<f32> {
         if self.outer_bounds.contains(cursor_position) {
             Some(if self.scroller.bounds.contains(cursor_position) {
-                (cursor_position.y - self.scroller.bounds.y) / self.scroller.bounds.height
+                (cursor_position.x - self.scroller.bounds.x) / self.scroller.bounds.width
             } else {
                 0.5
             })
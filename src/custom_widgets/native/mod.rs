@@ -1 +1,2 @@
 pub mod horizontal_scrollable;
+pub mod modal;
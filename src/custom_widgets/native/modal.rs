@@ -0,0 +1,224 @@
+//! Show content above the rest of the window, blocking it until dismissed.
+use iced_native::event::{self, Event};
+use iced_native::layout;
+use iced_native::mouse;
+use iced_native::overlay;
+use iced_native::renderer;
+use iced_native::{
+    Background, Clipboard, Color, Element, Layout, Length, Padding, Point, Rectangle, Shell, Size,
+    Widget,
+};
+
+const PADDING: u16 = 20;
+
+/// A widget which draws its content centred over a dimmed underlay when shown.
+/// The underlay receives no mouse input while the content is shown
+#[allow(missing_debug_implementations)]
+pub struct Modal<'a, Message, Renderer> {
+    underlay: Element<'a, Message, Renderer>,
+    content: Option<Element<'a, Message, Renderer>>,
+}
+
+impl<'a, Message, Renderer> Modal<'a, Message, Renderer>
+where
+    Renderer: iced_native::Renderer,
+{
+    pub fn new(underlay: impl Into<Element<'a, Message, Renderer>>) -> Self {
+        Modal {
+            underlay: underlay.into(),
+            content: None,
+        }
+    }
+
+    /// Shows the content, leaving the modal hidden when it is None
+    pub fn content(mut self, content: Option<impl Into<Element<'a, Message, Renderer>>>) -> Self {
+        self.content = content.map(Into::into);
+        self
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Modal<'a, Message, Renderer>
+where
+    Renderer: iced_native::Renderer,
+{
+    fn width(&self) -> Length {
+        self.underlay.width()
+    }
+
+    fn height(&self) -> Length {
+        self.underlay.height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.underlay.layout(renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        self.underlay
+            .on_event(event, layout, cursor_position, renderer, clipboard, shell)
+    }
+
+    fn mouse_interaction(
+        &self,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.underlay
+            .mouse_interaction(layout, cursor_position, viewport, renderer)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        self.underlay
+            .draw(renderer, theme, style, layout, cursor_position, viewport)
+    }
+
+    fn overlay(
+        &mut self,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'_, Message, Renderer>> {
+        let Self { underlay, content } = self;
+
+        match content {
+            Some(content) => Some(overlay::Element::new(
+                layout.position(),
+                Box::new(Overlay { content }),
+            )),
+            None => underlay.overlay(layout, renderer),
+        }
+    }
+}
+
+struct Overlay<'a, 'b, Message, Renderer> {
+    content: &'b mut Element<'a, Message, Renderer>,
+}
+
+impl<'a, 'b, Message, Renderer> overlay::Overlay<Message, Renderer>
+    for Overlay<'a, 'b, Message, Renderer>
+where
+    Renderer: iced_native::Renderer,
+{
+    /// Covers the whole window, with the content centred
+    fn layout(&self, renderer: &Renderer, bounds: Size, _position: Point) -> layout::Node {
+        let limits = layout::Limits::new(Size::ZERO, bounds).pad(Padding::new(PADDING));
+        let mut content = self.content.layout(renderer, &limits);
+        let size = content.size();
+
+        content.move_to(Point::new(
+            ((bounds.width - size.width) / 2.0).max(0.0),
+            ((bounds.height - size.height) / 2.0).max(0.0),
+        ));
+
+        layout::Node::with_children(bounds, vec![content])
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        self.content.on_event(
+            event,
+            layout.children().next().unwrap(),
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        );
+
+        // Nothing gets past the backdrop
+        event::Status::Captured
+    }
+
+    fn mouse_interaction(
+        &self,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.mouse_interaction(
+            layout.children().next().unwrap(),
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+    ) {
+        let bounds = layout.bounds();
+        let content_layout = layout.children().next().unwrap();
+        let content_bounds = content_layout.bounds();
+
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border_radius: 0.0,
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            },
+            Background::Color(Color {
+                a: 0.7,
+                ..Color::BLACK
+            }),
+        );
+
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds: content_bounds,
+                border_radius: 5.0,
+                border_width: 1.0,
+                border_color: style.text_color,
+            },
+            Background::Color(Color::from_rgb8(0x20, 0x22, 0x25)),
+        );
+
+        self.content.draw(
+            renderer,
+            theme,
+            style,
+            content_layout,
+            cursor_position,
+            &bounds,
+        );
+    }
+}
+
+impl<'a, Message, Renderer> From<Modal<'a, Message, Renderer>> for Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: 'a + iced_native::Renderer,
+{
+    fn from(modal: Modal<'a, Message, Renderer>) -> Element<'a, Message, Renderer> {
+        Element::new(modal)
+    }
+}
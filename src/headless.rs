@@ -0,0 +1,150 @@
+use std::error::Error;
+
+use futures_util::{select, FutureExt};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::control::{Control, ControlRequest};
+use crate::sequencer::bundle;
+use crate::sequencer::device::DevicesCollection;
+use crate::sequencer::variable_store::VariableStore;
+use crate::triggers::TriggerCollection;
+use crate::{sequencer, ThreadActions};
+
+/// Runs the listeners without the UI until interrupted, taking requests from the control API.
+/// Sequences are read from disk each time the listeners start
+pub async fn run(
+    devices: DevicesCollection,
+    triggers: TriggerCollection,
+    variable_store: VariableStore,
+    control: Control,
+    record_events: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut requests = control.subscribe();
+    let mut listeners = Some(
+        start(
+            &devices,
+            &triggers,
+            &variable_store,
+            &control,
+            record_events,
+        )
+        .await?,
+    );
+
+    loop {
+        let request = {
+            let mut next_request = Box::pin(requests.recv().fuse());
+            let mut interrupted = Box::pin(tokio::signal::ctrl_c().fuse());
+            let mut finished = Box::pin(finished(&mut listeners).fuse());
+
+            select! {
+                request = next_request => match request {
+                    Ok(request) => Some(request),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _x = interrupted => break,
+                _x = finished => None,
+            }
+        };
+
+        let request = match request {
+            Some(request) => request,
+            None => {
+                // Ended without being asked to, so the API must not keep reporting it running
+                warn!("Listeners stopped by themselves");
+                listeners = None;
+                control.set_running(false);
+                continue;
+            }
+        };
+
+        match (request, listeners.as_ref()) {
+            (ControlRequest::StartListeners, None) => {
+                match start(
+                    &devices,
+                    &triggers,
+                    &variable_store,
+                    &control,
+                    record_events,
+                )
+                .await
+                {
+                    Ok(started) => listeners = Some(started),
+                    Err(e) => warn!("Could not start listeners: {}", e),
+                }
+            }
+            (ControlRequest::StopListeners, Some(_)) => {
+                if let Some(running) = listeners.take() {
+                    stop(running, &control).await;
+                }
+            }
+            (ControlRequest::Pause, Some((sender, _))) => {
+                if sender.send(ThreadActions::Pause).await.is_ok() {
+                    control.set_paused(true);
+                }
+            }
+            (ControlRequest::Resume, Some((sender, _))) => {
+                if sender.send(ThreadActions::Resume).await.is_ok() {
+                    control.set_paused(false);
+                }
+            }
+            _ => {} // Already in the state asked for
+        }
+    }
+
+    info!("Stopping");
+    if let Some(running) = listeners.take() {
+        stop(running, &control).await;
+    }
+
+    Ok(())
+}
+
+type Listeners = (mpsc::Sender<ThreadActions>, JoinHandle<()>);
+
+async fn start(
+    devices: &DevicesCollection,
+    triggers: &TriggerCollection,
+    variable_store: &VariableStore,
+    control: &Control,
+    record_events: bool,
+) -> Result<Listeners, Box<dyn Error>> {
+    let sequences = bundle::read_sequences().await?;
+    info!("Starting listeners for {} sequences", sequences.len());
+    control.set_sequences(sequences.clone());
+
+    let (sender, reciever) = mpsc::channel(1);
+    let handle = tokio::spawn(sequencer::run_listeners(
+        devices.clone(),
+        triggers.clone(),
+        sequences,
+        variable_store.clone(),
+        record_events,
+        reciever,
+        control.progress().clone(),
+        control.play_lock(),
+    ));
+    control.set_running(true);
+
+    Ok((sender, handle))
+}
+
+/// Completes when running listeners end, never when there are none
+async fn finished(listeners: &mut Option<Listeners>) {
+    match listeners {
+        Some((_, handle)) => {
+            handle.await.ok();
+        }
+        None => futures_util::future::pending::<()>().await,
+    }
+}
+
+async fn stop((sender, handle): Listeners, control: &Control) {
+    // Fails when the listeners already finished by themselves
+    sender.send(ThreadActions::Stop).await.ok();
+    handle.await.ok();
+    control.set_running(false);
+}
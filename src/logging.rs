@@ -0,0 +1,225 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::EnvFilter;
+
+pub const LOG_DIRECTORY: &str = "./TRS/logs"; // TODO: this path should be relative to a userdata folder
+const LOG_FILE_PREFIX: &str = "trs.log";
+const KEPT_LOG_FILES: usize = 7; // One file per day
+const DEFAULT_LEVEL: &str = "info";
+
+const REDACTED: &str = "[redacted]";
+
+/// Text which is followed by a secret, matched ignoring case
+const SECRET_MARKERS: [&str; 8] = [
+    "oauth:",
+    "bearer ",
+    "access_token",
+    "refresh_token",
+    "client_secret",
+    "password",
+    "token=",
+    "\"token\"",
+];
+
+/// Logs to the terminal and to a file in the log directory which rolls over daily.
+/// `level` is a filter such as "debug" or "info,twitchbtevents::triggers=trace", RUST_LOG overrides it.
+/// Logs written after the guard is dropped are lost, so keep it until exiting
+pub fn init(level: Option<&str>) -> Result<WorkerGuard, Box<dyn Error>> {
+    std::fs::create_dir_all(LOG_DIRECTORY)?;
+    remove_old_logs(Path::new(LOG_DIRECTORY))?;
+
+    let filter = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => EnvFilter::try_new(level.unwrap_or(DEFAULT_LEVEL))?,
+    };
+
+    let file = tracing_appender::rolling::daily(LOG_DIRECTORY, LOG_FILE_PREFIX);
+    let (file, guard) = tracing_appender::non_blocking(file);
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(Redacting(io::stdout)))
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(Redacting(file)),
+        )
+        .try_init()?;
+
+    Ok(guard)
+}
+
+/// The file being logged to, which is the newest
+pub fn current_log_file() -> Option<PathBuf> {
+    log_files(Path::new(LOG_DIRECTORY)).ok()?.pop()
+}
+
+/// Replaces whatever follows a secret marker, e.g. `oauth:abc123` becomes `oauth:[redacted]`
+pub fn redact(text: &str) -> String {
+    let lowercase = text.to_ascii_lowercase(); // Keeps byte offsets the same as in text
+    let mut redacted = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut search_from = 0;
+
+    while let Some((start, marker)) = next_marker(&lowercase, search_from) {
+        let marker_end = start + marker.len();
+        let value_start = marker_end
+            + text[marker_end..]
+                .find(|c: char| !is_separator(c))
+                .unwrap_or(text.len() - marker_end);
+        let value_end = value_start
+            + text[value_start..]
+                .find(|c: char| is_separator(c) || c == ',' || c == '}' || c == '&')
+                .unwrap_or(text.len() - value_start);
+
+        if value_end > value_start && !text[value_start..value_end].starts_with(REDACTED) {
+            redacted.push_str(&text[copied..value_start]);
+            redacted.push_str(REDACTED);
+            copied = value_end;
+        }
+
+        search_from = value_end.max(marker_end);
+    }
+
+    redacted.push_str(&text[copied..]);
+    redacted
+}
+
+/// The earliest marker at or after `from`, the longest when several start at the same place
+fn next_marker(lowercase: &str, from: usize) -> Option<(usize, &'static str)> {
+    SECRET_MARKERS
+        .iter()
+        .filter_map(|marker| {
+            lowercase[from..]
+                .find(marker)
+                .map(|index| (from + index, *marker))
+        })
+        .min_by_key(|(index, marker)| (*index, usize::MAX - marker.len()))
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == '"' || c == '\'' || c == ':' || c == '='
+}
+
+fn log_files(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name().map_or(false, |name| {
+                name.to_string_lossy().starts_with(LOG_FILE_PREFIX)
+            })
+        })
+        .collect();
+
+    // Named by date, so sorting by name sorts by age
+    files.sort();
+    Ok(files)
+}
+
+fn remove_old_logs(directory: &Path) -> io::Result<()> {
+    let files = log_files(directory)?;
+    if files.len() > KEPT_LOG_FILES {
+        for file in &files[..files.len() - KEPT_LOG_FILES] {
+            std::fs::remove_file(file)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes writers which redact secrets from each formatted line before writing it
+struct Redacting<M>(M);
+
+impl<'a, M> MakeWriter<'a> for Redacting<M>
+where
+    M: MakeWriter<'a>,
+{
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
+struct RedactingWriter<W>(W);
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .write_all(redact(&String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn irc_password_is_redacted() {
+        assert_eq!(redact("PASS oauth:abc123"), "PASS oauth:[redacted]");
+        assert_eq!(redact("PASS OAuth:ABC123"), "PASS OAuth:[redacted]");
+    }
+
+    #[test]
+    fn bearer_token_is_redacted() {
+        assert_eq!(
+            redact("Authorization: Bearer abc123 sent"),
+            "Authorization: Bearer [redacted] sent"
+        );
+    }
+
+    #[test]
+    fn json_values_are_redacted() {
+        assert_eq!(
+            redact(r#"{"access_token":"abc","expires_in":3600,"refresh_token": "def"}"#),
+            r#"{"access_token":"[redacted]","expires_in":3600,"refresh_token": "[redacted]"}"#
+        );
+        assert_eq!(redact(r#"{"token":"abc"}"#), r#"{"token":"[redacted]"}"#);
+    }
+
+    #[test]
+    fn query_parameters_are_redacted() {
+        assert_eq!(
+            redact("GET /fire?token=abc123&sequence=raid"),
+            "GET /fire?token=[redacted]&sequence=raid"
+        );
+        assert_eq!(
+            redact("client_id=id&client_secret=shh&grant_type=refresh_token"),
+            "client_id=id&client_secret=[redacted]&grant_type=refresh_token"
+        );
+    }
+
+    #[test]
+    fn redacted_text_is_left_as_it_is() {
+        let once = redact("PASS oauth:abc123 password=hunter2");
+
+        assert_eq!(once, "PASS oauth:[redacted] password=[redacted]");
+        assert_eq!(redact(&once), once);
+    }
+
+    #[test]
+    fn text_without_secrets_is_unchanged() {
+        let text = "Sequence raid played for viewer42 at 12:00, {\"bits\": 100}";
+
+        assert_eq!(redact(text), text);
+    }
+
+    #[test]
+    fn non_ascii_text_is_kept() {
+        assert_eq!(
+            redact("Grüße von 🐰 oauth:äbc123 — password=pässwörd ✓"),
+            "Grüße von 🐰 oauth:[redacted] — password=[redacted] ✓"
+        );
+    }
+}
@@ -1,6 +1,14 @@
+use std::collections::HashMap;
 use std::error::Error;
 
+mod cli;
+mod control;
 mod custom_widgets;
+mod headless;
+mod logging;
+mod migrations;
+mod overlay;
+mod secret;
 mod sequencer;
 mod settings;
 mod triggers;
@@ -10,33 +18,130 @@ use crate::settings::Settings;
 use dotenv::dotenv;
 use jsonschema::JSONSchema;
 use serde_json::json;
-use tokio::fs;
+use std::path::{Path, PathBuf};
+use tracing::{error, info};
 
 #[derive(Debug, Clone)]
 pub enum ThreadActions {
     Stop,
+    Pause, // Events wait in the queue until resumed
+    Resume,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     dotenv().ok();
 
-    let settings_content = fs::read(&"./TRS/settings.json").await?;
     let s = json!(include_str!("../schemas/settings.json"));
     // let settings_schema = JSONSchema::compile(&s).unwrap();
 
-    let settings = serde_json::from_slice::<Settings>(&settings_content)?;
+    let settings: Settings = migrations::settings::load(Path::new("./TRS/settings.json")).await?;
 
     // match settings_schema.validate(&json!(settings_content)) {
     //     Err(_x) => panic!("Settings were not valid"),
     //     Ok(_x) => {} //TODO: fix this
     // }
 
-    println!("{:?}", settings.configured_devices);
+    // Logs written before this are lost, and stop being written once the guard is dropped
+    let _log_guard = logging::init(settings.log_level.as_deref())?;
+
+    info!(
+        devices = ?settings
+            .configured_devices
+            .iter()
+            .map(|device| &device.id)
+            .collect::<Vec<_>>(),
+        "Loaded settings"
+    );
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let headless = match cli::Command::parse(&args)? {
+        Some(cli::Command::Run { record_events }) => Some(record_events),
+        Some(command) => {
+            // Listing devices and their actions needs no Twitch login. Exporting only reads the
+            // names and types of devices, so it works on a machine without the Bluetooth hardware
+            let device_set = match command {
+                cli::Command::Export { .. } => {
+                    sequencer::devices::setup_software_devices(HashMap::new(), &settings, None)?
+                }
+                _ => sequencer::devices::setup_devices(&settings, None).await?,
+            };
+            return cli::run(command, &device_set).await;
+        }
+        None => None,
+    };
+
     // TODO: These should be read from a settings file on disk
-    let device_set = sequencer::devices::setup_devices().await?;
-    let triggers = triggers::get_available_trigger_sources().await?;
+    // One login covers every trigger source and device which talks to Twitch
+    let mut scopes = triggers::required_scopes(&settings);
+    for scope in sequencer::devices::required_scopes(&settings) {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    let user_token = if scopes.is_empty() {
+        None
+    } else {
+        Some(triggers::twitch_auth::get_user_token(scopes).await?)
+    };
+
+    let variable_store = sequencer::variable_store::VariableStore::load().await?;
+    let device_set = sequencer::devices::setup_devices(&settings, user_token.as_ref()).await?;
+    let (triggers, trigger_handles) =
+        triggers::get_available_trigger_sources(&settings, user_token.as_ref()).await?;
+
+    let mut trigger_ids: Vec<String> = triggers.keys().cloned().collect();
+    trigger_ids.sort();
+    let control = control::Control::new(device_set.clone(), trigger_ids, variable_store.clone());
+
+    if let Some(api) = settings.control_api.as_ref() {
+        if api.enabled.unwrap_or(false) {
+            match u16::try_from(api.port) {
+                Ok(port) => {
+                    let server = control::server::serve(port, api.token.clone(), control.clone());
+                    tokio::spawn(async move {
+                        if let Err(e) = server.await {
+                            error!("Control API stopped: {}", e);
+                        }
+                    });
+                }
+                Err(_) => error!("The control API port {} is not a valid port", api.port),
+            }
+        }
+    }
+
+    if let Some(page) = settings.overlay.as_ref() {
+        if page.enabled.unwrap_or(false) {
+            match u16::try_from(page.port) {
+                Ok(port) => {
+                    let server = overlay::serve(
+                        port,
+                        page.html.as_ref().map(PathBuf::from),
+                        page.css.as_ref().map(PathBuf::from),
+                        control.progress().clone(),
+                    );
+                    tokio::spawn(async move {
+                        if let Err(e) = server.await {
+                            error!("Overlay stopped: {}", e);
+                        }
+                    });
+                }
+                Err(_) => error!("The overlay port {} is not a valid port", page.port),
+            }
+        }
+    }
+
+    if let Some(record_events) = headless {
+        return headless::run(device_set, triggers, variable_store, control, record_events).await;
+    }
 
-    ui::ui(device_set.clone(), triggers.clone(), settings.clone());
+    ui::ui(
+        device_set.clone(),
+        triggers.clone(),
+        settings.clone(),
+        trigger_handles,
+        variable_store,
+        control,
+    );
     Ok(())
 }
@@ -0,0 +1,268 @@
+pub mod sequence;
+pub mod settings;
+
+use core::fmt;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use tokio::fs;
+use tracing::info;
+
+pub const BACKUP_DIRECTORY: &str = "./TRS/backups"; // TODO: this path should be relative to a userdata folder
+
+/// Upgrades a document by one version, the chain index is the version it upgrades from
+pub type Migration = fn(&mut Map<String, Value>) -> Result<(), MigrationError>;
+
+#[derive(Debug, Clone)]
+pub enum MigrationError {
+    FileError(String),
+    FormatError(String),
+    NewerVersion(i64),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::FileError(msg) => write!(f, "File error: {}", msg),
+            MigrationError::FormatError(msg) => write!(f, "Format error: {}", msg),
+            MigrationError::NewerVersion(version) => write!(
+                f,
+                "Version {} was written by a newer version of the app",
+                version
+            ),
+        }
+    }
+}
+
+impl Error for MigrationError {}
+
+/// Files written before versioning have no version field and count as version 0
+pub fn version_of(document: &Value) -> i64 {
+    document
+        .get("version")
+        .and_then(|version| version.as_i64())
+        .unwrap_or(0)
+}
+
+/// Runs every migration after the document's version, returns whether anything changed
+pub fn migrate(document: &mut Value, migrations: &[Migration]) -> Result<bool, MigrationError> {
+    let current = migrations.len() as i64;
+    let version = version_of(document);
+
+    if version > current {
+        return Err(MigrationError::NewerVersion(version));
+    }
+
+    let object = document
+        .as_object_mut()
+        .ok_or_else(|| MigrationError::FormatError(String::from("Expected a JSON object")))?;
+
+    for (from, migration) in migrations.iter().enumerate().skip(version as usize) {
+        migration(object)?;
+        object.insert(String::from("version"), Value::from(from as i64 + 1));
+    }
+
+    Ok(version < current)
+}
+
+/// Reads a document, upgrading the file on disk after copying the original to the backup directory
+pub async fn load<T: DeserializeOwned>(
+    path: &Path,
+    migrations: &[Migration],
+) -> Result<T, MigrationError> {
+    load_with_backups(path, migrations, Path::new(BACKUP_DIRECTORY)).await
+}
+
+async fn load_with_backups<T: DeserializeOwned>(
+    path: &Path,
+    migrations: &[Migration],
+    backup_directory: &Path,
+) -> Result<T, MigrationError> {
+    let content = fs::read(path)
+        .await
+        .map_err(|e| MigrationError::FileError(e.to_string()))?;
+
+    let mut document: Value =
+        serde_json::from_slice(&content).map_err(|e| MigrationError::FormatError(e.to_string()))?;
+    let original_version = version_of(&document);
+
+    if migrate(&mut document, migrations)? {
+        let backup_path = backup(path, original_version, &content, backup_directory).await?;
+
+        let json = serde_json::to_string_pretty(&document)
+            .map_err(|e| MigrationError::FormatError(e.to_string()))?;
+        fs::write(path, json)
+            .await
+            .map_err(|e| MigrationError::FileError(e.to_string()))?;
+
+        info!(
+            "Upgraded {} from version {} to {}, the original is in {}",
+            path.display(),
+            original_version,
+            migrations.len(),
+            backup_path.display()
+        );
+    }
+
+    serde_json::from_value(document).map_err(|e| MigrationError::FormatError(e.to_string()))
+}
+
+async fn backup(
+    path: &Path,
+    version: i64,
+    content: &[u8],
+    backup_directory: &Path,
+) -> Result<PathBuf, MigrationError> {
+    fs::create_dir_all(backup_directory)
+        .await
+        .map_err(|e| MigrationError::FileError(e.to_string()))?;
+
+    let filename = path
+        .file_name()
+        .map(|filename| filename.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let backup_path = backup_directory.join(format!("{}.v{}", filename, version));
+
+    // An earlier backup of the same version is the real original, keep it
+    if fs::metadata(&backup_path).await.is_err() {
+        fs::write(&backup_path, content)
+            .await
+            .map_err(|e| MigrationError::FileError(e.to_string()))?;
+    }
+
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::sequencer::reaction_sequence::ReactionSequence;
+    use crate::settings::Settings;
+
+    /// A scratch directory holding a copy of the fixture, as loading rewrites it
+    struct Scratch {
+        directory: PathBuf,
+        path: PathBuf,
+    }
+
+    impl Scratch {
+        async fn with(name: &str, fixture: &[u8]) -> Self {
+            let directory = std::env::temp_dir().join(format!(
+                "trs-migrations-{}-{}",
+                name,
+                std::process::id()
+            ));
+            fs::create_dir_all(&directory).await.unwrap();
+
+            let path = directory.join(format!("{}.json", name));
+            fs::write(&path, fixture).await.unwrap();
+
+            Scratch { directory, path }
+        }
+
+        fn backups(&self) -> PathBuf {
+            self.directory.join("backups")
+        }
+
+        async fn load<T: DeserializeOwned>(
+            &self,
+            migrations: &[Migration],
+        ) -> Result<T, MigrationError> {
+            load_with_backups(&self.path, migrations, &self.backups()).await
+        }
+
+        async fn saved(&self) -> Value {
+            serde_json::from_slice(&fs::read(&self.path).await.unwrap()).unwrap()
+        }
+
+        async fn backup(&self, version: i64) -> Vec<u8> {
+            let filename = format!(
+                "{}.v{}",
+                self.path.file_name().unwrap().to_string_lossy(),
+                version
+            );
+            fs::read(self.backups().join(filename)).await.unwrap()
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.directory).ok();
+        }
+    }
+
+    const SEQUENCE_V0: &[u8] = include_bytes!("fixtures/sequence_v0.json");
+    const SEQUENCE_V1: &[u8] = include_bytes!("fixtures/sequence_v1.json");
+    const SETTINGS_V0: &[u8] = include_bytes!("fixtures/settings_v0.json");
+
+    #[tokio::test]
+    async fn sequence_v0_is_upgraded() {
+        let scratch = Scratch::with("sequence_v0", SEQUENCE_V0).await;
+
+        let sequence: ReactionSequence = scratch.load(sequence::MIGRATIONS).await.unwrap();
+
+        assert_eq!(sequence.version, sequence::VERSION);
+        assert!(sequence.enabled);
+        assert_eq!(scratch.saved().await["version"], json!(sequence::VERSION));
+        assert_eq!(scratch.saved().await["enabled"], json!(true));
+        assert_eq!(scratch.backup(0).await, SEQUENCE_V0);
+    }
+
+    #[tokio::test]
+    async fn sequence_v1_is_upgraded() {
+        let scratch = Scratch::with("sequence_v1", SEQUENCE_V1).await;
+
+        let sequence: ReactionSequence = scratch.load(sequence::MIGRATIONS).await.unwrap();
+
+        assert_eq!(sequence.version, sequence::VERSION);
+        assert!(sequence.enabled);
+        assert_eq!(scratch.saved().await["version"], json!(sequence::VERSION));
+        assert_eq!(scratch.saved().await["enabled"], json!(true));
+        assert_eq!(scratch.backup(1).await, SEQUENCE_V1);
+    }
+
+    #[tokio::test]
+    async fn settings_v0_is_upgraded() {
+        let scratch = Scratch::with("settings_v0", SETTINGS_V0).await;
+
+        let settings: Settings = scratch.load(settings::MIGRATIONS).await.unwrap();
+
+        assert_eq!(settings.version, settings::MIGRATIONS.len() as i64);
+        assert_eq!(settings.log_level.as_deref(), Some("debug"));
+        assert_eq!(
+            scratch.saved().await["version"],
+            json!(settings::MIGRATIONS.len())
+        );
+        assert_eq!(scratch.backup(0).await, SETTINGS_V0);
+    }
+
+    #[tokio::test]
+    async fn current_version_is_left_alone() {
+        let mut current: Value = serde_json::from_slice(SEQUENCE_V0).unwrap();
+        sequence::upgrade(&mut current).unwrap();
+        let content = serde_json::to_vec(&current).unwrap();
+        let scratch = Scratch::with("sequence_current", &content).await;
+
+        let _: ReactionSequence = scratch.load(sequence::MIGRATIONS).await.unwrap();
+
+        assert_eq!(fs::read(&scratch.path).await.unwrap(), content);
+        assert!(fs::metadata(scratch.backups()).await.is_err());
+    }
+
+    #[test]
+    fn newer_version_is_refused() {
+        for migrations in [sequence::MIGRATIONS, settings::MIGRATIONS] {
+            let newer = migrations.len() as i64 + 1;
+            let mut document = json!({ "version": newer });
+
+            match migrate(&mut document, migrations) {
+                Err(MigrationError::NewerVersion(version)) => assert_eq!(version, newer),
+                other => panic!("Expected NewerVersion, got {:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use super::{Migration, MigrationError};
+use crate::sequencer::reaction_sequence::ReactionSequence;
+
+/// Add new migrations to the end, never edit or reorder ones which have shipped
+pub const MIGRATIONS: &[Migration] = &[v0_add_version, v1_add_enabled];
+
+pub const VERSION: i64 = MIGRATIONS.len() as i64;
+
+pub async fn load(path: &Path) -> Result<ReactionSequence, MigrationError> {
+    super::load(path, MIGRATIONS).await
+}
+
+/// Upgrades a sequence which did not come from a file, e.g. one inside a bundle
+pub fn upgrade(document: &mut Value) -> Result<(), MigrationError> {
+    super::migrate(document, MIGRATIONS).map(|_| ())
+}
+
+/// Sequences from before versioning only gain the version field
+fn v0_add_version(_sequence: &mut Map<String, Value>) -> Result<(), MigrationError> {
+    Ok(())
+}
+
+/// Sequences were always live before they could be disabled
+fn v1_add_enabled(sequence: &mut Map<String, Value>) -> Result<(), MigrationError> {
+    sequence.entry("enabled").or_insert(Value::Bool(true));
+
+    Ok(())
+}
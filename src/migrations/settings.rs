@@ -0,0 +1,18 @@
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use super::{Migration, MigrationError};
+use crate::settings::Settings;
+
+/// Add new migrations to the end, never edit or reorder ones which have shipped
+pub const MIGRATIONS: &[Migration] = &[v0_add_version];
+
+pub async fn load(path: &Path) -> Result<Settings, MigrationError> {
+    super::load(path, MIGRATIONS).await
+}
+
+/// Settings from before versioning only gain the version field
+fn v0_add_version(_settings: &mut Map<String, Value>) -> Result<(), MigrationError> {
+    Ok(())
+}
@@ -0,0 +1,332 @@
+//! Localhost HTTP server for an OBS browser source showing what the sequences are doing.
+//!
+//! - `GET /` is the overlay page, the built in one unless a custom HTML file is configured.
+//!   `?device={id}` only shows the text and alerts of that Overlay device
+//! - `GET /overlay.css` is the configured CSS file, loaded after the page's own styles
+//! - `GET /ws` is a WebSocket of JSON messages for started and finished sequences,
+//!   with the user and reward which triggered them, and for Overlay device actions
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use futures_util::{select, FutureExt, SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket, Ws};
+use warp::{Filter, Reply};
+
+use crate::sequencer::devices::overlay::OverlayMessage;
+use crate::sequencer::progress::{Progress, ProgressSender};
+
+const BUILT_IN_PAGE: &str = include_str!("../overlay/overlay.html");
+
+/// Serves the overlay until the app exits.
+/// Custom files are read on each request so edits show when the browser source is refreshed
+pub async fn serve(
+    port: u16,
+    html: Option<PathBuf>,
+    css: Option<PathBuf>,
+    progress: ProgressSender,
+) -> Result<(), Box<dyn Error>> {
+    let page_route = warp::get()
+        .and(warp::path::end())
+        .then(move || page(html.clone()));
+
+    let css_route = warp::get()
+        .and(warp::path!("overlay.css"))
+        .then(move || stylesheet(css.clone()));
+
+    let ws_route = warp::path!("ws").and(warp::ws()).map(move |ws: Ws| {
+        let progress = progress.clone();
+        ws.on_upgrade(move |socket| feed(socket, progress))
+    });
+
+    let routes = page_route.or(css_route).or(ws_route);
+
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+    let (address, server) = warp::serve(routes).try_bind_ephemeral(address)?;
+
+    info!("Overlay listening on http://{}", address);
+    server.await;
+
+    Ok(())
+}
+
+async fn page(html: Option<PathBuf>) -> warp::reply::Response {
+    match html {
+        None => warp::reply::html(BUILT_IN_PAGE).into_response(),
+        Some(path) => match tokio::fs::read_to_string(&path).await {
+            Ok(page) => warp::reply::html(page).into_response(),
+            Err(e) => {
+                warn!("Could not read overlay page {}: {}", path.display(), e);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+    }
+}
+
+async fn stylesheet(css: Option<PathBuf>) -> warp::reply::Response {
+    let styles = match css {
+        None => String::new(),
+        Some(path) => match tokio::fs::read_to_string(&path).await {
+            Ok(styles) => styles,
+            Err(e) => {
+                warn!("Could not read overlay styles {}: {}", path.display(), e);
+                String::new()
+            }
+        },
+    };
+
+    warp::reply::with_header(styles, "content-type", "text/css").into_response()
+}
+
+/// Sends updates until the browser source goes away. What it sends is ignored
+async fn feed(socket: WebSocket, progress: ProgressSender) {
+    let (mut sender, mut reciever) = socket.split();
+    let mut updates = progress.subscribe();
+
+    // Started sequences by id, oldest first, as finishing only reports the id
+    let mut playing: HashMap<String, VecDeque<Value>> = HashMap::new();
+
+    loop {
+        let mut update = Box::pin(updates.recv().fuse());
+
+        let message = select! {
+            message = reciever.next().fuse() => match message {
+                Some(Ok(message)) if !message.is_close() => continue,
+                _ => break,
+            },
+            update = update => match update {
+                Ok(update) => match overlay_update(&update, &mut playing) {
+                    Some(message) => message,
+                    None => continue,
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+
+        if sender
+            .send(Message::text(message.to_string()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+
+    debug!("Overlay feed closed");
+}
+
+fn overlay_update(
+    progress: &Progress,
+    playing: &mut HashMap<String, VecDeque<Value>>,
+) -> Option<Value> {
+    match progress {
+        Progress::SequenceStarted(id, name, payload) => {
+            let started = json!({
+                "id": id,
+                "name": name,
+                "user": triggering_user(payload),
+                "reward": reward(payload),
+            });
+            playing
+                .entry(id.clone())
+                .or_default()
+                .push_back(started.clone());
+
+            Some(with_type("sequence_started", started))
+        }
+        Progress::SequenceFinished(id) => {
+            let started = playing.get_mut(id).and_then(VecDeque::pop_front);
+            if playing.get(id).map_or(false, VecDeque::is_empty) {
+                playing.remove(id);
+            }
+
+            let finished = started.unwrap_or_else(|| {
+                // Started before the browser source connected
+                json!({ "id": id, "name": null, "user": null, "reward": null })
+            });
+
+            Some(with_type("sequence_finished", finished))
+        }
+        Progress::Overlay(message) => Some(message_json(message)),
+        _ => None,
+    }
+}
+
+fn with_type(kind: &str, mut message: Value) -> Value {
+    message["type"] = Value::from(kind);
+    message
+}
+
+/// Who caused the event, for chat messages, redemptions, cheers, follows and raids
+fn triggering_user(payload: &Value) -> Option<&str> {
+    [
+        "user_display_name",
+        "user_name",
+        "from_broadcaster_user_name",
+    ]
+    .iter()
+    .find_map(|field| payload.get(field).and_then(Value::as_str))
+}
+
+/// The title of the redeemed reward, None for other events
+fn reward(payload: &Value) -> Option<&str> {
+    match payload.get("reward") {
+        Some(Value::String(title)) => Some(title),
+        Some(reward) => reward.get("title").and_then(Value::as_str),
+        None => None,
+    }
+}
+
+/// How overlay device actions are sent to browser sources and control API clients
+pub fn message_json(message: &OverlayMessage) -> Value {
+    match message {
+        OverlayMessage::Text {
+            device_id,
+            text,
+            duration_ms,
+        } => json!({
+            "type": "text",
+            "device": device_id,
+            "text": text,
+            "duration_ms": duration_ms,
+        }),
+        OverlayMessage::Alert {
+            device_id,
+            title,
+            message,
+            duration_ms,
+        } => json!({
+            "type": "alert",
+            "device": device_id,
+            "title": title,
+            "message": message,
+            "duration_ms": duration_ms,
+        }),
+        OverlayMessage::Clear { device_id } => json!({ "type": "clear", "device": device_id }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started(id: &str, payload: Value) -> Progress {
+        Progress::SequenceStarted(String::from(id), format!("Sequence {}", id), payload)
+    }
+
+    #[test]
+    fn finished_sequence_names_who_started_it() {
+        let mut playing = HashMap::new();
+
+        let first = overlay_update(
+            &started("raid", json!({ "user_name": "first" })),
+            &mut playing,
+        );
+        overlay_update(
+            &started("raid", json!({ "user_name": "second" })),
+            &mut playing,
+        );
+        let finished = overlay_update(
+            &Progress::SequenceFinished(String::from("raid")),
+            &mut playing,
+        );
+
+        assert_eq!(
+            first,
+            Some(json!({
+                "type": "sequence_started",
+                "id": "raid",
+                "name": "Sequence raid",
+                "user": "first",
+                "reward": null,
+            }))
+        );
+        assert_eq!(
+            finished,
+            Some(json!({
+                "type": "sequence_finished",
+                "id": "raid",
+                "name": "Sequence raid",
+                "user": "first",
+                "reward": null,
+            }))
+        );
+
+        let finished = overlay_update(
+            &Progress::SequenceFinished(String::from("raid")),
+            &mut playing,
+        );
+        assert_eq!(finished.unwrap()["user"], "second");
+        assert!(playing.is_empty());
+    }
+
+    #[test]
+    fn finished_before_connecting_has_only_an_id() {
+        let mut playing = HashMap::new();
+
+        let finished = overlay_update(
+            &Progress::SequenceFinished(String::from("raid")),
+            &mut playing,
+        );
+
+        assert_eq!(
+            finished,
+            Some(json!({
+                "type": "sequence_finished",
+                "id": "raid",
+                "name": null,
+                "user": null,
+                "reward": null,
+            }))
+        );
+    }
+
+    #[test]
+    fn other_progress_is_not_shown() {
+        let mut playing = HashMap::new();
+
+        assert_eq!(overlay_update(&Progress::EventTaken, &mut playing), None);
+        assert_eq!(
+            overlay_update(
+                &Progress::StepStarted(String::from("raid"), 0),
+                &mut playing
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn user_is_found_in_each_payload_shape() {
+        let chat = json!({ "user_display_name": "Viewer", "message": "!ears" });
+        let redemption = json!({ "user_name": "viewer", "reward": { "title": "Ears" } });
+        let cheer = json!({ "user_name": "viewer", "bits": 100, "is_anonymous": false });
+        let anonymous_cheer = json!({ "user_name": null, "bits": 100, "is_anonymous": true });
+        let raid = json!({ "from_broadcaster_user_name": "raider", "viewers": 12 });
+
+        assert_eq!(triggering_user(&chat), Some("Viewer"));
+        assert_eq!(triggering_user(&redemption), Some("viewer"));
+        assert_eq!(triggering_user(&cheer), Some("viewer"));
+        assert_eq!(triggering_user(&anonymous_cheer), None);
+        assert_eq!(triggering_user(&raid), Some("raider"));
+        assert_eq!(triggering_user(&Value::Null), None);
+    }
+
+    #[test]
+    fn reward_is_only_found_for_redemptions() {
+        let redemption =
+            json!({ "user_name": "viewer", "reward": { "title": "Ears", "cost": 100 } });
+        let simulated = json!({ "user_name": "viewer", "reward": "Ears" });
+        let cheer = json!({ "user_name": "viewer", "bits": 100 });
+
+        assert_eq!(reward(&redemption), Some("Ears"));
+        assert_eq!(reward(&simulated), Some("Ears"));
+        assert_eq!(reward(&cheer), None);
+        assert_eq!(reward(&Value::Null), None);
+    }
+}
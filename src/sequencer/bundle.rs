@@ -0,0 +1,434 @@
+use core::fmt;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use super::device::{DeviceImpler, DevicesCollection};
+use super::reaction_sequence::ReactionSequence;
+use super::sequence_files::{self, SEQUENCES_DIRECTORY};
+use crate::migrations;
+
+const BUNDLE_VERSION: i64 = 1;
+
+#[derive(Debug, Clone)]
+pub enum BundleError {
+    FileError(String),
+    FormatError(String),
+    UnsupportedVersion(i64),
+    UnmappedDevice(String),
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::FileError(msg) => write!(f, "File error: {}", msg),
+            BundleError::FormatError(msg) => write!(f, "Invalid bundle: {}", msg),
+            BundleError::UnsupportedVersion(version) => {
+                write!(f, "Bundle version {} is not supported", version)
+            }
+            BundleError::UnmappedDevice(id) => write!(f, "Device {} has not been mapped", id),
+        }
+    }
+}
+
+impl Error for BundleError {}
+
+/// Sequences shared between machines, along with what they expect of the devices they use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bundle {
+    pub version: i64,
+    pub devices: Vec<BundleDevice>,
+    pub sequences: Vec<ReactionSequence>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleDevice {
+    pub id: String,
+    pub name: String,
+    pub device_type: Option<String>, // None when the device was missing on the exporting machine
+    pub action_ids: Vec<String>,
+}
+
+impl BundleDevice {
+    /// A local device can stand in when it is the same type and has every action the sequences use
+    pub fn is_satisfied_by(&self, device: &DeviceImpler) -> bool {
+        let same_type = match &self.device_type {
+            Some(device_type) => device_type == device.get_device_type().as_str(),
+            None => true,
+        };
+
+        same_type
+            && self
+                .action_ids
+                .iter()
+                .all(|action_id| device.get_actions().contains_key(action_id))
+    }
+
+    /// Ids of the local devices which could replace this one, sorted
+    pub fn compatible_devices(&self, devices: &DevicesCollection) -> Vec<String> {
+        let mut ids: Vec<String> = devices
+            .iter()
+            .filter(|(_, device)| self.is_satisfied_by(device))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+
+        ids
+    }
+}
+
+impl Bundle {
+    pub fn export(sequences: Vec<ReactionSequence>, devices: &DevicesCollection) -> Self {
+        let mut used: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        for step in sequences
+            .iter()
+            .flat_map(|sequence| sequence.sequence.iter())
+        {
+            let action_ids = used.entry(step.device_id.clone()).or_default();
+            if !action_ids.contains(&step.device_action_id) {
+                action_ids.push(step.device_action_id.clone());
+            }
+        }
+
+        let devices = used
+            .into_iter()
+            .map(|(id, action_ids)| {
+                let device = devices.get(&id);
+                BundleDevice {
+                    name: device
+                        .map(|device| device.get_name().clone())
+                        .unwrap_or_else(|| id.clone()),
+                    device_type: device
+                        .map(|device| String::from(device.get_device_type().as_str())),
+                    id,
+                    action_ids,
+                }
+            })
+            .collect();
+
+        Bundle {
+            version: BUNDLE_VERSION,
+            devices,
+            sequences,
+        }
+    }
+
+    pub async fn read(path: &Path) -> Result<Self, BundleError> {
+        let content = fs::read(path)
+            .await
+            .map_err(|e| BundleError::FileError(e.to_string()))?;
+
+        let mut document: serde_json::Value = serde_json::from_slice(&content)
+            .map_err(|e| BundleError::FormatError(e.to_string()))?;
+
+        let version = migrations::version_of(&document);
+        if version > BUNDLE_VERSION {
+            return Err(BundleError::UnsupportedVersion(version));
+        }
+
+        // Sequences keep their own format version, bundles from older apps hold older sequences
+        for sequence in document
+            .get_mut("sequences")
+            .and_then(|sequences| sequences.as_array_mut())
+            .into_iter()
+            .flatten()
+        {
+            migrations::sequence::upgrade(sequence)
+                .map_err(|e| BundleError::FormatError(e.to_string()))?;
+        }
+
+        serde_json::from_value(document).map_err(|e| BundleError::FormatError(e.to_string()))
+    }
+
+    pub async fn write(&self, path: &Path) -> Result<(), BundleError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| BundleError::FormatError(e.to_string()))?;
+
+        fs::write(path, json)
+            .await
+            .map_err(|e| BundleError::FileError(e.to_string()))
+    }
+
+    /// Keeps devices which exist locally as they are and picks the only candidate where there is one
+    pub fn default_mapping(&self, devices: &DevicesCollection) -> HashMap<String, String> {
+        let mut mapping = HashMap::new();
+
+        for bundle_device in self.devices.iter() {
+            let same_id = devices
+                .get(&bundle_device.id)
+                .filter(|device| bundle_device.is_satisfied_by(device));
+
+            if same_id.is_some() {
+                mapping.insert(bundle_device.id.clone(), bundle_device.id.clone());
+            } else if let [only] = bundle_device.compatible_devices(devices).as_slice() {
+                mapping.insert(bundle_device.id.clone(), only.clone());
+            }
+        }
+
+        mapping
+    }
+
+    /// Rebinds the steps to local devices and gives every sequence and step a fresh id
+    pub fn import(
+        &self,
+        mapping: &HashMap<String, String>,
+    ) -> Result<Vec<ReactionSequence>, BundleError> {
+        let mut sequences = self.sequences.clone();
+
+        for sequence in sequences.iter_mut() {
+            sequence.id = new_id();
+
+            for step in sequence.sequence.iter_mut() {
+                step.id = new_id();
+                step.device_id = mapping
+                    .get(&step.device_id)
+                    .cloned()
+                    .ok_or_else(|| BundleError::UnmappedDevice(step.device_id.clone()))?;
+            }
+        }
+
+        Ok(sequences)
+    }
+}
+
+/// Writes each sequence to its own file in the sequences directory
+pub async fn write_sequences(
+    sequences: &[ReactionSequence],
+) -> Result<Vec<(ReactionSequence, PathBuf)>, BundleError> {
+    fs::create_dir_all(SEQUENCES_DIRECTORY)
+        .await
+        .map_err(|e| BundleError::FileError(e.to_string()))?;
+
+    let mut written = vec![];
+    let mut taken = vec![];
+    for sequence in sequences {
+        let filename = sequence_files::filename_for(&sequence.name, None, &taken);
+        taken.push(filename.clone());
+
+        let path = PathBuf::from(filename);
+        let json = serde_json::to_string_pretty(sequence)
+            .map_err(|e| BundleError::FormatError(e.to_string()))?;
+
+        fs::write(&path, json)
+            .await
+            .map_err(|e| BundleError::FileError(e.to_string()))?;
+
+        written.push((sequence.clone(), path));
+    }
+
+    Ok(written)
+}
+
+/// Reads every sequence file, used where the UI is not running
+pub async fn read_sequences() -> Result<Vec<ReactionSequence>, BundleError> {
+    let mut entries = fs::read_dir(SEQUENCES_DIRECTORY)
+        .await
+        .map_err(|e| BundleError::FileError(e.to_string()))?;
+
+    let mut sequences = vec![];
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if entry.path().is_dir() {
+            continue;
+        }
+
+        sequences.push(
+            migrations::sequence::load(&entry.path())
+                .await
+                .map_err(|e| BundleError::FormatError(e.to_string()))?,
+        );
+    }
+
+    Ok(sequences)
+}
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().to_hyphenated().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::sequencer::devices::{overlay::Overlay, timer};
+
+    fn sequence(id: &str, steps: &[(&str, &str, &str)]) -> ReactionSequence {
+        let steps: Vec<_> = steps
+            .iter()
+            .map(|(step_id, device_id, action_id)| {
+                json!({
+                    "id": step_id,
+                    "deviceId": device_id,
+                    "deviceActionId": action_id,
+                    "arguments": [],
+                })
+            })
+            .collect();
+
+        serde_json::from_value(json!({
+            "version": 1,
+            "id": id,
+            "name": id,
+            "enabled": true,
+            "trigger": { "triggerId": "twitch", "triggerEventId": "follow" },
+            "sequence": steps,
+        }))
+        .unwrap()
+    }
+
+    fn overlay(id: &str) -> DeviceImpler {
+        Box::new(Overlay::new(String::from(id), format!("Overlay {}", id)))
+    }
+
+    fn bundle_device(id: &str, device_type: Option<&str>, action_ids: &[&str]) -> BundleDevice {
+        BundleDevice {
+            id: String::from(id),
+            name: String::from(id),
+            device_type: device_type.map(String::from),
+            action_ids: action_ids.iter().map(|id| String::from(*id)).collect(),
+        }
+    }
+
+    fn bundle(devices: Vec<BundleDevice>, sequences: Vec<ReactionSequence>) -> Bundle {
+        Bundle {
+            version: BUNDLE_VERSION,
+            devices,
+            sequences,
+        }
+    }
+
+    #[test]
+    fn export_lists_each_used_device_and_action_once() {
+        let mut devices = timer::setup(HashMap::new());
+        devices.insert(String::from("overlay"), overlay("overlay"));
+        let sequences = vec![
+            sequence(
+                "first",
+                &[
+                    ("1", "overlay", "showText"),
+                    ("2", "timer", "delay"),
+                    ("3", "overlay", "showText"),
+                ],
+            ),
+            sequence(
+                "second",
+                &[("4", "overlay", "clear"), ("5", "gone", "spin")],
+            ),
+        ];
+
+        let exported = Bundle::export(sequences, &devices);
+
+        let listed: Vec<_> = exported
+            .devices
+            .iter()
+            .map(|device| {
+                (
+                    device.id.as_str(),
+                    device.name.as_str(),
+                    device.device_type.as_deref(),
+                    device.action_ids.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("gone", "gone", None, vec![String::from("spin")]),
+                (
+                    "overlay",
+                    "Overlay overlay",
+                    Some("OVERLAY"),
+                    vec![String::from("showText"), String::from("clear")]
+                ),
+                ("timer", "Timer", Some("TIMER"), vec![String::from("delay")]),
+            ]
+        );
+        assert_eq!(exported.sequences.len(), 2);
+    }
+
+    #[test]
+    fn default_mapping_keeps_matching_ids_and_picks_an_only_candidate() {
+        let mut devices = timer::setup(HashMap::new());
+        devices.insert(String::from("overlay"), overlay("overlay"));
+        let bundle = bundle(
+            vec![
+                bundle_device("timer", Some("TIMER"), &["delay"]),
+                bundle_device("their-overlay", Some("OVERLAY"), &["showText"]),
+                bundle_device("lights", Some("MQTT"), &["publish"]),
+            ],
+            vec![],
+        );
+
+        let mapping = bundle.default_mapping(&devices);
+
+        assert_eq!(mapping.len(), 2);
+        assert_eq!(mapping["timer"], "timer");
+        assert_eq!(mapping["their-overlay"], "overlay");
+    }
+
+    #[test]
+    fn default_mapping_leaves_a_choice_between_candidates_to_the_user() {
+        let mut devices = HashMap::new();
+        devices.insert(String::from("left"), overlay("left"));
+        devices.insert(String::from("right"), overlay("right"));
+        let bundle = bundle(
+            vec![bundle_device("their-overlay", Some("OVERLAY"), &["clear"])],
+            vec![],
+        );
+
+        assert!(bundle.default_mapping(&devices).is_empty());
+        assert_eq!(
+            bundle.devices[0].compatible_devices(&devices),
+            vec![String::from("left"), String::from("right")]
+        );
+    }
+
+    #[test]
+    fn import_gives_new_ids_and_rebinds_devices() {
+        let bundle = bundle(
+            vec![bundle_device("their-overlay", Some("OVERLAY"), &["clear"])],
+            vec![sequence(
+                "shared",
+                &[
+                    ("1", "their-overlay", "clear"),
+                    ("2", "their-overlay", "clear"),
+                ],
+            )],
+        );
+        let mapping = HashMap::from([(String::from("their-overlay"), String::from("overlay"))]);
+
+        let imported = bundle.import(&mapping).unwrap();
+
+        let sequence = &imported[0];
+        assert_ne!(sequence.id, "shared");
+        assert_eq!(sequence.name, "shared");
+        assert_ne!(sequence.sequence[0].id, "1");
+        assert_ne!(sequence.sequence[0].id, sequence.sequence[1].id);
+        assert!(sequence
+            .sequence
+            .iter()
+            .all(|step| step.device_id == "overlay"));
+
+        let again = bundle.import(&mapping).unwrap();
+        assert_ne!(again[0].id, sequence.id);
+    }
+
+    #[test]
+    fn import_refuses_unmapped_devices() {
+        let bundle = bundle(
+            vec![],
+            vec![sequence("shared", &[("1", "their-overlay", "clear")])],
+        );
+
+        assert!(matches!(
+            bundle.import(&HashMap::new()),
+            Err(BundleError::UnmappedDevice(id)) if id == "their-overlay"
+        ));
+    }
+}
@@ -1,13 +1,67 @@
 use async_trait::async_trait;
 use core::fmt;
-use serde_json;
+use serde_json::{self, json};
 use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
 
 use crate::sequencer::devices::DeviceTypes;
+use crate::sequencer::progress::ProgressSender;
+use crate::sequencer::variable_store::{VariableScope, VariableStore};
+
+#[derive(Debug, Clone)]
+pub enum ActionError {
+    InvalidArguments(String),
+    Failed(String),
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::InvalidArguments(msg) => write!(f, "Invalid arguments: {}", msg),
+            ActionError::Failed(msg) => write!(f, "Failed: {}", msg),
+        }
+    }
+}
+
+impl Error for ActionError {}
+
+pub type ActionResult = Result<(), ActionError>;
+
+/// Values shared between the steps of one run of a sequence
+pub type RunVariables = Arc<Mutex<serde_json::Map<String, serde_json::Value>>>;
+
+/// What an action knows about the event which started its sequence
+#[derive(Debug, Clone, Default)]
+pub struct ActionContext {
+    pub payload: serde_json::Value,
+    pub sequence_id: String,
+    pub variable_store: VariableStore,
+    pub variables: RunVariables,
+    pub devices: Arc<DevicesCollection>, // Lets scripts call the actions of other devices
+    pub progress: ProgressSender,        // Lets actions reach the overlay
+}
+
+impl ActionContext {
+    /// The values templates can refer to, e.g. `{{args.0}}`, `{{payload.user_name}}` or `{{global.deaths}}`
+    pub fn template_data(&self, arguments: &[serde_json::Value]) -> serde_json::Value {
+        json!({
+            "args": arguments,
+            "payload": self.payload,
+            "variables": *self.variables.lock().unwrap(),
+            "global": self.variable_store.values(&self.sequence_id, VariableScope::Global),
+            "sequence": self.variable_store.values(&self.sequence_id, VariableScope::Sequence),
+        })
+    }
+}
 
 #[async_trait]
 pub trait DeviceAction: fmt::Debug + dyn_clone::DynClone + Send + Sync {
-    async fn action(&self, arguments: Vec<serde_json::Value>) -> ();
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult;
 }
 
 dyn_clone::clone_trait_object!(DeviceAction);
@@ -15,11 +69,18 @@ dyn_clone::clone_trait_object!(DeviceAction);
 #[derive(Debug, Clone)]
 pub enum ParameterName {
     Address,
+    Host,
+    Port,
+    SerialPort,
+    BaudRate,
+    BrokerUrl,
+    Channel,
 }
 
 #[derive(Debug, Clone)]
 pub enum Parameter {
     String(ParameterName), //Name, optional default
+    Integer(ParameterName),
 }
 
 pub trait DeviceTrait: fmt::Debug + dyn_clone::DynClone + Send + Sync {
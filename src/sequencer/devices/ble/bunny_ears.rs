@@ -6,13 +6,14 @@ use crate::sequencer::devices::DeviceTypes;
 use async_trait::async_trait;
 use btleplug::api::{BDAddr, Peripheral as _, WriteType};
 use btleplug::platform::Peripheral;
+use tracing::info;
 use uuid::Uuid;
 
 const DEVICE_NAME: &str = "Bunny Ears";
 const DEVICE_ID: &str = "bunnyEars";
 const RX_CHARACTERISTIC: &str = "6E400003-B5A3-F393-E0A9-E50E24DCCA9E";
 
-use crate::sequencer::device::DeviceAction;
+use crate::sequencer::device::{ActionContext, ActionError, ActionResult, DeviceAction};
 
 use crate::sequencer::devices::ble::ble_device;
 
@@ -35,19 +36,23 @@ impl LeftEar {
 
 #[async_trait]
 impl DeviceAction for LeftEar {
-    async fn action(&self, _arguments: Vec<serde_json::Value>) {
-        self.mb.connect().await.unwrap();
-        self.mb.discover_services().await.unwrap();
+    async fn action(
+        &self,
+        _arguments: Vec<serde_json::Value>,
+        _context: &ActionContext,
+    ) -> ActionResult {
+        self.mb.connect().await.map_err(ble_error)?;
+        self.mb.discover_services().await.map_err(ble_error)?;
         let chars = self.mb.characteristics();
         let rx_char = chars
             .iter()
             .find(|c| c.uuid == Uuid::parse_str(RX_CHARACTERISTIC).unwrap())
-            .unwrap();
+            .ok_or_else(|| ActionError::Failed(String::from("No RX characteristic")))?;
         let cmd = vec![0x48, 0x31, 0x38, 0x30, 0xA];
         self.mb
             .write(rx_char, &cmd, WriteType::WithoutResponse)
             .await
-            .unwrap();
+            .map_err(ble_error)
     }
 }
 
@@ -70,17 +75,21 @@ impl RightEar {
 
 #[async_trait]
 impl DeviceAction for RightEar {
-    async fn action(&self, _arguments: Vec<serde_json::Value>) {
+    async fn action(
+        &self,
+        _arguments: Vec<serde_json::Value>,
+        _context: &ActionContext,
+    ) -> ActionResult {
         let chars = self.mb.characteristics();
         let rx_char = chars
             .iter()
             .find(|c| c.uuid == Uuid::parse_str(RX_CHARACTERISTIC).unwrap())
-            .unwrap();
+            .ok_or_else(|| ActionError::Failed(String::from("No RX characteristic")))?;
         let cmd = vec![0x48, 0x32, 0x38, 0x30, 0xA];
         self.mb
             .write(rx_char, &cmd, WriteType::WithoutResponse)
             .await
-            .unwrap();
+            .map_err(ble_error)
     }
 }
 
@@ -135,7 +144,7 @@ pub async fn setup(
     let mb = find_mb(&peripherals, String::from("EB:A0:B4:C2:82:C8"))
         .await
         .unwrap();
-    println!("found mb");
+    info!("Found the bunny ears");
     devices.insert(
         DEVICE_ID.to_string(),
         Box::new(ble_device::BleDevice::new(
@@ -148,6 +157,10 @@ pub async fn setup(
     devices
 }
 
+fn ble_error(e: btleplug::Error) -> ActionError {
+    ActionError::Failed(e.to_string())
+}
+
 async fn find_mb(peripherals: &Vec<Peripheral>, address: String) -> Option<Peripheral> {
     for p in peripherals {
         if p.properties().await.unwrap().unwrap().address
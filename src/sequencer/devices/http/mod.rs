@@ -0,0 +1,354 @@
+use crate::sequencer::device::{
+    self, ActionContext, ActionError, ActionResult, DeviceTrait, Parameter,
+};
+use crate::sequencer::devices::DeviceTypes;
+use crate::sequencer::template::{render, render_escaped, Escape};
+
+use async_trait::async_trait;
+use reqwest::Method;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct Http {
+    id: String,
+    name: String,
+    actions: HashMap<String, Box<dyn device::DeviceAction>>,
+    device_type: DeviceTypes,
+}
+
+impl Http {
+    pub fn new(id: String, name: String, requests: Vec<HttpRequest>) -> Self {
+        let mut actions: HashMap<String, Box<dyn device::DeviceAction>> = HashMap::new();
+
+        for request in requests {
+            actions.insert(request.id.clone(), Box::new(request));
+        }
+
+        Http {
+            id,
+            name,
+            actions,
+            device_type: DeviceTypes::Http,
+        }
+    }
+}
+
+impl DeviceTrait for Http {
+    fn get_actions(&self) -> &HashMap<String, Box<dyn device::DeviceAction>> {
+        &self.actions
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_device_type(&self) -> &DeviceTypes {
+        &self.device_type
+    }
+
+    fn get_device_parameters() -> Vec<Parameter> {
+        vec![]
+    }
+}
+
+/// A request declared in settings, its url, header values and body are templates
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    id: String,
+    name: String,
+    method: Method,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    timeout: Duration,
+    retries: u32,
+    success_status: Vec<u16>,
+    client: reqwest::Client,
+}
+
+impl HttpRequest {
+    pub fn new(
+        id: String,
+        name: String,
+        method: &str,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: Option<String>,
+    ) -> Result<Self, String> {
+        let method = Method::from_bytes(method.to_uppercase().as_bytes())
+            .map_err(|_| format!("{} is not an HTTP method", method))?;
+
+        Ok(HttpRequest {
+            id,
+            name,
+            method,
+            url,
+            headers,
+            body,
+            timeout: DEFAULT_TIMEOUT,
+            retries: 0,
+            success_status: vec![],
+            client: reqwest::Client::new(),
+        })
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn with_success_status(mut self, success_status: Vec<u16>) -> Self {
+        self.success_status = success_status;
+        self
+    }
+
+    /// Bodies are taken to be JSON unless a content-type header says otherwise
+    fn body_escape(&self) -> Escape {
+        let content_type = self
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+
+        match content_type {
+            Some((_, value)) if !value.to_lowercase().contains("json") => Escape::None,
+            _ => Escape::Json,
+        }
+    }
+
+    fn is_success(&self, status: reqwest::StatusCode) -> bool {
+        if self.success_status.is_empty() {
+            status.is_success()
+        } else {
+            self.success_status.contains(&status.as_u16())
+        }
+    }
+
+    async fn send(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+        body: &Option<String>,
+    ) -> ActionResult {
+        let mut request = self
+            .client
+            .request(self.method.clone(), url)
+            .timeout(self.timeout);
+
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        if let Some(body) = body {
+            request = request.body(body.clone());
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ActionError::Failed(format!("{} {}: {}", self.method, url, e)))?;
+
+        if self.is_success(response.status()) {
+            Ok(())
+        } else {
+            Err(ActionError::Failed(format!(
+                "{} {} responded {}",
+                self.method,
+                url,
+                response.status()
+            )))
+        }
+    }
+}
+
+#[async_trait]
+impl device::DeviceAction for HttpRequest {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult {
+        let data = context.template_data(&arguments);
+
+        let url = render_escaped(&self.url, &data, Escape::Url);
+        let headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), render(value, &data)))
+            .collect();
+        let body = self
+            .body
+            .as_ref()
+            .map(|body| render_escaped(body, &data, self.body_escape()));
+
+        let mut result = self.send(&url, &headers, &body).await;
+        for _ in 0..self.retries {
+            if result.is_ok() {
+                break;
+            }
+
+            time::sleep(RETRY_DELAY).await;
+            result = self.send(&url, &headers, &body).await;
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+
+    struct Received {
+        request_line: String,
+        body: String,
+    }
+
+    /// A stand in server answering one request per status in turn
+    async fn serve(statuses: Vec<u16>) -> (String, JoinHandle<Vec<Received>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let mut received = Vec::new();
+
+            for status in statuses {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await.unwrap();
+
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).await.unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).await.unwrap();
+                reader
+                    .get_mut()
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 {} Stand In\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                            status
+                        )
+                        .as_bytes(),
+                    )
+                    .await
+                    .unwrap();
+
+                received.push(Received {
+                    request_line: String::from(request_line.trim()),
+                    body: String::from_utf8(body).unwrap(),
+                });
+            }
+
+            received
+        });
+
+        (url, server)
+    }
+
+    fn request(url: String, body: Option<&str>) -> HttpRequest {
+        HttpRequest::new(
+            String::from("notify"),
+            String::from("Notify"),
+            "post",
+            url,
+            vec![],
+            body.map(String::from),
+        )
+        .unwrap()
+    }
+
+    fn context() -> ActionContext {
+        ActionContext {
+            payload: json!({ "user_name": "a\"b&c/d" }),
+            ..ActionContext::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn payload_values_are_escaped() {
+        let (url, server) = serve(vec![200]).await;
+
+        request(
+            format!("{}/users/{{{{payload.user_name}}}}", url),
+            Some(r#"{"user": "{{payload.user_name}}"}"#),
+        )
+        .action(vec![], &context())
+        .await
+        .unwrap();
+        let received = server.await.unwrap();
+
+        assert_eq!(
+            received[0].request_line,
+            "POST /users/a%22b%26c%2Fd HTTP/1.1"
+        );
+        let body: Value = serde_json::from_str(&received[0].body).unwrap();
+        assert_eq!(body, json!({ "user": "a\"b&c/d" }));
+    }
+
+    #[tokio::test]
+    async fn failed_requests_are_retried() {
+        let (url, server) = serve(vec![500, 200]).await;
+
+        let result = request(url, None)
+            .with_retries(3)
+            .action(vec![], &context())
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(server.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failure_is_reported_once_retries_run_out() {
+        let (url, server) = serve(vec![500, 503]).await;
+
+        let result = request(url, None)
+            .with_retries(1)
+            .action(vec![], &context())
+            .await;
+
+        match result {
+            Err(ActionError::Failed(message)) => assert!(message.contains("503"), "{}", message),
+            other => panic!("Expected a failure, got {:?}", other),
+        }
+        assert_eq!(server.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn success_status_replaces_the_2xx_default() {
+        let (url, server) = serve(vec![404, 200]).await;
+        let request = request(url, None).with_success_status(vec![404]);
+
+        assert!(request.action(vec![], &context()).await.is_ok());
+        assert!(request.action(vec![], &context()).await.is_err());
+        server.await.unwrap();
+    }
+}
@@ -1,25 +1,335 @@
 pub mod ble;
+pub mod http;
+pub mod mqtt;
+pub mod obs;
+pub mod osc;
+pub mod overlay;
+pub mod script;
+pub mod serial;
 pub mod timer;
+pub mod twitch_chat_sender;
+pub mod variables;
 
+use core::fmt;
 use std::collections::HashMap;
 use std::error::Error;
+use std::time::Duration;
 
-use super::device::DevicesCollection;
+use super::device::{DeviceImpler, DeviceTrait, DevicesCollection, Parameter, ParameterName};
+use crate::settings::{Settings, SettingsItemConfiguredDevices};
+use twitch_api2::twitch_oauth2::{Scope, UserToken};
 
-pub async fn setup_devices() -> Result<DevicesCollection, Box<dyn Error>> {
+pub async fn setup_devices(
+    settings: &Settings,
+    user_token: Option<&UserToken>,
+) -> Result<DevicesCollection, Box<dyn Error>> {
     let device_set: DevicesCollection = HashMap::new();
 
     let device_set = ble::util::setup_ble_devices(device_set).await?;
+    let device_set = setup_software_devices(device_set, settings, user_token)?;
+
+    Ok(device_set)
+}
+
+/// Every device except the Bluetooth ones, which need an adapter and a scan to be found
+pub fn setup_software_devices(
+    device_set: DevicesCollection,
+    settings: &Settings,
+    user_token: Option<&UserToken>,
+) -> Result<DevicesCollection, Box<dyn Error>> {
     let device_set = timer::setup(device_set);
+    let device_set = variables::setup(device_set);
+    let device_set = setup_configured_devices(device_set, settings, user_token)?;
 
     Ok(device_set)
 }
 
+/// Adds the devices from settings which need no hardware scan to be found
+fn setup_configured_devices(
+    mut devices: DevicesCollection,
+    settings: &Settings,
+    user_token: Option<&UserToken>,
+) -> Result<DevicesCollection, Box<dyn Error>> {
+    for configured_device in settings.configured_devices.iter() {
+        if let Some(device) = create_configured_device(configured_device, user_token)? {
+            devices.insert(configured_device.id.clone(), device);
+        }
+    }
+
+    Ok(devices)
+}
+
+/// The Twitch scopes the configured devices need from the user token
+pub fn required_scopes(settings: &Settings) -> Vec<Scope> {
+    let uses_chat_sender = settings.configured_devices.iter().any(|device| {
+        matches!(
+            DeviceTypes::from_string(&device.device_type),
+            DeviceTypes::TwitchChatSender
+        )
+    });
+
+    if uses_chat_sender {
+        twitch_chat_sender::required_scopes()
+    } else {
+        vec![]
+    }
+}
+
+/// Creates devices which are set up purely from their settings, hardware devices return None
+pub fn create_configured_device(
+    device: &SettingsItemConfiguredDevices,
+    user_token: Option<&UserToken>,
+) -> Result<Option<DeviceImpler>, Box<dyn Error>> {
+    match DeviceTypes::from_string(&device.device_type) {
+        DeviceTypes::Obs => {
+            validate_parameters(device, obs::Obs::get_device_parameters())?;
+
+            Ok(Some(Box::new(obs::Obs::new(
+                device.id.clone(),
+                device.name.clone(),
+                device.host.clone().unwrap(),
+                in_range(device, "port", device.port.unwrap())?,
+                device.password.clone(),
+            ))))
+        }
+        DeviceTypes::Http => {
+            let mut requests = Vec::new();
+            for configured in device.http_actions.iter().flatten() {
+                let mut request = http::HttpRequest::new(
+                    configured.id.clone(),
+                    configured.name.clone(),
+                    &configured.method,
+                    configured.url.clone(),
+                    configured
+                        .headers
+                        .iter()
+                        .flatten()
+                        .map(|header| (header.name.clone(), header.value.clone()))
+                        .collect(),
+                    configured.body.clone(),
+                )?;
+
+                if let Some(timeout_ms) = configured.timeout_ms {
+                    request = request.with_timeout(Duration::from_millis(in_range(
+                        device,
+                        "timeout_ms",
+                        timeout_ms,
+                    )?));
+                }
+                if let Some(retries) = configured.retries {
+                    request = request.with_retries(in_range(device, "retries", retries)?);
+                }
+                if let Some(success_status) = &configured.success_status {
+                    request = request.with_success_status(
+                        success_status
+                            .iter()
+                            .map(|status| {
+                                match in_range::<u16>(device, "success_status", *status) {
+                                    Ok(status) if (100..=599).contains(&status) => Ok(status),
+                                    _ => Err(format!(
+                                        "The success_status {} of {} is not an HTTP status",
+                                        status, device.name
+                                    )),
+                                }
+                            })
+                            .collect::<Result<_, _>>()?,
+                    );
+                }
+
+                requests.push(request);
+            }
+
+            Ok(Some(Box::new(http::Http::new(
+                device.id.clone(),
+                device.name.clone(),
+                requests,
+            ))))
+        }
+        DeviceTypes::Osc => {
+            validate_parameters(device, osc::Osc::get_device_parameters())?;
+
+            Ok(Some(Box::new(osc::Osc::new(
+                device.id.clone(),
+                device.name.clone(),
+                device.host.clone().unwrap(),
+                in_range(device, "port", device.port.unwrap())?,
+            ))))
+        }
+        DeviceTypes::Mqtt => {
+            validate_parameters(device, mqtt::Mqtt::get_device_parameters())?;
+
+            let options = mqtt::mqtt_options(
+                device.broker_url.as_ref().unwrap(),
+                device
+                    .client_id
+                    .clone()
+                    .unwrap_or_else(|| device.id.clone()),
+                device.username.clone(),
+                device.password.clone(),
+                device.tls_ca_file.clone(),
+            )?;
+
+            Ok(Some(Box::new(mqtt::Mqtt::new(
+                device.id.clone(),
+                device.name.clone(),
+                options,
+            ))))
+        }
+        DeviceTypes::Serial => {
+            validate_parameters(device, serial::Serial::get_device_parameters())?;
+
+            Ok(Some(Box::new(serial::Serial::new(
+                device.id.clone(),
+                device.name.clone(),
+                device.serial_port.clone().unwrap(),
+                baud_rate(device)?,
+                device.line_ending.as_deref().unwrap_or("lf"),
+            ))))
+        }
+        DeviceTypes::TwitchChatSender => {
+            validate_parameters(
+                device,
+                twitch_chat_sender::TwitchChatSender::get_device_parameters(),
+            )?;
+
+            let tls = device.use_tls.unwrap_or(true);
+            let server = twitch_chat_sender::ChatServer {
+                host: device
+                    .host
+                    .clone()
+                    .unwrap_or_else(|| String::from(twitch_chat_sender::TWITCH_IRC_HOST)),
+                port: match device.port {
+                    Some(port) => in_range(device, "port", port)?,
+                    None if tls => twitch_chat_sender::TWITCH_IRC_TLS_PORT,
+                    None => twitch_chat_sender::TWITCH_IRC_PORT,
+                },
+                tls,
+            };
+
+            Ok(Some(Box::new(twitch_chat_sender::TwitchChatSender::new(
+                device.id.clone(),
+                device.name.clone(),
+                device.channel.clone().unwrap(),
+                server,
+                user_token,
+            ))))
+        }
+        DeviceTypes::Script => Ok(Some(Box::new(script::Script::new(
+            device.id.clone(),
+            device.name.clone(),
+            script_limit(device, "script_timeout_ms", device.script_timeout_ms)?
+                .map(Duration::from_millis),
+            script_limit(
+                device,
+                "script_max_operations",
+                device.script_max_operations,
+            )?,
+        )?))),
+        DeviceTypes::Overlay => Ok(Some(Box::new(overlay::Overlay::new(
+            device.id.clone(),
+            device.name.clone(),
+        )))),
+        _ => Ok(None),
+    }
+}
+
+fn baud_rate(device: &SettingsItemConfiguredDevices) -> Result<u32, String> {
+    match in_range::<u32>(device, "baud_rate", device.baud_rate.unwrap()) {
+        Ok(0) => Err(format!("The baud_rate of {} can not be 0", device.name)),
+        baud_rate => baud_rate,
+    }
+}
+
+/// A limit of 0 would stop every script before it starts, rhai even reads 0 operations as no limit
+fn script_limit(
+    device: &SettingsItemConfiguredDevices,
+    setting: &str,
+    value: Option<i64>,
+) -> Result<Option<u64>, String> {
+    match value
+        .map(|value| in_range(device, setting, value))
+        .transpose()?
+    {
+        Some(0) => Err(format!("The {} of {} can not be 0", setting, device.name)),
+        limit => Ok(limit),
+    }
+}
+
+/// Settings hold every number as an i64, this narrows one and names it when it does not fit
+fn in_range<T: TryFrom<i64>>(
+    device: &SettingsItemConfiguredDevices,
+    setting: &str,
+    value: i64,
+) -> Result<T, String> {
+    T::try_from(value).map_err(|_| {
+        format!(
+            "The {} {} of {} is out of range",
+            setting, value, device.name
+        )
+    })
+}
+
+#[derive(Debug)]
+pub enum ValidationError {
+    MissingValue(ParameterName),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MissingValue(name) => write!(f, "Device is missing {:?}", name),
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+pub fn validate_parameters(
+    device: &SettingsItemConfiguredDevices,
+    params: Vec<Parameter>,
+) -> Result<(), ValidationError> {
+    for parameter in params {
+        let is_missing = match &parameter {
+            Parameter::String(name) => match name {
+                //TODO: This is not a scalable solution
+                ParameterName::Address => device.address.is_none(),
+                ParameterName::Host => device.host.is_none(),
+                ParameterName::SerialPort => device.serial_port.is_none(),
+                ParameterName::BrokerUrl => device.broker_url.is_none(),
+                ParameterName::Channel => device.channel.is_none(),
+                _ => true,
+            },
+            Parameter::Integer(name) => match name {
+                ParameterName::Port => device.port.is_none(),
+                ParameterName::BaudRate => device.baud_rate.is_none(),
+                _ => true,
+            },
+        };
+
+        if is_missing {
+            return Err(ValidationError::MissingValue(match parameter {
+                Parameter::String(name) | Parameter::Integer(name) => name,
+            }));
+        }
+    }
+
+    return Ok(());
+}
+
 #[derive(Debug, Clone)]
 pub enum DeviceTypes {
     BunnyEars,
     Timer,
     BleDevice,
+    Obs,
+    Http,
+    Serial,
+    Osc,
+    Mqtt,
+    TwitchChatSender,
+    Script,
+    Variables,
+    Overlay,
 }
 
 impl DeviceTypes {
@@ -28,7 +338,33 @@ impl DeviceTypes {
             "BUNNY_EARS" => DeviceTypes::BunnyEars,
             "TIMER" => DeviceTypes::Timer,
             "BLE_DEVICE" => DeviceTypes::BleDevice,
+            "OBS" => DeviceTypes::Obs,
+            "HTTP" => DeviceTypes::Http,
+            "SERIAL" => DeviceTypes::Serial,
+            "OSC" => DeviceTypes::Osc,
+            "MQTT" => DeviceTypes::Mqtt,
+            "TWITCH_CHAT_SENDER" => DeviceTypes::TwitchChatSender,
+            "SCRIPT" => DeviceTypes::Script,
+            "VARIABLES" => DeviceTypes::Variables,
+            "OVERLAY" => DeviceTypes::Overlay,
             _ => panic!("Invalid"),
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceTypes::BunnyEars => "BUNNY_EARS",
+            DeviceTypes::Timer => "TIMER",
+            DeviceTypes::BleDevice => "BLE_DEVICE",
+            DeviceTypes::Obs => "OBS",
+            DeviceTypes::Http => "HTTP",
+            DeviceTypes::Serial => "SERIAL",
+            DeviceTypes::Osc => "OSC",
+            DeviceTypes::Mqtt => "MQTT",
+            DeviceTypes::TwitchChatSender => "TWITCH_CHAT_SENDER",
+            DeviceTypes::Script => "SCRIPT",
+            DeviceTypes::Variables => "VARIABLES",
+            DeviceTypes::Overlay => "OVERLAY",
+        }
+    }
 }
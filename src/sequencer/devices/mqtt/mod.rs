@@ -0,0 +1,362 @@
+use crate::sequencer::device::{
+    self, ActionContext, ActionError, ActionResult, DeviceTrait, Parameter, ParameterName,
+};
+use crate::sequencer::devices::DeviceTypes;
+use crate::sequencer::template::render;
+
+use async_trait::async_trait;
+use reqwest::Url;
+use rumqttc::{
+    AsyncClient, ConnectionError, Event, EventLoop, MqttOptions, Packet, QoS, Transport,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tokio::time;
+use tracing::warn;
+
+const ACTION_PUBLISH_ID: &str = "publish";
+const ACTION_PUBLISH_NAME: &str = "Publish";
+
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct Mqtt {
+    id: String,
+    name: String,
+    actions: HashMap<String, Box<dyn device::DeviceAction>>,
+    device_type: DeviceTypes,
+}
+
+impl Mqtt {
+    pub fn new(id: String, name: String, options: MqttOptions) -> Self {
+        let connection = MqttConnection {
+            options,
+            client: Arc::new(Mutex::new(None)),
+        };
+
+        Mqtt {
+            id,
+            name,
+            actions: create_actions(&connection),
+            device_type: DeviceTypes::Mqtt,
+        }
+    }
+}
+
+impl DeviceTrait for Mqtt {
+    fn get_actions(&self) -> &HashMap<String, Box<dyn device::DeviceAction>> {
+        &self.actions
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_device_type(&self) -> &DeviceTypes {
+        &self.device_type
+    }
+
+    fn get_device_parameters() -> Vec<Parameter> {
+        vec![Parameter::String(ParameterName::BrokerUrl)]
+    }
+}
+
+/// Builds the connection options from a `mqtt://` or `mqtts://` broker url
+pub fn mqtt_options(
+    broker_url: &str,
+    client_id: String,
+    username: Option<String>,
+    password: Option<String>,
+    tls_ca_file: Option<String>,
+) -> Result<MqttOptions, Box<dyn Error>> {
+    let url = Url::parse(broker_url)?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("{} has no host", broker_url))?;
+
+    let tls = match url.scheme() {
+        "mqtt" | "tcp" => false,
+        "mqtts" | "ssl" => true,
+        scheme => return Err(format!("Unsupported MQTT scheme {}", scheme).into()),
+    };
+
+    let port = url.port().unwrap_or(if tls { 8883 } else { 1883 });
+
+    let mut options = MqttOptions::new(client_id, host, port);
+    options.set_keep_alive(KEEP_ALIVE);
+
+    if let Some(username) = username {
+        options.set_credentials(username, password.unwrap_or_default());
+    }
+
+    if tls {
+        options.set_transport(match tls_ca_file {
+            Some(path) => Transport::tls(std::fs::read(path)?, None, None),
+            None => Transport::tls_with_default_config(),
+        });
+    }
+
+    Ok(options)
+}
+
+/// Whether the client is connected, None until its first attempt to connect has finished
+type ConnectionState = watch::Receiver<Option<bool>>;
+
+/// One client per device, connected on first publish. The event loop keeps polling in the
+/// background, which is what reconnects it after the broker goes away
+#[derive(Debug, Clone)]
+struct MqttConnection {
+    options: MqttOptions,
+    client: Arc<Mutex<Option<(AsyncClient, ConnectionState)>>>,
+}
+
+impl MqttConnection {
+    async fn client(&self) -> (AsyncClient, ConnectionState) {
+        let mut client = self.client.lock().await;
+
+        if let Some(client) = client.as_ref() {
+            return client.clone();
+        }
+
+        let (new_client, event_loop) = AsyncClient::new(self.options.clone(), REQUEST_CAPACITY);
+        let (state_sender, state) = watch::channel(None);
+        tokio::spawn(poll_event_loop(event_loop, state_sender));
+        *client = Some((new_client.clone(), state.clone()));
+
+        (new_client, state)
+    }
+
+    /// Waits for the first attempt to connect, after that fails straight away while the broker is unreachable
+    async fn connected_client(&self) -> Result<AsyncClient, ActionError> {
+        let (client, mut state) = self.client().await;
+
+        let connected = time::timeout(CONNECT_TIMEOUT, async {
+            loop {
+                let current = *state.borrow();
+                if let Some(connected) = current {
+                    return connected;
+                }
+
+                if state.changed().await.is_err() {
+                    return false;
+                }
+            }
+        })
+        .await
+        .unwrap_or(false);
+
+        if connected {
+            Ok(client)
+        } else {
+            Err(ActionError::Failed(String::from(
+                "Not connected to the MQTT broker",
+            )))
+        }
+    }
+}
+
+async fn poll_event_loop(mut event_loop: EventLoop, state: watch::Sender<Option<bool>>) {
+    loop {
+        match event_loop.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                state.send(Some(true)).ok();
+            }
+            Ok(_) => {}
+            // Every client has been dropped
+            Err(ConnectionError::RequestsDone) => break,
+            Err(e) => {
+                state.send(Some(false)).ok();
+                warn!("MQTT connection error, reconnecting: {}", e);
+                time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Publishes `[topic, payload, qos?, retain?]`, the topic and payload are templates
+#[derive(Debug, Clone)]
+struct Publish {
+    id: String,
+    name: String,
+    connection: MqttConnection,
+}
+
+#[async_trait]
+impl device::DeviceAction for Publish {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult {
+        let data = context.template_data(&arguments);
+
+        let topic = match arguments.get(0).and_then(|topic| topic.as_str()) {
+            Some(topic) => render(topic, &data),
+            None => {
+                return Err(ActionError::InvalidArguments(String::from(
+                    "Expected a topic",
+                )))
+            }
+        };
+
+        let payload = match arguments.get(1) {
+            Some(serde_json::Value::String(payload)) => render(payload, &data),
+            Some(payload) => payload.to_string(),
+            None => String::new(),
+        };
+
+        let qos = match arguments.get(2).and_then(|qos| qos.as_u64()).unwrap_or(0) {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            2 => QoS::ExactlyOnce,
+            other => {
+                return Err(ActionError::InvalidArguments(format!(
+                    "{} is not a QoS level",
+                    other
+                )))
+            }
+        };
+
+        let retain = arguments
+            .get(3)
+            .and_then(|retain| retain.as_bool())
+            .unwrap_or(false);
+
+        // Only queued for the event loop, which would wait forever for room while it reconnects
+        self.connection
+            .connected_client()
+            .await?
+            .try_publish(topic, qos, retain, payload)
+            .map_err(|e| ActionError::Failed(format!("Could not queue the message: {}", e)))
+    }
+}
+
+fn create_actions(connection: &MqttConnection) -> HashMap<String, Box<dyn device::DeviceAction>> {
+    let mut actions: HashMap<String, Box<dyn device::DeviceAction>> = HashMap::new();
+
+    actions.insert(
+        String::from(ACTION_PUBLISH_ID),
+        Box::new(Publish {
+            id: String::from(ACTION_PUBLISH_ID),
+            name: String::from(ACTION_PUBLISH_NAME),
+            connection: connection.clone(),
+        }),
+    );
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+
+    /// Reads one MQTT control packet, returning its first byte and everything after the length
+    async fn read_packet(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+        let header = stream.read_u8().await.unwrap();
+
+        let mut length = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = stream.read_u8().await.unwrap();
+            length |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+
+        let mut body = vec![0; length];
+        stream.read_exact(&mut body).await.unwrap();
+
+        (header, body)
+    }
+
+    fn device(broker_url: &str) -> Mqtt {
+        Mqtt::new(
+            String::from("mqtt"),
+            String::from("MQTT"),
+            mqtt_options(broker_url, String::from("test"), None, None, None).unwrap(),
+        )
+    }
+
+    /// A stand in broker which accepts the connection and hands back the first publish
+    #[tokio::test]
+    async fn publish_reaches_the_broker() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("mqtt://{}", listener.local_addr().unwrap());
+
+        let broker = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let (connect, _) = read_packet(&mut stream).await;
+            assert_eq!(connect >> 4, 1);
+            stream.write_all(&[0x20, 0x02, 0x00, 0x00]).await.unwrap(); // CONNACK accepted
+
+            loop {
+                let (header, body) = read_packet(&mut stream).await;
+                if header >> 4 == 3 {
+                    return body;
+                }
+            }
+        });
+
+        let context = ActionContext {
+            payload: json!({ "user_name": "viewer" }),
+            ..ActionContext::default()
+        };
+        device(&url).get_actions()[ACTION_PUBLISH_ID]
+            .action(
+                vec![json!("ears/{{payload.user_name}}"), json!("up")],
+                &context,
+            )
+            .await
+            .unwrap();
+
+        let body = time::timeout(Duration::from_secs(5), broker)
+            .await
+            .expect("Nothing was published")
+            .unwrap();
+        let topic_length = u16::from_be_bytes([body[0], body[1]]) as usize;
+
+        assert_eq!(&body[2..2 + topic_length], b"ears/viewer");
+        assert_eq!(&body[2 + topic_length..], b"up");
+    }
+
+    #[tokio::test]
+    async fn publish_fails_while_the_broker_is_down() {
+        let unused = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("mqtt://{}", unused.local_addr().unwrap());
+        drop(unused);
+
+        let mqtt = device(&url);
+        let publish = &mqtt.get_actions()[ACTION_PUBLISH_ID];
+
+        // More than fit in the request channel, none may wait for room
+        let results = time::timeout(Duration::from_secs(10), async {
+            let mut results = vec![];
+            for _ in 0..REQUEST_CAPACITY * 2 {
+                results.push(
+                    publish
+                        .action(vec![json!("ears"), json!("up")], &ActionContext::default())
+                        .await,
+                );
+            }
+            results
+        })
+        .await
+        .expect("Publishing hung while the broker was down");
+
+        assert!(results
+            .iter()
+            .all(|result| matches!(result, Err(ActionError::Failed(_)))));
+    }
+}
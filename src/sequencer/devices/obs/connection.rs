@@ -0,0 +1,421 @@
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::time;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::warn;
+
+type ObsResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+type ObsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+const RPC_VERSION: u64 = 1;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// obs-websocket v5 opcodes
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_REQUEST: u64 = 6;
+const OP_REQUEST_RESPONSE: u64 = 7;
+
+/// One websocket shared by every action of an OBS device, opened on first use and reopened after it drops
+#[derive(Debug, Clone)]
+pub struct ObsConnection {
+    url: String,
+    password: Option<String>,
+    stream: Arc<Mutex<Option<ObsStream>>>,
+}
+
+impl ObsConnection {
+    pub fn new(host: &str, port: u16, password: Option<String>) -> Self {
+        ObsConnection {
+            url: format!("ws://{}:{}", host, port),
+            password,
+            stream: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Sends a request and waits for its response data.
+    /// It is only sent again on a new connection if it was never written to the old one,
+    /// as requests such as StartRecord must not run twice
+    pub async fn request(&self, request_type: &str, request_data: Value) -> ObsResult<Value> {
+        let mut stream = self.stream.lock().await;
+
+        if let Some(open_stream) = stream.as_mut() {
+            match time::timeout(
+                REQUEST_TIMEOUT,
+                send_request(open_stream, request_type, &request_data),
+            )
+            .await
+            {
+                Ok(Ok(response)) => return response,
+                Ok(Err(RequestError::Unsent(e))) => {
+                    warn!("OBS connection lost, reconnecting: {}", e)
+                }
+                Ok(Err(RequestError::Unanswered(e))) => {
+                    *stream = None;
+                    return Err(
+                        format!("OBS connection lost waiting for {}: {}", request_type, e).into(),
+                    );
+                }
+                Err(_) => {
+                    *stream = None;
+                    return Err(format!("OBS did not answer {} in time", request_type).into());
+                }
+            }
+        }
+
+        *stream = None;
+        let mut new_stream = time::timeout(CONNECT_TIMEOUT, self.connect())
+            .await
+            .map_err(|_| "Timed out connecting to OBS")??;
+
+        let response = time::timeout(
+            REQUEST_TIMEOUT,
+            send_request(&mut new_stream, request_type, &request_data),
+        )
+        .await
+        .map_err(|_| format!("OBS did not answer {} in time", request_type))?
+        .map_err(RequestError::into_inner)?;
+        *stream = Some(new_stream);
+
+        response
+    }
+
+    async fn connect(&self) -> ObsResult<ObsStream> {
+        let (mut ws_stream, _) = connect_async(self.url.as_str()).await?;
+
+        let hello = read_op(&mut ws_stream, OP_HELLO).await?;
+
+        let mut identify = json!({
+            "rpcVersion": RPC_VERSION,
+            "eventSubscriptions": 0,
+        });
+
+        if let Some(authentication) = hello.get("authentication") {
+            let password = self
+                .password
+                .as_ref()
+                .ok_or("OBS requires a password but none is configured")?;
+
+            identify["authentication"] = Value::String(authentication_string(
+                password,
+                authentication["salt"].as_str().unwrap_or_default(),
+                authentication["challenge"].as_str().unwrap_or_default(),
+            ));
+        }
+
+        ws_stream
+            .send(Message::text(
+                json!({ "op": OP_IDENTIFY, "d": identify }).to_string(),
+            ))
+            .await?;
+
+        read_op(&mut ws_stream, OP_IDENTIFIED).await?;
+
+        Ok(ws_stream)
+    }
+}
+
+/// base64(sha256(base64(sha256(password + salt)) + challenge))
+pub fn authentication_string(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = base64::encode(Sha256::digest(format!("{}{}", password, salt).as_bytes()));
+    base64::encode(Sha256::digest(
+        format!("{}{}", secret, challenge).as_bytes(),
+    ))
+}
+
+/// A failure of the connection, split by whether OBS could have seen the request
+enum RequestError {
+    Unsent(Box<dyn Error + Send + Sync>),
+    Unanswered(Box<dyn Error + Send + Sync>),
+}
+
+impl RequestError {
+    fn into_inner(self) -> Box<dyn Error + Send + Sync> {
+        match self {
+            RequestError::Unsent(e) | RequestError::Unanswered(e) => e,
+        }
+    }
+}
+
+/// The outer result is a failure of the connection, the inner one a failure reported by OBS
+async fn send_request(
+    ws_stream: &mut ObsStream,
+    request_type: &str,
+    request_data: &Value,
+) -> Result<ObsResult<Value>, RequestError> {
+    let request_id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+
+    ws_stream
+        .send(Message::text(
+            json!({
+                "op": OP_REQUEST,
+                "d": {
+                    "requestType": request_type,
+                    "requestId": request_id,
+                    "requestData": request_data,
+                },
+            })
+            .to_string(),
+        ))
+        .await
+        .map_err(|e| RequestError::Unsent(e.into()))?;
+
+    loop {
+        let response = read_op(ws_stream, OP_REQUEST_RESPONSE)
+            .await
+            .map_err(RequestError::Unanswered)?;
+        if response["requestId"].as_str() != Some(request_id.as_str()) {
+            continue;
+        }
+
+        let status = &response["requestStatus"];
+        if status["result"].as_bool() == Some(true) {
+            return Ok(Ok(response["responseData"].clone()));
+        }
+
+        return Ok(Err(format!(
+            "{} failed with code {}: {}",
+            request_type,
+            status["code"],
+            status["comment"].as_str().unwrap_or_default()
+        )
+        .into()));
+    }
+}
+
+/// Reads messages until one with the given opcode arrives, returning its data
+async fn read_op(ws_stream: &mut ObsStream, op: u64) -> ObsResult<Value> {
+    while let Some(msg) = ws_stream.next().await {
+        match msg? {
+            Message::Text(text) => {
+                let message: Value = serde_json::from_str(&text)?;
+                if message["op"].as_u64() == Some(op) {
+                    return Ok(message["d"].clone());
+                }
+            }
+            Message::Ping(data) => ws_stream.send(Message::Pong(data)).await?,
+            Message::Close(frame) => {
+                return Err(format!("OBS closed the connection {:?}", frame).into())
+            }
+            _ => {}
+        }
+    }
+
+    Err("OBS closed the connection".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+
+    use super::*;
+
+    type MockStream = WebSocketStream<TcpStream>;
+
+    async fn next_message(ws: &mut MockStream) -> Option<Value> {
+        while let Some(Ok(msg)) = ws.next().await {
+            if let Message::Text(text) = msg {
+                return Some(serde_json::from_str(&text).unwrap());
+            }
+        }
+
+        None
+    }
+
+    /// Plays the obs-websocket side of the handshake without authentication
+    async fn identify(listener: &TcpListener) -> MockStream {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = accept_async(stream).await.unwrap();
+
+        ws.send(Message::text(
+            json!({ "op": OP_HELLO, "d": { "rpcVersion": RPC_VERSION } }).to_string(),
+        ))
+        .await
+        .unwrap();
+        let identify = next_message(&mut ws).await.unwrap();
+        assert_eq!(identify["op"], OP_IDENTIFY);
+        ws.send(Message::text(
+            json!({ "op": OP_IDENTIFIED, "d": { "negotiatedRpcVersion": RPC_VERSION } })
+                .to_string(),
+        ))
+        .await
+        .unwrap();
+
+        ws
+    }
+
+    async fn listen() -> (TcpListener, ObsConnection) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, ObsConnection::new("127.0.0.1", port, None))
+    }
+
+    #[tokio::test]
+    async fn request_is_answered() {
+        let (listener, connection) = listen().await;
+
+        let server = tokio::spawn(async move {
+            let mut ws = identify(&listener).await;
+            let request = next_message(&mut ws).await.unwrap();
+            ws.send(Message::text(
+                json!({
+                    "op": OP_REQUEST_RESPONSE,
+                    "d": {
+                        "requestType": request["d"]["requestType"],
+                        "requestId": request["d"]["requestId"],
+                        "requestStatus": { "result": true, "code": 100 },
+                        "responseData": { "sceneItemId": 7 },
+                    },
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+
+            request
+        });
+
+        let response = connection
+            .request(
+                "GetSceneItemId",
+                json!({ "sceneName": "Live", "sourceName": "Ears" }),
+            )
+            .await
+            .unwrap();
+        let request = server.await.unwrap();
+
+        assert_eq!(response["sceneItemId"], 7);
+        assert_eq!(request["op"], OP_REQUEST);
+        assert_eq!(request["d"]["requestType"], "GetSceneItemId");
+        assert_eq!(request["d"]["requestData"]["sceneName"], "Live");
+    }
+
+    #[tokio::test]
+    async fn request_is_not_resent_after_it_was_written() {
+        let (listener, connection) = listen().await;
+
+        let server = tokio::spawn(async move {
+            let mut ws = identify(&listener).await;
+            let request = next_message(&mut ws).await.unwrap();
+            assert_eq!(request["d"]["requestType"], "StartRecord");
+            drop(ws); // Lost before answering
+
+            // A resend would come on a new connection
+            time::timeout(Duration::from_millis(500), listener.accept())
+                .await
+                .is_err()
+        });
+
+        assert!(connection.request("StartRecord", json!({})).await.is_err());
+        assert!(server.await.unwrap(), "StartRecord was sent again");
+    }
+
+    const PASSWORD: &str = "supersecretpassword";
+    const SALT: &str = "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=";
+    const CHALLENGE: &str = "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY=";
+
+    /// The example from the obs-websocket protocol documentation
+    #[test]
+    fn authentication_string_matches_the_protocol_example() {
+        assert_eq!(
+            authentication_string(PASSWORD, SALT, CHALLENGE),
+            "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4="
+        );
+    }
+
+    #[tokio::test]
+    async fn handshake_authenticates() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connection = ObsConnection::new("127.0.0.1", port, Some(String::from(PASSWORD)));
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+
+            ws.send(Message::text(
+                json!({
+                    "op": OP_HELLO,
+                    "d": {
+                        "rpcVersion": RPC_VERSION,
+                        "authentication": { "challenge": CHALLENGE, "salt": SALT },
+                    },
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+            let identify = next_message(&mut ws).await.unwrap();
+            ws.send(Message::text(
+                json!({ "op": OP_IDENTIFIED, "d": { "negotiatedRpcVersion": RPC_VERSION } })
+                    .to_string(),
+            ))
+            .await
+            .unwrap();
+
+            let request = next_message(&mut ws).await.unwrap();
+            ws.send(Message::text(
+                json!({
+                    "op": OP_REQUEST_RESPONSE,
+                    "d": {
+                        "requestType": request["d"]["requestType"],
+                        "requestId": request["d"]["requestId"],
+                        "requestStatus": { "result": true, "code": 100 },
+                    },
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+
+            identify
+        });
+
+        connection.request("StartRecord", json!({})).await.unwrap();
+        let identify = server.await.unwrap();
+
+        assert_eq!(
+            identify["d"]["authentication"],
+            "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4="
+        );
+    }
+
+    #[tokio::test]
+    async fn handshake_needs_a_password_when_asked_for_one() {
+        let (listener, connection) = listen().await;
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(stream).await.unwrap();
+            ws.send(Message::text(
+                json!({
+                    "op": OP_HELLO,
+                    "d": {
+                        "rpcVersion": RPC_VERSION,
+                        "authentication": { "challenge": CHALLENGE, "salt": SALT },
+                    },
+                })
+                .to_string(),
+            ))
+            .await
+            .unwrap();
+            next_message(&mut ws).await;
+        });
+
+        let result = connection.request("StartRecord", json!({})).await;
+
+        assert!(result.unwrap_err().to_string().contains("password"));
+    }
+}
@@ -0,0 +1,239 @@
+mod connection;
+
+use crate::sequencer::device::{self, DeviceTrait, Parameter, ParameterName};
+use crate::sequencer::devices::DeviceTypes;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use connection::ObsConnection;
+
+const ACTION_SET_CURRENT_PROGRAM_SCENE_ID: &str = "setCurrentProgramScene";
+const ACTION_SET_SCENE_ITEM_ENABLED_ID: &str = "setSceneItemEnabled";
+const ACTION_TRIGGER_MEDIA_INPUT_ACTION_ID: &str = "triggerMediaInputAction";
+const ACTION_SET_INPUT_TEXT_ID: &str = "setInputText";
+const ACTION_START_RECORD_ID: &str = "startRecord";
+const ACTION_STOP_RECORD_ID: &str = "stopRecord";
+
+#[derive(Debug, Clone)]
+pub struct Obs {
+    id: String,
+    name: String,
+    actions: HashMap<String, Box<dyn device::DeviceAction>>,
+    device_type: DeviceTypes,
+}
+
+impl Obs {
+    pub fn new(
+        id: String,
+        name: String,
+        host: String,
+        port: u16,
+        password: Option<String>,
+    ) -> Self {
+        let connection = ObsConnection::new(&host, port, password);
+
+        Obs {
+            id,
+            name,
+            actions: create_actions(&connection),
+            device_type: DeviceTypes::Obs,
+        }
+    }
+}
+
+impl DeviceTrait for Obs {
+    fn get_actions(&self) -> &HashMap<String, Box<dyn device::DeviceAction>> {
+        &self.actions
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_device_type(&self) -> &DeviceTypes {
+        &self.device_type
+    }
+
+    fn get_device_parameters() -> Vec<device::Parameter> {
+        vec![
+            Parameter::String(ParameterName::Host),
+            Parameter::Integer(ParameterName::Port),
+        ]
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ObsActionKind {
+    SetCurrentProgramScene,  // [sceneName]
+    SetSceneItemEnabled,     // [sceneName, sourceName, enabled]
+    TriggerMediaInputAction, // [inputName, play|pause|stop|restart|next|previous]
+    SetInputText,            // [inputName, text]
+    StartRecord,
+    StopRecord,
+}
+
+#[derive(Debug, Clone)]
+struct ObsAction {
+    kind: ObsActionKind,
+    connection: ObsConnection,
+}
+
+impl ObsAction {
+    pub fn new(kind: ObsActionKind, connection: &ObsConnection) -> Self {
+        ObsAction {
+            kind,
+            connection: connection.clone(),
+        }
+    }
+
+    async fn run(
+        &self,
+        arguments: &[Value],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match self.kind {
+            ObsActionKind::SetCurrentProgramScene => {
+                self.connection
+                    .request(
+                        "SetCurrentProgramScene",
+                        json!({ "sceneName": string_argument(arguments, 0)? }),
+                    )
+                    .await?;
+            }
+            ObsActionKind::SetSceneItemEnabled => {
+                let scene_name = string_argument(arguments, 0)?;
+                let scene_item = self
+                    .connection
+                    .request(
+                        "GetSceneItemId",
+                        json!({
+                            "sceneName": scene_name,
+                            "sourceName": string_argument(arguments, 1)?,
+                        }),
+                    )
+                    .await?;
+
+                self.connection
+                    .request(
+                        "SetSceneItemEnabled",
+                        json!({
+                            "sceneName": scene_name,
+                            "sceneItemId": scene_item["sceneItemId"],
+                            "sceneItemEnabled": enabled_argument(arguments, 2)?,
+                        }),
+                    )
+                    .await?;
+            }
+            ObsActionKind::TriggerMediaInputAction => {
+                let media_action = format!(
+                    "OBS_WEBSOCKET_MEDIA_INPUT_ACTION_{}",
+                    string_argument(arguments, 1)?.to_uppercase()
+                );
+
+                self.connection
+                    .request(
+                        "TriggerMediaInputAction",
+                        json!({
+                            "inputName": string_argument(arguments, 0)?,
+                            "mediaAction": media_action,
+                        }),
+                    )
+                    .await?;
+            }
+            ObsActionKind::SetInputText => {
+                self.connection
+                    .request(
+                        "SetInputSettings",
+                        json!({
+                            "inputName": string_argument(arguments, 0)?,
+                            "inputSettings": { "text": string_argument(arguments, 1)? },
+                            "overlay": true,
+                        }),
+                    )
+                    .await?;
+            }
+            ObsActionKind::StartRecord => {
+                self.connection.request("StartRecord", json!({})).await?;
+            }
+            ObsActionKind::StopRecord => {
+                self.connection.request("StopRecord", json!({})).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn string_argument(arguments: &[Value], index: usize) -> Result<String, String> {
+    match arguments.get(index) {
+        Some(Value::String(value)) => Ok(value.clone()),
+        Some(value) => Ok(value.to_string()),
+        None => Err(format!("Missing argument {}", index)),
+    }
+}
+
+/// Shown when missing, anything other than true or false is refused rather than guessed at
+fn enabled_argument(arguments: &[Value], index: usize) -> Result<bool, String> {
+    match arguments.get(index) {
+        None | Some(Value::Null) => Ok(true),
+        Some(Value::Bool(enabled)) => Ok(*enabled),
+        Some(value) => Err(format!("{} is not true or false", value)),
+    }
+}
+
+#[async_trait]
+impl device::DeviceAction for ObsAction {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        _context: &device::ActionContext,
+    ) -> device::ActionResult {
+        self.run(&arguments)
+            .await
+            .map_err(|e| device::ActionError::Failed(e.to_string()))
+    }
+}
+
+fn create_actions(connection: &ObsConnection) -> HashMap<String, Box<dyn device::DeviceAction>> {
+    let mut actions: HashMap<String, Box<dyn device::DeviceAction>> = HashMap::new();
+
+    let definitions = [
+        (
+            ACTION_SET_CURRENT_PROGRAM_SCENE_ID,
+            ObsActionKind::SetCurrentProgramScene,
+        ),
+        (
+            ACTION_SET_SCENE_ITEM_ENABLED_ID,
+            ObsActionKind::SetSceneItemEnabled,
+        ),
+        (
+            ACTION_TRIGGER_MEDIA_INPUT_ACTION_ID,
+            ObsActionKind::TriggerMediaInputAction,
+        ),
+        (ACTION_SET_INPUT_TEXT_ID, ObsActionKind::SetInputText),
+        (ACTION_START_RECORD_ID, ObsActionKind::StartRecord),
+        (ACTION_STOP_RECORD_ID, ObsActionKind::StopRecord),
+    ];
+
+    for (id, kind) in definitions {
+        actions.insert(String::from(id), Box::new(ObsAction::new(kind, connection)));
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enabled_argument_is_a_bool() {
+        assert_eq!(enabled_argument(&[], 2), Ok(true));
+        assert_eq!(
+            enabled_argument(&[json!("a"), json!("b"), json!(false)], 2),
+            Ok(false)
+        );
+        assert!(enabled_argument(&[json!("a"), json!("b"), json!("false")], 2).is_err());
+    }
+}
@@ -0,0 +1,382 @@
+use crate::sequencer::device::{
+    self, ActionContext, ActionError, ActionResult, DeviceTrait, Parameter, ParameterName,
+};
+use crate::sequencer::devices::DeviceTypes;
+use crate::sequencer::template::render;
+
+use async_trait::async_trait;
+use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::net::{self, UdpSocket};
+use tokio::sync::Mutex;
+
+const ACTION_SEND_MESSAGE_ID: &str = "sendMessage";
+const ACTION_SEND_MESSAGE_NAME: &str = "Send Message";
+
+const ACTION_SEND_BUNDLE_ID: &str = "sendBundle";
+const ACTION_SEND_BUNDLE_NAME: &str = "Send Bundle";
+
+// The timetag OSC reserves for "as soon as it arrives"
+const IMMEDIATELY: OscTime = OscTime {
+    seconds: 0,
+    fractional: 1,
+};
+
+#[derive(Debug, Clone)]
+pub struct Osc {
+    id: String,
+    name: String,
+    actions: HashMap<String, Box<dyn device::DeviceAction>>,
+    device_type: DeviceTypes,
+}
+
+impl Osc {
+    pub fn new(id: String, name: String, host: String, port: u16) -> Self {
+        let target = OscTarget {
+            host,
+            port,
+            socket: Arc::new(Mutex::new(None)),
+        };
+
+        Osc {
+            id,
+            name,
+            actions: create_actions(&target),
+            device_type: DeviceTypes::Osc,
+        }
+    }
+}
+
+impl DeviceTrait for Osc {
+    fn get_actions(&self) -> &HashMap<String, Box<dyn device::DeviceAction>> {
+        &self.actions
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_device_type(&self) -> &DeviceTypes {
+        &self.device_type
+    }
+
+    fn get_device_parameters() -> Vec<Parameter> {
+        vec![
+            Parameter::String(ParameterName::Host),
+            Parameter::Integer(ParameterName::Port),
+        ]
+    }
+}
+
+/// The host is resolved and the UDP socket bound on first send,
+/// both are shared by every action of the device
+#[derive(Debug, Clone)]
+struct OscTarget {
+    host: String,
+    port: u16,
+    socket: Arc<Mutex<Option<(UdpSocket, SocketAddr)>>>,
+}
+
+impl OscTarget {
+    async fn send(&self, packet: &OscPacket) -> ActionResult {
+        let buffer = rosc::encoder::encode(packet)
+            .map_err(|e| ActionError::InvalidArguments(format!("Could not encode OSC: {}", e)))?;
+
+        let mut socket = self.socket.lock().await;
+        if socket.is_none() {
+            *socket = Some(self.bind().await?);
+        }
+
+        let (socket, address) = socket.as_ref().unwrap();
+        socket
+            .send_to(&buffer, address)
+            .await
+            .map_err(|e| ActionError::Failed(format!("Could not send to {}: {}", address, e)))?;
+
+        Ok(())
+    }
+
+    /// Binds in the address family of the target, an IPv4 socket can't send to an IPv6 host
+    async fn bind(&self) -> Result<(UdpSocket, SocketAddr), ActionError> {
+        let address = net::lookup_host((self.host.as_str(), self.port))
+            .await
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .ok_or_else(|| ActionError::Failed(format!("Could not resolve {}", self.host)))?;
+
+        let local = if address.is_ipv6() {
+            SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0))
+        } else {
+            SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))
+        };
+        let socket = UdpSocket::bind(local)
+            .await
+            .map_err(|e| ActionError::Failed(format!("Could not bind OSC socket: {}", e)))?;
+
+        Ok((socket, address))
+    }
+}
+
+/// Builds a message from `[address, ...values]`, integers become int, other numbers float,
+/// strings are rendered as templates
+fn to_message(parts: &[Value], data: &Value) -> Result<OscMessage, ActionError> {
+    let address = match parts.get(0) {
+        Some(Value::String(address)) if address.starts_with('/') => address.clone(),
+        _ => {
+            return Err(ActionError::InvalidArguments(String::from(
+                "Expected an OSC address starting with /",
+            )))
+        }
+    };
+
+    let args = parts[1..]
+        .iter()
+        .map(|value| to_osc_type(value, data))
+        .collect::<Result<Vec<OscType>, ActionError>>()?;
+
+    Ok(OscMessage {
+        addr: address,
+        args,
+    })
+}
+
+fn to_osc_type(value: &Value, data: &Value) -> Result<OscType, ActionError> {
+    match value {
+        Value::Bool(value) => Ok(OscType::Bool(*value)),
+        Value::String(value) => Ok(OscType::String(render(value, data))),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) if integer >= i32::MIN as i64 && integer <= i32::MAX as i64 => {
+                Ok(OscType::Int(integer as i32))
+            }
+            _ => Ok(OscType::Float(number.as_f64().unwrap_or_default() as f32)),
+        },
+        other => Err(ActionError::InvalidArguments(format!(
+            "{} can't be sent over OSC",
+            other
+        ))),
+    }
+}
+
+/// Sends `[address, ...values]` as a single message
+#[derive(Debug, Clone)]
+struct SendMessage {
+    id: String,
+    name: String,
+    target: OscTarget,
+}
+
+#[async_trait]
+impl device::DeviceAction for SendMessage {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult {
+        let message = to_message(&arguments, &context.template_data(&arguments))?;
+
+        self.target.send(&OscPacket::Message(message)).await
+    }
+}
+
+/// Sends `[delay_ms, [address, ...values], ...]` as one bundle, the receiver applies every
+/// message together once the delay has passed, a delay of 0 applies them immediately
+#[derive(Debug, Clone)]
+struct SendBundle {
+    id: String,
+    name: String,
+    target: OscTarget,
+}
+
+#[async_trait]
+impl device::DeviceAction for SendBundle {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult {
+        let delay_ms = arguments
+            .get(0)
+            .and_then(|delay| delay.as_u64())
+            .ok_or_else(|| {
+                ActionError::InvalidArguments(String::from("Expected a delay in milliseconds"))
+            })?;
+
+        let timetag = if delay_ms == 0 {
+            IMMEDIATELY
+        } else {
+            OscTime::try_from(SystemTime::now() + Duration::from_millis(delay_ms))
+                .map_err(|e| ActionError::InvalidArguments(format!("Invalid timetag: {:?}", e)))?
+        };
+
+        let data = context.template_data(&arguments);
+        let content = arguments[1..]
+            .iter()
+            .map(|message| match message {
+                Value::Array(parts) => to_message(parts, &data).map(OscPacket::Message),
+                other => Err(ActionError::InvalidArguments(format!(
+                    "Expected [address, ...values] but got {}",
+                    other
+                ))),
+            })
+            .collect::<Result<Vec<OscPacket>, ActionError>>()?;
+
+        self.target
+            .send(&OscPacket::Bundle(OscBundle { timetag, content }))
+            .await
+    }
+}
+
+fn create_actions(target: &OscTarget) -> HashMap<String, Box<dyn device::DeviceAction>> {
+    let mut actions: HashMap<String, Box<dyn device::DeviceAction>> = HashMap::new();
+
+    actions.insert(
+        String::from(ACTION_SEND_MESSAGE_ID),
+        Box::new(SendMessage {
+            id: String::from(ACTION_SEND_MESSAGE_ID),
+            name: String::from(ACTION_SEND_MESSAGE_NAME),
+            target: target.clone(),
+        }),
+    );
+
+    actions.insert(
+        String::from(ACTION_SEND_BUNDLE_ID),
+        Box::new(SendBundle {
+            id: String::from(ACTION_SEND_BUNDLE_ID),
+            name: String::from(ACTION_SEND_BUNDLE_NAME),
+            target: target.clone(),
+        }),
+    );
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Runs an action against a local socket and decodes the packet which arrives
+    async fn received(action_id: &str, arguments: Vec<Value>) -> OscPacket {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        received_by(receiver, "127.0.0.1", action_id, arguments).await
+    }
+
+    async fn received_by(
+        receiver: UdpSocket,
+        host: &str,
+        action_id: &str,
+        arguments: Vec<Value>,
+    ) -> OscPacket {
+        let port = receiver.local_addr().unwrap().port();
+
+        let osc = Osc::new(
+            String::from("osc"),
+            String::from("OSC"),
+            String::from(host),
+            port,
+        );
+        let context = ActionContext {
+            payload: json!({ "user_name": "viewer" }),
+            ..ActionContext::default()
+        };
+        osc.get_actions()[action_id]
+            .action(arguments, &context)
+            .await
+            .unwrap();
+
+        let mut buffer = [0u8; rosc::decoder::MTU];
+        let size = tokio::time::timeout(Duration::from_secs(5), receiver.recv(&mut buffer))
+            .await
+            .expect("Nothing was sent")
+            .unwrap();
+
+        rosc::decoder::decode_udp(&buffer[..size]).unwrap().1
+    }
+
+    #[tokio::test]
+    async fn message_is_encoded() {
+        let packet = received(
+            ACTION_SEND_MESSAGE_ID,
+            vec![
+                json!("/ears/left"),
+                json!(1),
+                json!(0.5),
+                json!("{{payload.user_name}}"),
+                json!(true),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            packet,
+            OscPacket::Message(OscMessage {
+                addr: String::from("/ears/left"),
+                args: vec![
+                    OscType::Int(1),
+                    OscType::Float(0.5),
+                    OscType::String(String::from("viewer")),
+                    OscType::Bool(true),
+                ],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn bundle_is_encoded() {
+        let packet = received(
+            ACTION_SEND_BUNDLE_ID,
+            vec![
+                json!(0),
+                json!(["/ears/left", 1]),
+                json!(["/ears/right", 2]),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            packet,
+            OscPacket::Bundle(OscBundle {
+                timetag: IMMEDIATELY,
+                content: vec![
+                    OscPacket::Message(OscMessage {
+                        addr: String::from("/ears/left"),
+                        args: vec![OscType::Int(1)],
+                    }),
+                    OscPacket::Message(OscMessage {
+                        addr: String::from("/ears/right"),
+                        args: vec![OscType::Int(2)],
+                    }),
+                ],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn message_reaches_an_ipv6_host() {
+        let receiver = match UdpSocket::bind("[::1]:0").await {
+            Ok(receiver) => receiver,
+            Err(_) => return, // No IPv6 loopback on this machine
+        };
+
+        let packet = received_by(
+            receiver,
+            "::1",
+            ACTION_SEND_MESSAGE_ID,
+            vec![json!("/ears")],
+        )
+        .await;
+
+        assert_eq!(
+            packet,
+            OscPacket::Message(OscMessage {
+                addr: String::from("/ears"),
+                args: vec![],
+            })
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use crate::sequencer::device::{
+    self, ActionContext, ActionError, ActionResult, DeviceTrait, Parameter,
+};
+use crate::sequencer::devices::DeviceTypes;
+use crate::sequencer::progress::Progress;
+use crate::sequencer::template::render;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const ACTION_SHOW_TEXT_ID: &str = "showText";
+const ACTION_SHOW_TEXT_NAME: &str = "Show Text";
+
+const ACTION_SHOW_ALERT_ID: &str = "showAlert";
+const ACTION_SHOW_ALERT_NAME: &str = "Show Alert";
+
+const ACTION_CLEAR_ID: &str = "clear";
+const ACTION_CLEAR_NAME: &str = "Clear";
+
+const DEFAULT_ALERT_MS: u64 = 5000;
+
+/// What an overlay device asks the browser source to show.
+/// Each carries the device id so a page can show only one device's messages
+#[derive(Debug, Clone)]
+pub enum OverlayMessage {
+    Text {
+        device_id: String,
+        text: String,
+        duration_ms: Option<u64>, // Shown until replaced or cleared when missing
+    },
+    Alert {
+        device_id: String,
+        title: String,
+        message: String,
+        duration_ms: u64,
+    },
+    Clear {
+        device_id: String,
+    },
+}
+
+/// Shows text and alerts on the browser source served by the overlay server
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    id: String,
+    name: String,
+    actions: HashMap<String, Box<dyn device::DeviceAction>>,
+    device_type: DeviceTypes,
+}
+
+impl Overlay {
+    pub fn new(id: String, name: String) -> Self {
+        Overlay {
+            actions: create_actions(&id),
+            id,
+            name,
+            device_type: DeviceTypes::Overlay,
+        }
+    }
+}
+
+impl DeviceTrait for Overlay {
+    fn get_actions(&self) -> &HashMap<String, Box<dyn device::DeviceAction>> {
+        &self.actions
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_device_type(&self) -> &DeviceTypes {
+        &self.device_type
+    }
+
+    fn get_device_parameters() -> Vec<Parameter> {
+        vec![]
+    }
+}
+
+#[derive(Debug, Clone)]
+enum OverlayActionKind {
+    ShowText,  // [text, duration_ms?]
+    ShowAlert, // [title, message?, duration_ms?]
+    Clear,     // []
+}
+
+/// Text arguments are rendered as templates, e.g. "Thanks {{payload.user_name}}!"
+#[derive(Debug, Clone)]
+struct OverlayAction {
+    id: String,
+    name: String,
+    device_id: String,
+    kind: OverlayActionKind,
+}
+
+fn text_argument(arguments: &[Value], index: usize, data: &Value) -> Option<String> {
+    arguments
+        .get(index)
+        .and_then(|text| text.as_str())
+        .map(|text| render(text, data))
+}
+
+fn duration_argument(arguments: &[Value], index: usize) -> Result<Option<u64>, ActionError> {
+    match arguments.get(index) {
+        None | Some(Value::Null) => Ok(None),
+        Some(duration) => duration.as_u64().map(Some).ok_or_else(|| {
+            ActionError::InvalidArguments(format!("{} is not a duration in milliseconds", duration))
+        }),
+    }
+}
+
+#[async_trait]
+impl device::DeviceAction for OverlayAction {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult {
+        let data = context.template_data(&arguments);
+        let device_id = self.device_id.clone();
+
+        let message = match self.kind {
+            OverlayActionKind::ShowText => OverlayMessage::Text {
+                device_id,
+                text: text_argument(&arguments, 0, &data).ok_or_else(|| {
+                    ActionError::InvalidArguments(String::from("Expected text to show"))
+                })?,
+                duration_ms: duration_argument(&arguments, 1)?,
+            },
+            OverlayActionKind::ShowAlert => OverlayMessage::Alert {
+                device_id,
+                title: text_argument(&arguments, 0, &data).ok_or_else(|| {
+                    ActionError::InvalidArguments(String::from("Expected an alert title"))
+                })?,
+                message: text_argument(&arguments, 1, &data).unwrap_or_default(),
+                duration_ms: duration_argument(&arguments, 2)?.unwrap_or(DEFAULT_ALERT_MS),
+            },
+            OverlayActionKind::Clear => OverlayMessage::Clear { device_id },
+        };
+
+        // Nothing is shown when the overlay server is off or no browser source is connected
+        context.progress.publish(Progress::Overlay(message));
+
+        Ok(())
+    }
+}
+
+fn create_actions(device_id: &str) -> HashMap<String, Box<dyn device::DeviceAction>> {
+    let mut actions: HashMap<String, Box<dyn device::DeviceAction>> = HashMap::new();
+
+    let definitions = [
+        (
+            ACTION_SHOW_TEXT_ID,
+            ACTION_SHOW_TEXT_NAME,
+            OverlayActionKind::ShowText,
+        ),
+        (
+            ACTION_SHOW_ALERT_ID,
+            ACTION_SHOW_ALERT_NAME,
+            OverlayActionKind::ShowAlert,
+        ),
+        (ACTION_CLEAR_ID, ACTION_CLEAR_NAME, OverlayActionKind::Clear),
+    ];
+
+    for (id, name, kind) in definitions {
+        actions.insert(
+            String::from(id),
+            Box::new(OverlayAction {
+                id: String::from(id),
+                name: String::from(name),
+                device_id: String::from(device_id),
+                kind,
+            }),
+        );
+    }
+
+    actions
+}
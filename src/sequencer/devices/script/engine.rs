@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope};
+use serde_json::{Map, Value};
+use tracing::info;
+
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct ScriptLimits {
+    pub timeout: Duration,
+    pub max_operations: u64,
+}
+
+/// A device action the script asked for, run in order once the script has finished
+#[derive(Debug, Clone)]
+pub struct ScriptCall {
+    pub device_id: String,
+    pub action_id: String,
+    pub arguments: Vec<Value>,
+}
+
+/// The maps a script can read and change
+#[derive(Debug, Clone)]
+pub struct ScriptVariables {
+    pub run: Map<String, Value>,
+    pub global: Map<String, Value>,
+    pub sequence: Map<String, Value>,
+}
+
+#[derive(Debug)]
+pub struct ScriptOutcome {
+    pub variables: ScriptVariables,
+    pub calls: Vec<ScriptCall>,
+}
+
+/// Runs a script with `args`, `payload`, `variables`, `global` and `sequence` in scope. Changes
+/// the script makes to the variable maps are kept, `call(device, action, [args])` queues an action
+pub fn run(
+    source: &str,
+    arguments: Vec<Value>,
+    payload: Value,
+    variables: ScriptVariables,
+    limits: &ScriptLimits,
+) -> Result<ScriptOutcome, Box<EvalAltResult>> {
+    let calls: Arc<Mutex<Vec<ScriptCall>>> = Arc::new(Mutex::new(vec![]));
+    let engine = create_engine(limits, &calls);
+
+    let mut scope = Scope::new();
+    scope.push_dynamic("args", to_dynamic(&arguments)?);
+    scope.push_dynamic("payload", to_dynamic(&payload)?);
+    scope.push_dynamic("variables", to_dynamic(&variables.run)?);
+    scope.push_dynamic("global", to_dynamic(&variables.global)?);
+    scope.push_dynamic("sequence", to_dynamic(&variables.sequence)?);
+
+    engine.run_with_scope(&mut scope, source)?;
+
+    let variables = ScriptVariables {
+        run: read_map(&scope, "variables", variables.run)?,
+        global: read_map(&scope, "global", variables.global)?,
+        sequence: read_map(&scope, "sequence", variables.sequence)?,
+    };
+
+    let calls = calls.lock().unwrap().drain(..).collect();
+
+    Ok(ScriptOutcome { variables, calls })
+}
+
+fn read_map(
+    scope: &Scope,
+    name: &str,
+    fallback: Map<String, Value>,
+) -> Result<Map<String, Value>, Box<EvalAltResult>> {
+    match scope.get_value::<Dynamic>(name) {
+        Some(values) => from_dynamic(&values),
+        None => Ok(fallback),
+    }
+}
+
+fn create_engine(limits: &ScriptLimits, calls: &Arc<Mutex<Vec<ScriptCall>>>) -> Engine {
+    let mut engine = Engine::new();
+    // Scripts may not import other files
+    engine.set_module_resolver(DummyModuleResolver::new());
+
+    engine.set_max_operations(limits.max_operations);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(MAX_COLLECTION_SIZE);
+
+    let started = Instant::now();
+    let timeout = limits.timeout;
+    engine.on_progress(move |_| {
+        if started.elapsed() > timeout {
+            Some(Dynamic::from(format!(
+                "Script ran for longer than {}ms",
+                timeout.as_millis()
+            )))
+        } else {
+            None
+        }
+    });
+
+    engine.on_print(|text| info!(target: "script", "{}", text));
+
+    let with_arguments = calls.clone();
+    engine.register_fn(
+        "call",
+        move |device_id: &str,
+              action_id: &str,
+              arguments: Array|
+              -> Result<(), Box<EvalAltResult>> {
+            with_arguments.lock().unwrap().push(ScriptCall {
+                device_id: String::from(device_id),
+                action_id: String::from(action_id),
+                arguments: from_dynamic(&Dynamic::from_array(arguments))?,
+            });
+            Ok(())
+        },
+    );
+
+    let without_arguments = calls.clone();
+    engine.register_fn("call", move |device_id: &str, action_id: &str| {
+        without_arguments.lock().unwrap().push(ScriptCall {
+            device_id: String::from(device_id),
+            action_id: String::from(action_id),
+            arguments: vec![],
+        });
+    });
+
+    engine
+}
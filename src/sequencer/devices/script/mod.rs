@@ -0,0 +1,333 @@
+mod engine;
+
+use crate::sequencer::device::{
+    self, ActionContext, ActionError, ActionResult, DeviceTrait, Parameter,
+};
+use crate::sequencer::devices::DeviceTypes;
+use crate::sequencer::variable_store::VariableScope;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use engine::{ScriptCall, ScriptLimits, ScriptVariables};
+
+pub const SCRIPTS_DIRECTORY: &str = "./TRS/scripts"; // TODO: this path should be relative to a userdata folder
+const SCRIPT_EXTENSION: &str = "rhai";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_OPERATIONS: u64 = 100_000;
+
+/// Each `.rhai` file in the scripts directory is one action, named after the file
+#[derive(Debug, Clone)]
+pub struct Script {
+    id: String,
+    name: String,
+    actions: HashMap<String, Box<dyn device::DeviceAction>>,
+    device_type: DeviceTypes,
+}
+
+impl Script {
+    pub fn new(
+        id: String,
+        name: String,
+        timeout: Option<Duration>,
+        max_operations: Option<u64>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let limits = ScriptLimits {
+            timeout: timeout.unwrap_or(DEFAULT_TIMEOUT),
+            max_operations: max_operations.unwrap_or(DEFAULT_MAX_OPERATIONS),
+        };
+
+        Ok(Script {
+            id,
+            name,
+            actions: create_actions(Path::new(SCRIPTS_DIRECTORY), &limits)?,
+            device_type: DeviceTypes::Script,
+        })
+    }
+}
+
+impl DeviceTrait for Script {
+    fn get_actions(&self) -> &HashMap<String, Box<dyn device::DeviceAction>> {
+        &self.actions
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_device_type(&self) -> &DeviceTypes {
+        &self.device_type
+    }
+
+    fn get_device_parameters() -> Vec<Parameter> {
+        vec![]
+    }
+}
+
+/// The script file is read on every run so edits apply without a restart
+#[derive(Debug, Clone)]
+struct ScriptAction {
+    id: String,
+    name: String,
+    path: PathBuf,
+    limits: ScriptLimits,
+}
+
+#[async_trait]
+impl device::DeviceAction for ScriptAction {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult {
+        let source = tokio::fs::read_to_string(&self.path)
+            .await
+            .map_err(|e| ActionError::Failed(format!("Could not read {}: {}", self.name, e)))?;
+
+        let store = &context.variable_store;
+        let global = store.values(&context.sequence_id, VariableScope::Global);
+        let sequence = store.values(&context.sequence_id, VariableScope::Sequence);
+        let variables = ScriptVariables {
+            run: context.variables.lock().unwrap().clone(),
+            global: global.clone(),
+            sequence: sequence.clone(),
+        };
+        let payload = context.payload.clone();
+        let limits = self.limits.clone();
+
+        // Scripts are CPU bound so run away from the async workers
+        let outcome = tokio::task::spawn_blocking(move || {
+            engine::run(&source, arguments, payload, variables, &limits)
+        })
+        .await
+        .map_err(|e| ActionError::Failed(e.to_string()))?
+        .map_err(|e| ActionError::Failed(format!("{}: {}", self.name, e)))?;
+
+        *context.variables.lock().unwrap() = outcome.variables.run;
+        store.apply_changes(
+            &context.sequence_id,
+            VariableScope::Global,
+            &global,
+            outcome.variables.global,
+        );
+        store.apply_changes(
+            &context.sequence_id,
+            VariableScope::Sequence,
+            &sequence,
+            outcome.variables.sequence,
+        );
+
+        for call in outcome.calls {
+            run_call(&call, context).await?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn run_call(call: &ScriptCall, context: &ActionContext) -> ActionResult {
+    let does_not_exist = || {
+        ActionError::Failed(format!(
+            "Script called {} on {} which does not exist",
+            call.action_id, call.device_id
+        ))
+    };
+
+    let device = context
+        .devices
+        .get(&call.device_id)
+        .ok_or_else(does_not_exist)?;
+
+    // Scripts calling scripts could recurse without end
+    if let DeviceTypes::Script = device.get_device_type() {
+        return Err(ActionError::Failed(format!(
+            "Script called {} on {}, scripts can not call other scripts",
+            call.action_id, call.device_id
+        )));
+    }
+
+    let action = device
+        .get_actions()
+        .get(&call.action_id)
+        .ok_or_else(does_not_exist)?;
+
+    action.action(call.arguments.clone(), context).await
+}
+
+fn create_actions(
+    directory: &Path,
+    limits: &ScriptLimits,
+) -> Result<HashMap<String, Box<dyn device::DeviceAction>>, Box<dyn Error>> {
+    let mut actions: HashMap<String, Box<dyn device::DeviceAction>> = HashMap::new();
+
+    if !directory.exists() {
+        std::fs::create_dir_all(directory)?;
+    }
+
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some(SCRIPT_EXTENSION) {
+            continue;
+        }
+
+        let id = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(stem) => String::from(stem),
+            None => continue,
+        };
+
+        actions.insert(
+            id.clone(),
+            Box::new(ScriptAction {
+                name: id.clone(),
+                id,
+                path,
+                limits: limits.clone(),
+            }),
+        );
+    }
+
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::sequencer::device::DeviceAction;
+    use crate::sequencer::variable_store::VariableStore;
+
+    const SEQUENCE_ID: &str = "sequence";
+
+    fn script_action(name: &str, source: &str, limits: ScriptLimits) -> ScriptAction {
+        let directory =
+            std::env::temp_dir().join(format!("trs-script-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(format!("{}.{}", name, SCRIPT_EXTENSION));
+        std::fs::write(&path, source).unwrap();
+
+        ScriptAction {
+            id: String::from(name),
+            name: String::from(name),
+            path,
+            limits,
+        }
+    }
+
+    fn default_limits() -> ScriptLimits {
+        ScriptLimits {
+            timeout: DEFAULT_TIMEOUT,
+            max_operations: DEFAULT_MAX_OPERATIONS,
+        }
+    }
+
+    fn context() -> ActionContext {
+        ActionContext {
+            sequence_id: String::from(SEQUENCE_ID),
+            ..ActionContext::default()
+        }
+    }
+
+    async fn failure(action: &ScriptAction, context: &ActionContext) -> String {
+        match action.action(vec![], context).await {
+            Err(ActionError::Failed(message)) => message,
+            result => panic!("expected the script to fail, got {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn script_running_too_long_is_stopped() {
+        let limits = ScriptLimits {
+            timeout: Duration::from_millis(50),
+            max_operations: u64::MAX,
+        };
+        let action = script_action("endless", "loop {}", limits);
+
+        let message = failure(&action, &context()).await;
+
+        assert!(message.contains("longer than 50ms"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn script_running_too_many_operations_is_stopped() {
+        let limits = ScriptLimits {
+            timeout: Duration::from_secs(60),
+            max_operations: 1_000,
+        };
+        let action = script_action("busy", "loop {}", limits);
+
+        let message = failure(&action, &context()).await;
+
+        assert!(message.to_lowercase().contains("operations"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn errors_name_the_script() {
+        let action = script_action("broken", r#"throw "no cake";"#, default_limits());
+
+        let message = failure(&action, &context()).await;
+
+        assert!(message.starts_with("broken: "), "{}", message);
+        assert!(message.contains("no cake"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn scripts_can_not_call_scripts() {
+        let other: Box<dyn DeviceTrait> = Box::new(Script {
+            id: String::from("other"),
+            name: String::from("Other"),
+            actions: HashMap::new(),
+            device_type: DeviceTypes::Script,
+        });
+        let context = ActionContext {
+            devices: Arc::new(HashMap::from([(String::from("other"), other)])),
+            ..context()
+        };
+        let action = script_action("caller", r#"call("other", "anything");"#, default_limits());
+
+        let message = failure(&action, &context).await;
+
+        assert!(
+            message.contains("can not call other scripts"),
+            "{}",
+            message
+        );
+    }
+
+    #[tokio::test]
+    async fn changes_are_written_back() {
+        let store = VariableStore::default();
+        store.set(SEQUENCE_ID, VariableScope::Global, "deaths", json!(1));
+        store.set(SEQUENCE_ID, VariableScope::Global, "stale", json!(true));
+        let context = ActionContext {
+            variable_store: store.clone(),
+            ..context()
+        };
+        let source = r#"
+            global.deaths += 1;
+            global.remove("stale");
+            sequence.runs = 3;
+            variables.said = args[0];
+        "#;
+        let action = script_action("counter", source, default_limits());
+
+        action.action(vec![json!("hello")], &context).await.unwrap();
+
+        let global = store.values(SEQUENCE_ID, VariableScope::Global);
+        assert_eq!(global.get("deaths"), Some(&json!(2)));
+        assert_eq!(global.get("stale"), None);
+        assert_eq!(
+            store.get(SEQUENCE_ID, VariableScope::Sequence, "runs"),
+            Some(json!(3))
+        );
+        assert_eq!(
+            context.variables.lock().unwrap().get("said"),
+            Some(&json!("hello"))
+        );
+    }
+}
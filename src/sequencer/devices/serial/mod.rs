@@ -0,0 +1,243 @@
+use crate::sequencer::device::{
+    self, ActionContext, ActionError, ActionResult, DeviceTrait, Parameter, ParameterName,
+};
+use crate::sequencer::devices::DeviceTypes;
+use crate::sequencer::template::render;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tokio_serial::{SerialPortBuilderExt, SerialStream};
+use tracing::warn;
+
+const ACTION_WRITE_LINE_ID: &str = "writeLine";
+const ACTION_WRITE_LINE_NAME: &str = "Write Line";
+
+const ACTION_WRITE_BYTES_ID: &str = "writeBytes";
+const ACTION_WRITE_BYTES_NAME: &str = "Write Bytes";
+
+#[derive(Debug, Clone)]
+pub struct Serial {
+    id: String,
+    name: String,
+    actions: HashMap<String, Box<dyn device::DeviceAction>>,
+    device_type: DeviceTypes,
+}
+
+impl Serial {
+    pub fn new(id: String, name: String, path: String, baud_rate: u32, line_ending: &str) -> Self {
+        let port = SerialConnection {
+            path,
+            baud_rate,
+            stream: Arc::new(Mutex::new(None)),
+        };
+
+        Serial {
+            id,
+            name,
+            actions: create_actions(&port, line_ending_bytes(line_ending)),
+            device_type: DeviceTypes::Serial,
+        }
+    }
+}
+
+impl DeviceTrait for Serial {
+    fn get_actions(&self) -> &HashMap<String, Box<dyn device::DeviceAction>> {
+        &self.actions
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_device_type(&self) -> &DeviceTypes {
+        &self.device_type
+    }
+
+    fn get_device_parameters() -> Vec<Parameter> {
+        vec![
+            Parameter::String(ParameterName::SerialPort),
+            Parameter::Integer(ParameterName::BaudRate),
+        ]
+    }
+}
+
+fn line_ending_bytes(line_ending: &str) -> Vec<u8> {
+    match line_ending {
+        "crlf" => vec![b'\r', b'\n'],
+        "cr" => vec![b'\r'],
+        "none" => vec![],
+        _ => vec![b'\n'],
+    }
+}
+
+/// The port is opened on first write and reopened if it has disappeared, e.g. the board was unplugged
+#[derive(Debug, Clone)]
+struct SerialConnection {
+    path: String,
+    baud_rate: u32,
+    stream: Arc<Mutex<Option<SerialStream>>>,
+}
+
+impl SerialConnection {
+    async fn write(&self, bytes: &[u8]) -> ActionResult {
+        let mut stream = self.stream.lock().await;
+
+        if let Some(open_stream) = stream.as_mut() {
+            match write_all(open_stream, bytes).await {
+                Ok(_) => return Ok(()),
+                Err(e) => warn!("Serial port {} lost, reopening: {}", self.path, e),
+            }
+        }
+
+        *stream = None;
+        let mut new_stream = tokio_serial::new(&self.path, self.baud_rate)
+            .open_native_async()
+            .map_err(|e| ActionError::Failed(format!("Could not open {}: {}", self.path, e)))?;
+
+        write_all(&mut new_stream, bytes)
+            .await
+            .map_err(|e| ActionError::Failed(format!("Could not write to {}: {}", self.path, e)))?;
+        *stream = Some(new_stream);
+
+        Ok(())
+    }
+}
+
+async fn write_all(stream: &mut SerialStream, bytes: &[u8]) -> std::io::Result<()> {
+    stream.write_all(bytes).await?;
+    stream.flush().await
+}
+
+/// Writes the rendered template in the first argument followed by the line ending, e.g. `H{{args.1}}`
+#[derive(Debug, Clone)]
+struct WriteLine {
+    id: String,
+    name: String,
+    port: SerialConnection,
+    line_ending: Vec<u8>,
+}
+
+#[async_trait]
+impl device::DeviceAction for WriteLine {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult {
+        let template = arguments
+            .get(0)
+            .and_then(|template| template.as_str())
+            .ok_or_else(|| ActionError::InvalidArguments(String::from("Expected text to write")))?;
+
+        let mut bytes = render(template, &context.template_data(&arguments)).into_bytes();
+        bytes.extend_from_slice(&self.line_ending);
+
+        self.port.write(&bytes).await
+    }
+}
+
+/// Writes each argument as a single byte
+#[derive(Debug, Clone)]
+struct WriteBytes {
+    id: String,
+    name: String,
+    port: SerialConnection,
+}
+
+#[async_trait]
+impl device::DeviceAction for WriteBytes {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        _context: &ActionContext,
+    ) -> ActionResult {
+        let bytes = arguments
+            .iter()
+            .map(|argument| match argument.as_u64() {
+                Some(byte) if byte <= u8::MAX as u64 => Ok(byte as u8),
+                _ => Err(ActionError::InvalidArguments(format!(
+                    "{} is not a byte",
+                    argument
+                ))),
+            })
+            .collect::<Result<Vec<u8>, ActionError>>()?;
+
+        self.port.write(&bytes).await
+    }
+}
+
+fn create_actions(
+    port: &SerialConnection,
+    line_ending: Vec<u8>,
+) -> HashMap<String, Box<dyn device::DeviceAction>> {
+    let mut actions: HashMap<String, Box<dyn device::DeviceAction>> = HashMap::new();
+
+    actions.insert(
+        String::from(ACTION_WRITE_LINE_ID),
+        Box::new(WriteLine {
+            id: String::from(ACTION_WRITE_LINE_ID),
+            name: String::from(ACTION_WRITE_LINE_NAME),
+            port: port.clone(),
+            line_ending,
+        }),
+    );
+
+    actions.insert(
+        String::from(ACTION_WRITE_BYTES_ID),
+        Box::new(WriteBytes {
+            id: String::from(ACTION_WRITE_BYTES_ID),
+            name: String::from(ACTION_WRITE_BYTES_NAME),
+            port: port.clone(),
+        }),
+    );
+
+    actions
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+    use tokio::io::AsyncReadExt;
+    use tokio_serial::SerialPort;
+
+    use super::*;
+
+    /// Writes to one end of a pty pair as if it were a board and reads what arrives at the other
+    #[tokio::test]
+    async fn actions_write_to_the_port() {
+        let (mut board, port) = SerialStream::pair().unwrap();
+        let path = port.name().unwrap();
+        drop(port); // The action opens it itself
+
+        let serial = Serial::new(
+            String::from("serial"),
+            String::from("Serial"),
+            path,
+            9600,
+            "crlf",
+        );
+        let context = ActionContext::default();
+
+        serial.get_actions()[ACTION_WRITE_LINE_ID]
+            .action(vec![json!("H{{args.1}}"), json!(3)], &context)
+            .await
+            .unwrap();
+        serial.get_actions()[ACTION_WRITE_BYTES_ID]
+            .action(vec![json!(72), json!(105)], &context)
+            .await
+            .unwrap();
+
+        let mut received = [0u8; 6];
+        tokio::time::timeout(Duration::from_secs(5), board.read_exact(&mut received))
+            .await
+            .expect("Nothing arrived at the board")
+            .unwrap();
+
+        assert_eq!(&received, b"H3\r\nHi");
+    }
+}
@@ -63,8 +63,13 @@ impl Delay {
 
 #[async_trait]
 impl device::DeviceAction for Delay {
-    async fn action(&self, _arguments: Vec<serde_json::Value>) {
+    async fn action(
+        &self,
+        _arguments: Vec<serde_json::Value>,
+        _context: &device::ActionContext,
+    ) -> device::ActionResult {
         time::sleep(Duration::from_secs(1)).await;
+        Ok(())
     }
 }
 
@@ -0,0 +1,344 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use tokio::io::{
+    self, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines, ReadHalf,
+    WriteHalf,
+};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+type IrcResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+trait IrcStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> IrcStream for T {}
+
+type IrcWriter = WriteHalf<Box<dyn IrcStream>>;
+type IrcLines = Lines<BufReader<ReadHalf<Box<dyn IrcStream>>>>;
+
+/// The writer of the open connection, with the generation of the reader task watching it
+struct OpenWriter {
+    generation: u64,
+    writer: IrcWriter,
+}
+
+/// A write only IRC connection, opened on first message and reopened once it has been closed.
+/// A background task reads the other half to answer PINGs and notice when the server hangs up
+#[derive(Clone)]
+pub struct IrcConnection {
+    host: String,
+    port: u16,
+    tls: bool,
+    login: String,
+    access_token: String,
+    channel: String,
+    writer: Arc<Mutex<Option<OpenWriter>>>,
+    generations: Arc<AtomicU64>,
+}
+
+impl fmt::Debug for IrcConnection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IrcConnection")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("login", &self.login)
+            .field("channel", &self.channel)
+            .finish()
+    }
+}
+
+impl IrcConnection {
+    pub fn new(
+        host: String,
+        port: u16,
+        tls: bool,
+        login: String,
+        access_token: String,
+        channel: String,
+    ) -> Self {
+        IrcConnection {
+            host,
+            port,
+            tls,
+            login,
+            access_token,
+            channel: channel.trim_start_matches('#').to_lowercase(),
+            writer: Arc::new(Mutex::new(None)),
+            generations: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn channel(&self) -> &String {
+        &self.channel
+    }
+
+    /// Sends a PRIVMSG to the channel, as a reply when given the id of the message to reply to
+    pub async fn send_message(&self, text: &str, reply_to: Option<&str>) -> IrcResult<()> {
+        // A new line would let the text smuggle in another command
+        let text = text.replace(['\r', '\n'], " ");
+
+        let line = match reply_to {
+            // Tag values end at a space or ';', so only Twitch's UUID ids are passed on
+            Some(message_id) if !is_message_id(message_id) => {
+                return Err(format!("{} is not a chat message id", message_id).into())
+            }
+            Some(message_id) => format!(
+                "@reply-parent-msg-id={} PRIVMSG #{} :{}",
+                message_id, self.channel, text
+            ),
+            None => format!("PRIVMSG #{} :{}", self.channel, text),
+        };
+
+        self.send_line(&line).await
+    }
+
+    async fn send_line(&self, line: &str) -> IrcResult<()> {
+        let mut writer = self.writer.lock().await;
+
+        if let Some(open) = writer.as_mut() {
+            match write_line(&mut open.writer, line).await {
+                Ok(_) => return Ok(()),
+                Err(e) => warn!("Chat connection lost, reconnecting: {}", e),
+            }
+        }
+
+        *writer = None;
+        let mut new_writer = self.connect().await?;
+        write_line(&mut new_writer.writer, line).await?;
+        *writer = Some(new_writer);
+
+        Ok(())
+    }
+
+    async fn connect(&self) -> IrcResult<OpenWriter> {
+        let tcp = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        let stream: Box<dyn IrcStream> = if self.tls {
+            let connector = tokio_native_tls::TlsConnector::from(native_tls::TlsConnector::new()?);
+            Box::new(connector.connect(&self.host, tcp).await?)
+        } else {
+            Box::new(tcp)
+        };
+
+        let (reader, mut writer) = io::split(stream);
+        let mut lines = BufReader::new(reader).lines();
+
+        write_line(&mut writer, "CAP REQ :twitch.tv/tags twitch.tv/commands").await?;
+        write_line(&mut writer, &format!("PASS oauth:{}", self.access_token)).await?;
+        write_line(&mut writer, &format!("NICK {}", self.login)).await?;
+
+        loop {
+            let line = lines
+                .next_line()
+                .await?
+                .ok_or("Chat server closed the connection while logging in")?;
+
+            if command(&line) == Some("001") {
+                break;
+            }
+            if command(&line) == Some("NOTICE") {
+                return Err(format!("Chat login failed: {}", line).into());
+            }
+        }
+
+        write_line(&mut writer, &format!("JOIN #{}", self.channel)).await?;
+
+        let generation = self.generations.fetch_add(1, Ordering::Relaxed) + 1;
+        tokio::spawn(read_until_closed(lines, self.writer.clone(), generation));
+
+        Ok(OpenWriter { generation, writer })
+    }
+}
+
+pub fn is_message_id(message_id: &str) -> bool {
+    !message_id.is_empty()
+        && message_id
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+/// The command of a raw IRC line, skipping its tags and prefix
+fn command(line: &str) -> Option<&str> {
+    line.split(' ')
+        .find(|part| !part.starts_with('@') && !part.starts_with(':'))
+}
+
+async fn write_line(writer: &mut IrcWriter, line: &str) -> io::Result<()> {
+    writer.write_all(format!("{}\r\n", line).as_bytes()).await?;
+    writer.flush().await
+}
+
+/// Only touches the writer while it still belongs to this reader's connection,
+/// a newer connection may have replaced it by the time this one closes
+async fn read_until_closed(
+    mut lines: IrcLines,
+    writer: Arc<Mutex<Option<OpenWriter>>>,
+    generation: u64,
+) {
+    while let Ok(Some(line)) = lines.next_line().await {
+        match command(&line) {
+            Some("PING") => {
+                let pong = line.replacen("PING", "PONG", 1);
+                match writer.lock().await.as_mut() {
+                    Some(open) if open.generation == generation => {
+                        write_line(&mut open.writer, &pong).await.ok();
+                    }
+                    _ => break,
+                }
+            }
+            Some("RECONNECT") => break,
+            _ => {}
+        }
+    }
+
+    info!("Chat connection closed");
+    let mut writer = writer.lock().await;
+    if writer
+        .as_ref()
+        .map_or(false, |open| open.generation == generation)
+    {
+        *writer = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+    use tokio::net::TcpListener;
+    use tokio::time;
+
+    use super::*;
+
+    const MESSAGE_ID: &str = "885196de-cb67-427a-baa8-82f9b0fcd05f";
+
+    fn connection(listener: &TcpListener) -> IrcConnection {
+        IrcConnection::new(
+            String::from("127.0.0.1"),
+            listener.local_addr().unwrap().port(),
+            false,
+            String::from("bot"),
+            String::from("token"),
+            String::from("#Channel"),
+        )
+    }
+
+    /// Plays the chat server through the login, handing back the connection once joined
+    async fn accept_login(
+        listener: &TcpListener,
+    ) -> (Lines<BufReader<OwnedReadHalf>>, OwnedWriteHalf) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        for expected in ["CAP REQ", "PASS oauth:token", "NICK bot"] {
+            let line = lines.next_line().await.unwrap().unwrap();
+            assert!(
+                line.starts_with(expected),
+                "Expected {}, got {}",
+                expected,
+                line
+            );
+        }
+        writer
+            .write_all(b":tmi.twitch.tv 001 bot :Welcome, GLHF!\r\n")
+            .await
+            .unwrap();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "JOIN #channel");
+
+        (lines, writer)
+    }
+
+    #[tokio::test]
+    async fn messages_and_replies_reach_the_channel() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let connection = connection(&listener);
+
+        let server = tokio::spawn(async move {
+            let (mut lines, _writer) = accept_login(&listener).await;
+            let message = lines.next_line().await.unwrap().unwrap();
+            let reply = lines.next_line().await.unwrap().unwrap();
+            (message, reply)
+        });
+
+        connection
+            .send_message("hello\r\nPRIVMSG #other :spam", None)
+            .await
+            .unwrap();
+        connection
+            .send_message("thanks", Some(MESSAGE_ID))
+            .await
+            .unwrap();
+
+        let (message, reply) = time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("Nothing reached the chat server")
+            .unwrap();
+
+        assert_eq!(message, "PRIVMSG #channel :hello  PRIVMSG #other :spam");
+        assert_eq!(
+            reply,
+            format!(
+                "@reply-parent-msg-id={} PRIVMSG #channel :thanks",
+                MESSAGE_ID
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn reply_to_an_invalid_message_id_is_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let connection = connection(&listener);
+
+        let result = connection
+            .send_message("hi", Some("1 PRIVMSG #other :spam\r\nJOIN #other"))
+            .await;
+
+        assert!(result.is_err());
+        assert!(connection.writer.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_the_server_hangs_up() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let connection = connection(&listener);
+
+        let server = tokio::spawn(async move {
+            let (mut lines, writer) = accept_login(&listener).await;
+            let first = lines.next_line().await.unwrap().unwrap();
+            drop((lines, writer));
+
+            let (mut lines, _writer) = accept_login(&listener).await;
+            let second = lines.next_line().await.unwrap().unwrap();
+            (first, second)
+        });
+
+        connection.send_message("first", None).await.unwrap();
+
+        // The reader notices the hang up and lets the next message open a new connection
+        time::timeout(Duration::from_secs(5), async {
+            while connection.writer.lock().await.is_some() {
+                time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("The closed connection was kept");
+
+        connection.send_message("second", None).await.unwrap();
+
+        let (first, second) = time::timeout(Duration::from_secs(5), server)
+            .await
+            .expect("Nothing reached the chat server")
+            .unwrap();
+
+        assert_eq!(first, "PRIVMSG #channel :first");
+        assert_eq!(second, "PRIVMSG #channel :second");
+        assert_eq!(
+            connection.writer.lock().await.as_ref().unwrap().generation,
+            2
+        );
+    }
+}
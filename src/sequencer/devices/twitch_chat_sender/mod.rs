@@ -0,0 +1,356 @@
+mod irc;
+mod rate_limit;
+
+use crate::sequencer::device::{
+    self, ActionContext, ActionError, ActionResult, DeviceTrait, Parameter, ParameterName,
+};
+use crate::sequencer::devices::DeviceTypes;
+use crate::sequencer::template::render;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use twitch_api2::twitch_oauth2::{Scope, TwitchToken, UserToken};
+
+use irc::IrcConnection;
+use rate_limit::SlidingWindow;
+
+pub const TWITCH_IRC_HOST: &str = "irc.chat.twitch.tv";
+pub const TWITCH_IRC_TLS_PORT: u16 = 6697;
+pub const TWITCH_IRC_PORT: u16 = 6667;
+const TWITCH_HELIX_URL: &str = "https://api.twitch.tv/helix";
+
+const ACTION_SEND_MESSAGE_ID: &str = "sendMessage";
+const ACTION_SEND_MESSAGE_NAME: &str = "Send Message";
+
+const ACTION_REPLY_ID: &str = "reply";
+const ACTION_REPLY_NAME: &str = "Reply";
+
+const ACTION_ANNOUNCE_ID: &str = "announce";
+const ACTION_ANNOUNCE_NAME: &str = "Send Announcement";
+
+pub fn required_scopes() -> Vec<Scope> {
+    vec![
+        Scope::ChatEdit,
+        Scope::parse("moderator:manage:announcements"),
+    ]
+}
+
+/// Where the device connects, Twitch unless settings point it at another server
+#[derive(Debug, Clone)]
+pub struct ChatServer {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TwitchChatSender {
+    id: String,
+    name: String,
+    actions: HashMap<String, Box<dyn device::DeviceAction>>,
+    device_type: DeviceTypes,
+}
+
+impl TwitchChatSender {
+    /// Without a user token the device still lists its actions but they fail when run
+    pub fn new(
+        id: String,
+        name: String,
+        channel: String,
+        server: ChatServer,
+        user_token: Option<&UserToken>,
+    ) -> Self {
+        let logged_in = user_token.map(|user_token| {
+            let connection = IrcConnection::new(
+                server.host,
+                server.port,
+                server.tls,
+                String::from(user_token.login.as_str()),
+                String::from(user_token.token().secret()),
+                channel,
+            );
+
+            let helix = Helix {
+                url: String::from(TWITCH_HELIX_URL),
+                access_token: String::from(user_token.token().secret()),
+                client_id: String::from(user_token.client_id().as_str()),
+                moderator_id: String::from(user_token.user_id.as_str()),
+                broadcaster_id: Arc::new(Mutex::new(None)),
+                client: reqwest::Client::new(),
+            };
+
+            (connection, helix)
+        });
+
+        let chat = Chat {
+            logged_in,
+            rate_limit: SlidingWindow::new(rate_limit::MESSAGES_PER_WINDOW, rate_limit::WINDOW),
+        };
+
+        TwitchChatSender {
+            id,
+            name,
+            actions: create_actions(&chat),
+            device_type: DeviceTypes::TwitchChatSender,
+        }
+    }
+}
+
+impl DeviceTrait for TwitchChatSender {
+    fn get_actions(&self) -> &HashMap<String, Box<dyn device::DeviceAction>> {
+        &self.actions
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_device_type(&self) -> &DeviceTypes {
+        &self.device_type
+    }
+
+    fn get_device_parameters() -> Vec<Parameter> {
+        vec![Parameter::String(ParameterName::Channel)]
+    }
+}
+
+/// Announcements are not part of IRC so go through the Helix API instead
+#[derive(Debug, Clone)]
+struct Helix {
+    url: String,
+    access_token: String,
+    client_id: String,
+    moderator_id: String,
+    broadcaster_id: Arc<Mutex<Option<String>>>, // Looked up from the channel on first use
+    client: reqwest::Client,
+}
+
+impl Helix {
+    async fn broadcaster_id(&self, channel: &str) -> Result<String, ActionError> {
+        let mut broadcaster_id = self.broadcaster_id.lock().await;
+        if let Some(id) = broadcaster_id.as_ref() {
+            return Ok(id.clone());
+        }
+
+        let response: Value = self
+            .client
+            .get(format!("{}/users", self.url))
+            .query(&[("login", channel)])
+            .bearer_auth(&self.access_token)
+            .header("Client-Id", &self.client_id)
+            .send()
+            .await
+            .map_err(|e| ActionError::Failed(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| ActionError::Failed(e.to_string()))?;
+
+        let id = response["data"][0]["id"]
+            .as_str()
+            .ok_or_else(|| ActionError::Failed(format!("No Twitch user called {}", channel)))?;
+
+        *broadcaster_id = Some(String::from(id));
+        Ok(String::from(id))
+    }
+
+    async fn announce(&self, channel: &str, message: &str, color: &str) -> ActionResult {
+        let broadcaster_id = self.broadcaster_id(channel).await?;
+
+        let response = self
+            .client
+            .post(format!("{}/chat/announcements", self.url))
+            .query(&[
+                ("broadcaster_id", broadcaster_id.as_str()),
+                ("moderator_id", self.moderator_id.as_str()),
+            ])
+            .bearer_auth(&self.access_token)
+            .header("Client-Id", &self.client_id)
+            .json(&json!({ "message": message, "color": color }))
+            .send()
+            .await
+            .map_err(|e| ActionError::Failed(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(ActionError::Failed(format!(
+                "Announcement failed with {}",
+                response.status()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Chat {
+    logged_in: Option<(IrcConnection, Helix)>,
+    rate_limit: SlidingWindow,
+}
+
+#[derive(Debug, Clone)]
+enum ChatActionKind {
+    SendMessage, // [message]
+    Reply,       // [message], replies to payload.message_id
+    Announce,    // [message, color?] where color is blue, green, orange, purple or primary
+}
+
+#[derive(Debug, Clone)]
+struct ChatAction {
+    id: String,
+    name: String,
+    kind: ChatActionKind,
+    chat: Chat,
+}
+
+#[async_trait]
+impl device::DeviceAction for ChatAction {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult {
+        let message = match arguments.get(0).and_then(|message| message.as_str()) {
+            Some(message) => render(message, &context.template_data(&arguments)),
+            None => {
+                return Err(ActionError::InvalidArguments(String::from(
+                    "Expected a message",
+                )))
+            }
+        };
+
+        let (connection, helix) = self
+            .chat
+            .logged_in
+            .as_ref()
+            .ok_or_else(|| ActionError::Failed(String::from("Not logged in to Twitch")))?;
+
+        // Checked before waiting for the rate limit so a refused reply doesn't use up a message
+        let reply_to = match self.kind {
+            ChatActionKind::Reply => Some(reply_to(context)?),
+            _ => None,
+        };
+
+        self.chat.rate_limit.acquire().await;
+
+        match self.kind {
+            ChatActionKind::SendMessage | ChatActionKind::Reply => connection
+                .send_message(&message, reply_to)
+                .await
+                .map_err(|e| ActionError::Failed(e.to_string())),
+            ChatActionKind::Announce => {
+                let color = arguments
+                    .get(1)
+                    .and_then(|color| color.as_str())
+                    .unwrap_or("primary");
+
+                helix.announce(connection.channel(), &message, color).await
+            }
+        }
+    }
+}
+
+/// The id of the chat message which triggered the sequence
+fn reply_to(context: &ActionContext) -> Result<&str, ActionError> {
+    let message_id = context.payload["message_id"].as_str().ok_or_else(|| {
+        ActionError::InvalidArguments(String::from("The trigger has no chat message to reply to"))
+    })?;
+
+    if !irc::is_message_id(message_id) {
+        return Err(ActionError::InvalidArguments(format!(
+            "{} is not a chat message id",
+            message_id
+        )));
+    }
+
+    Ok(message_id)
+}
+
+fn create_actions(chat: &Chat) -> HashMap<String, Box<dyn device::DeviceAction>> {
+    let mut actions: HashMap<String, Box<dyn device::DeviceAction>> = HashMap::new();
+
+    let definitions = [
+        (
+            ACTION_SEND_MESSAGE_ID,
+            ACTION_SEND_MESSAGE_NAME,
+            ChatActionKind::SendMessage,
+        ),
+        (ACTION_REPLY_ID, ACTION_REPLY_NAME, ChatActionKind::Reply),
+        (
+            ACTION_ANNOUNCE_ID,
+            ACTION_ANNOUNCE_NAME,
+            ChatActionKind::Announce,
+        ),
+    ];
+
+    for (id, name, kind) in definitions {
+        actions.insert(
+            String::from(id),
+            Box::new(ChatAction {
+                id: String::from(id),
+                name: String::from(name),
+                kind,
+                chat: chat.clone(),
+            }),
+        );
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::time::Instant;
+
+    use super::*;
+
+    /// Logged in to a chat server which is never reached
+    fn chat(rate_limit: &SlidingWindow) -> Chat {
+        let connection = IrcConnection::new(
+            String::from("127.0.0.1"),
+            1,
+            false,
+            String::from("bot"),
+            String::from("token"),
+            String::from("channel"),
+        );
+        let helix = Helix {
+            url: String::from("http://127.0.0.1:1"),
+            access_token: String::from("token"),
+            client_id: String::from("client"),
+            moderator_id: String::from("1234"),
+            broadcaster_id: Arc::new(Mutex::new(None)),
+            client: reqwest::Client::new(),
+        };
+
+        Chat {
+            logged_in: Some((connection, helix)),
+            rate_limit: rate_limit.clone(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refused_reply_does_not_use_up_a_message() {
+        let rate_limit = SlidingWindow::new(1, rate_limit::WINDOW);
+        let actions = create_actions(&chat(&rate_limit));
+
+        for payload in [json!({}), json!({ "message_id": "1 PRIVMSG #other :spam" })] {
+            let context = ActionContext {
+                payload,
+                ..ActionContext::default()
+            };
+            let result = actions[ACTION_REPLY_ID]
+                .action(vec![json!("thanks")], &context)
+                .await;
+
+            assert!(matches!(result, Err(ActionError::InvalidArguments(_))));
+        }
+
+        let started = Instant::now();
+        rate_limit.acquire().await;
+        assert_eq!(Instant::now(), started);
+    }
+}
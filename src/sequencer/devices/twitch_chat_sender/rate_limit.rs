@@ -0,0 +1,66 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{self, Instant};
+
+/// Twitch allows 20 messages every 30 seconds for a user who is not a moderator of the channel
+pub const MESSAGES_PER_WINDOW: usize = 20;
+pub const WINDOW: Duration = Duration::from_secs(30);
+
+/// Remembers when the last messages were sent, shared by every action of a device.
+/// Once a window's worth have been sent a message waits until the oldest of them is a window old,
+/// instead of being dropped, so no window ever holds more than the limit
+#[derive(Debug, Clone)]
+pub struct SlidingWindow {
+    limit: usize,
+    window: Duration,
+    sent: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl SlidingWindow {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        SlidingWindow {
+            limit,
+            window,
+            sent: Arc::new(Mutex::new(VecDeque::with_capacity(limit))),
+        }
+    }
+
+    pub async fn acquire(&self) {
+        // Holding the lock while waiting keeps messages in the order they asked to be sent
+        let mut sent = self.sent.lock().await;
+
+        if sent.len() >= self.limit {
+            if let Some(oldest) = sent.pop_front() {
+                time::sleep_until(oldest + self.window).await;
+            }
+        }
+
+        sent.push_back(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn message_over_the_limit_waits_for_the_window() {
+        let rate_limit = SlidingWindow::new(MESSAGES_PER_WINDOW, WINDOW);
+        let started = Instant::now();
+
+        for _ in 0..MESSAGES_PER_WINDOW {
+            rate_limit.acquire().await;
+        }
+        assert_eq!(Instant::now(), started);
+
+        time::advance(Duration::from_secs(10)).await;
+        rate_limit.acquire().await;
+        assert_eq!(Instant::now(), started + WINDOW);
+
+        // The rest of the first burst left the window at the same moment
+        rate_limit.acquire().await;
+        assert_eq!(Instant::now(), started + WINDOW);
+    }
+}
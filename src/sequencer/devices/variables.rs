@@ -0,0 +1,175 @@
+use crate::sequencer::device::{
+    self, ActionContext, ActionError, ActionResult, DeviceTrait, Parameter,
+};
+use crate::sequencer::devices::DeviceTypes;
+use crate::sequencer::template::render;
+use crate::sequencer::variable_store::VariableScope;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const DEVICE_ID: &str = "variables";
+const DEVICE_NAME: &str = "Variables";
+
+const ACTION_SET_ID: &str = "set";
+const ACTION_SET_NAME: &str = "Set";
+
+const ACTION_INCREMENT_ID: &str = "increment";
+const ACTION_INCREMENT_NAME: &str = "Increment";
+
+const ACTION_RESET_ID: &str = "reset";
+const ACTION_RESET_NAME: &str = "Reset";
+
+#[derive(Debug, Clone)]
+pub struct Variables {
+    id: String,
+    name: String,
+    actions: HashMap<String, Box<dyn device::DeviceAction>>,
+    device_type: DeviceTypes,
+}
+
+impl Variables {
+    pub fn new(id: String, name: String) -> Self {
+        Variables {
+            id,
+            name,
+            actions: create_actions(),
+            device_type: DeviceTypes::Variables,
+        }
+    }
+}
+
+impl DeviceTrait for Variables {
+    fn get_actions(&self) -> &HashMap<String, Box<dyn device::DeviceAction>> {
+        &self.actions
+    }
+
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_device_type(&self) -> &DeviceTypes {
+        &self.device_type
+    }
+
+    fn get_device_parameters() -> Vec<Parameter> {
+        vec![]
+    }
+}
+
+#[derive(Debug, Clone)]
+enum VariableActionKind {
+    Set,       // [name, value, scope?]
+    Increment, // [name, amount?, scope?]
+    Reset,     // [name, scope?]
+}
+
+/// Variables are scoped to the sequence unless the scope argument is "global"
+#[derive(Debug, Clone)]
+struct VariableAction {
+    id: String,
+    name: String,
+    kind: VariableActionKind,
+}
+
+fn scope_argument(arguments: &[Value], index: usize) -> Result<VariableScope, ActionError> {
+    match arguments.get(index).and_then(|scope| scope.as_str()) {
+        None => Ok(VariableScope::Sequence),
+        Some(scope) => VariableScope::try_from_str(scope).ok_or_else(|| {
+            ActionError::InvalidArguments(format!("{} is not global or sequence", scope))
+        }),
+    }
+}
+
+#[async_trait]
+impl device::DeviceAction for VariableAction {
+    async fn action(
+        &self,
+        arguments: Vec<serde_json::Value>,
+        context: &ActionContext,
+    ) -> ActionResult {
+        let name = arguments
+            .get(0)
+            .and_then(|name| name.as_str())
+            .ok_or_else(|| {
+                ActionError::InvalidArguments(String::from("Expected a variable name"))
+            })?;
+
+        let store = &context.variable_store;
+        let sequence_id = &context.sequence_id;
+
+        match self.kind {
+            VariableActionKind::Set => {
+                let value = match arguments.get(1) {
+                    Some(Value::String(value)) => {
+                        Value::String(render(value, &context.template_data(&arguments)))
+                    }
+                    Some(value @ Value::Number(_)) => value.clone(),
+                    _ => {
+                        return Err(ActionError::InvalidArguments(String::from(
+                            "Expected a number or string to set",
+                        )))
+                    }
+                };
+
+                store.set(sequence_id, scope_argument(&arguments, 2)?, name, value);
+            }
+            VariableActionKind::Increment => {
+                let amount = arguments.get(1).cloned().unwrap_or_else(|| Value::from(1));
+
+                store
+                    .increment(sequence_id, scope_argument(&arguments, 2)?, name, &amount)
+                    .map_err(ActionError::InvalidArguments)?;
+            }
+            VariableActionKind::Reset => {
+                store.reset(sequence_id, scope_argument(&arguments, 1)?, name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub fn setup(mut devices: device::DevicesCollection) -> device::DevicesCollection {
+    devices.insert(
+        String::from(DEVICE_ID),
+        Box::new(Variables::new(
+            String::from(DEVICE_ID),
+            String::from(DEVICE_NAME),
+        )),
+    );
+
+    devices
+}
+
+fn create_actions() -> HashMap<String, Box<dyn device::DeviceAction>> {
+    let mut actions: HashMap<String, Box<dyn device::DeviceAction>> = HashMap::new();
+
+    let definitions = [
+        (ACTION_SET_ID, ACTION_SET_NAME, VariableActionKind::Set),
+        (
+            ACTION_INCREMENT_ID,
+            ACTION_INCREMENT_NAME,
+            VariableActionKind::Increment,
+        ),
+        (
+            ACTION_RESET_ID,
+            ACTION_RESET_NAME,
+            VariableActionKind::Reset,
+        ),
+    ];
+
+    for (id, name, kind) in definitions {
+        actions.insert(
+            String::from(id),
+            Box::new(VariableAction {
+                id: String::from(id),
+                name: String::from(name),
+                kind,
+            }),
+        );
+    }
+
+    actions
+}
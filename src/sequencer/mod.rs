@@ -1,28 +1,112 @@
+pub mod bundle;
 pub mod device;
 pub mod devices;
+pub mod progress;
 pub mod reaction_sequence;
-use self::{device::DevicesCollection, reaction_sequence::ReactionSequence};
-use crate::triggers::TriggerSource;
+pub mod sequence_files;
+pub mod template;
+pub mod templates;
+pub mod variable_store;
+use self::{
+    device::{ActionContext, ActionError, DevicesCollection},
+    progress::{Progress, ProgressSender, Status},
+    reaction_sequence::ReactionSequence,
+    variable_store::VariableStore,
+};
+use crate::triggers::{self, TriggerCollection, TriggerSource};
+use crate::ThreadActions;
+use futures_util::{future, select, FutureExt};
 use std::collections::HashMap;
 use std::error::Error;
-use tokio::sync::watch;
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch, Mutex};
+use tracing::{debug, info, info_span, instrument, warn, Instrument};
 
 impl reaction_sequence::ReactionSequence {
-    pub async fn play(&self, device_set: &HashMap<String, Box<dyn device::DeviceTrait>>) {
+    #[instrument(name = "sequence", skip_all, fields(id = %self.id, name = %self.name))]
+    pub async fn play(
+        &self,
+        device_set: &HashMap<String, Box<dyn device::DeviceTrait>>,
+        variable_store: &VariableStore,
+        event: &QueueEvent,
+        progress: &ProgressSender,
+    ) {
+        if !variable_store.condition_holds(
+            &self.id,
+            self.trigger.condition.as_deref(),
+            &event.payload,
+        ) {
+            debug!("Skipped as its trigger condition does not hold");
+            return;
+        }
+
+        let context = ActionContext {
+            payload: event.payload.clone(),
+            sequence_id: self.id.clone(),
+            variable_store: variable_store.clone(),
+            variables: Default::default(),
+            devices: Arc::new(device_set.clone()),
+            progress: progress.clone(),
+        };
+
+        info!("Playing");
+        progress.publish(Progress::SequenceStarted(
+            self.id.clone(),
+            self.name.clone(),
+            event.payload.clone(),
+        ));
+
         let sequence = &self.sequence;
-        for method in sequence {
-            let device = get_device_by_id(device_set, &method.device_id);
+        for (step, method) in sequence.iter().enumerate() {
+            if !variable_store.condition_holds(
+                &self.id,
+                method.condition.as_deref(),
+                &event.payload,
+            ) {
+                continue;
+            }
+
+            progress.publish(Progress::StepStarted(self.id.clone(), step));
+
             let method_arguments = method.arguments.clone();
 
-            println!("{}", &method.device_action_id);
-            device
-                .unwrap()
-                .get_actions()
-                .get(&method.device_action_id)
-                .unwrap()
-                .action(method_arguments)
-                .await;
+            debug!(
+                step,
+                device = %method.device_id,
+                action = %method.device_action_id,
+                "Running step"
+            );
+            let result = match get_device_by_id(device_set, &method.device_id)
+                .and_then(|device| device.get_actions().get(&method.device_action_id))
+            {
+                Some(action) => action.action(method_arguments, &context).await,
+                None => Err(ActionError::Failed(format!(
+                    "{} has no action {}",
+                    method.device_id, method.device_action_id
+                ))),
+            };
+
+            match result {
+                Ok(()) => {
+                    progress.publish(Progress::Device(method.device_id.clone(), Status::Ready))
+                }
+                Err(e) => {
+                    warn!(step, device = %method.device_id, "Step failed: {}", e);
+                    progress.publish(Progress::Device(
+                        method.device_id.clone(),
+                        Status::Failed(e.to_string()),
+                    ));
+                    progress.publish(Progress::Error(format!(
+                        "Step {} of {} failed: {}",
+                        step + 1,
+                        self.name,
+                        e
+                    )));
+                }
+            }
         }
+
+        progress.publish(Progress::SequenceFinished(self.id.clone()));
     }
 
     pub fn is_triggered_by_event(&self, event: QueueEvent) -> bool {
@@ -42,26 +126,136 @@ fn get_device_by_id<'a>(
 pub struct QueueEvent {
     pub trigger_source: TriggerSource,
     pub trigger_event_id: String,
+    pub payload: serde_json::Value,
 }
 
+/// Events waiting for the sequencer, trigger sources wait when it is full
+const QUEUE_CAPACITY: usize = 100;
+
+/// Held while sequences play, so one fired through the control API never plays over the queue's
+pub type PlayLock = Arc<Mutex<()>>;
+
+/// Plays the sequences triggered by each event in the order the events arrived.
+/// Nothing is taken from the queue while paused
 pub async fn watch_queue(
     device_set: DevicesCollection,
     sequences: Vec<ReactionSequence>,
-    mut queue_reciever: watch::Receiver<QueueEvent>,
+    variable_store: VariableStore,
+    mut queue_reciever: mpsc::Receiver<QueueEvent>,
     task_handler_reciever: watch::Receiver<()>,
+    mut paused: watch::Receiver<bool>,
+    progress: ProgressSender,
+    play_lock: PlayLock,
 ) -> Result<(), Box<dyn Error>> {
-    println!("Started queue reciever");
-
-    while queue_reciever.changed().await.is_ok() && !task_handler_reciever.has_changed().unwrap() {
-        let event = (*queue_reciever.borrow()).clone();
-        println!("Sequencer Queue Recieved = {:?}", *queue_reciever.borrow());
-        for sequence in sequences.iter() {
-            if sequence.is_triggered_by_event(event.clone()) {
-                println!("Played Sequence = {:?}", sequence.clone());
-                sequence.play(&device_set).await;
+    info!("Started queue reciever");
+
+    loop {
+        while *paused.borrow() {
+            if paused.changed().await.is_err() {
+                break;
+            }
+        }
+
+        let event = match queue_reciever.recv().await {
+            Some(event) => event,
+            None => break,
+        };
+
+        if task_handler_reciever.has_changed().unwrap_or(true) {
+            break;
+        }
+
+        progress.publish(Progress::EventTaken);
+
+        let span = info_span!(
+            "queue_event",
+            source = event.trigger_source.as_str(),
+            event = %event.trigger_event_id
+        );
+        async {
+            debug!(payload = %event.payload, "Took event from the queue");
+            let _playing = play_lock.lock().await;
+            for sequence in sequences.iter() {
+                if sequence.enabled && sequence.is_triggered_by_event(event.clone()) {
+                    sequence
+                        .play(&device_set, &variable_store, &event, &progress)
+                        .await;
+                }
             }
         }
+        .instrument(span)
+        .await;
     }
 
     Ok(())
 }
+
+/// Watches the trigger sources and plays the sequences they trigger until told to stop
+pub async fn run_listeners(
+    device_set: DevicesCollection,
+    triggers: TriggerCollection,
+    sequences: Vec<ReactionSequence>,
+    variable_store: VariableStore,
+    record_events: bool,
+    mut listener: mpsc::Receiver<ThreadActions>,
+    progress: ProgressSender,
+    play_lock: PlayLock,
+) {
+    let (trigger_sequence, trigger_sequence_reciever) = mpsc::channel(QUEUE_CAPACITY);
+
+    let (task_handler_sender, task_handler_reciever) = watch::channel(());
+    let (paused_sender, paused_reciever) = watch::channel(false);
+
+    let sequencer_queue = watch_queue(
+        device_set,
+        sequences,
+        variable_store,
+        trigger_sequence_reciever,
+        task_handler_reciever.clone(),
+        paused_reciever,
+        progress.clone(),
+        play_lock,
+    );
+
+    let trigger_manager = triggers::watch_trigger_sources(
+        triggers,
+        trigger_sequence,
+        task_handler_reciever.clone(),
+        record_events,
+        progress.clone(),
+    );
+    let mut listeners = Box::pin(future::try_join(trigger_manager, sequencer_queue).fuse());
+
+    loop {
+        let mut l = Box::pin(listener.recv().fuse());
+
+        select! {
+            _x = listeners => {
+                info!("Listeners finished");
+                return;
+            }
+            action = l => match action {
+                Some(ThreadActions::Pause) => {
+                    info!("Paused");
+                    paused_sender.send(true).ok();
+                    progress.publish(Progress::Paused(true));
+                }
+                Some(ThreadActions::Resume) => {
+                    info!("Resumed");
+                    paused_sender.send(false).ok();
+                    progress.publish(Progress::Paused(false));
+                }
+                Some(ThreadActions::Stop) | None => {
+                    info!("Told to stop");
+                    task_handler_sender.send(()).unwrap();
+                    // The queue is left waiting for a resume otherwise
+                    paused_sender.send(false).ok();
+                    break;
+                }
+            }
+        }
+    }
+
+    listeners.await.unwrap();
+    info!("Finished listeners")
+}
@@ -0,0 +1,71 @@
+use core::fmt;
+
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use super::devices::overlay::OverlayMessage;
+use super::QueueEvent;
+
+/// Updates shown on the dashboard while listeners run, slow receivers miss the oldest
+const CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub enum Progress {
+    TriggerSource(String, Status),
+    Device(String, Status), // From the result of the device's last action
+    EventReceived(QueueEvent),
+    EventTaken,                             // The oldest pending event has left the queue
+    SequenceStarted(String, String, Value), // With the payload of the event which triggered it
+    StepStarted(String, usize),
+    SequenceFinished(String),
+    Paused(bool),
+    Overlay(OverlayMessage), // Shown by the overlay server
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Watching,
+    Ready,
+    Stopped,
+    Failed(String),
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Watching => write!(f, "watching"),
+            Status::Ready => write!(f, "ready"),
+            Status::Stopped => write!(f, "stopped"),
+            Status::Failed(e) => write!(f, "failed: {}", e),
+        }
+    }
+}
+
+/// Publishes progress to every subscriber, nothing is kept when nobody is subscribed
+#[derive(Debug, Clone)]
+pub struct ProgressSender {
+    sender: broadcast::Sender<Progress>,
+}
+
+impl ProgressSender {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+
+        ProgressSender { sender }
+    }
+
+    pub fn publish(&self, progress: Progress) {
+        let _ = self.sender.send(progress);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Progress> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for ProgressSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+pub const SEQUENCES_DIRECTORY: &str = "./TRS/sequences"; // TODO: this path should be relative to a userdata folder
+
+/// Longest slug in characters, numbering and the extension still fit well within path limits
+const MAX_SLUG_LENGTH: usize = 64;
+
+/// Names Windows keeps for devices, a file named after one can not be created there
+const RESERVED_NAMES: [&str; 22] = [
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Lowercase letters and digits with single dashes between words, e.g. "Bits: 100+!" becomes "bits-100"
+pub fn slug(name: &str) -> String {
+    let mut slug = String::new();
+
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if slug.chars().count() >= MAX_SLUG_LENGTH {
+            break;
+        }
+
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        String::from("sequence")
+    } else if RESERVED_NAMES.contains(&slug) {
+        format!("{}-sequence", slug)
+    } else {
+        String::from(slug)
+    }
+}
+
+/// The file a sequence with this name should be saved to, numbered when the slug is already in use.
+/// `current` is where the sequence is saved now, it never counts as taken
+pub fn filename_for(name: &str, current: Option<&str>, taken: &[String]) -> String {
+    filename_in(SEQUENCES_DIRECTORY, name, current, taken)
+}
+
+/// `filename_for` in another directory than the sequences one
+pub fn filename_in(directory: &str, name: &str, current: Option<&str>, taken: &[String]) -> String {
+    let slug = slug(name);
+    let mut number = 1;
+
+    loop {
+        let candidate = if number == 1 {
+            format!("{}/{}.json", directory, slug)
+        } else {
+            format!("{}/{}-{}.json", directory, slug, number)
+        };
+
+        if Some(candidate.as_str()) == current
+            || (!taken.contains(&candidate) && !Path::new(&candidate).exists())
+        {
+            return candidate;
+        }
+
+        number += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slug_joins_words_with_single_dashes() {
+        assert_eq!(slug("Bits: 100+!"), "bits-100");
+        assert_eq!(slug("  --Raid   Alert-- "), "raid-alert");
+        assert_eq!(slug("Ünïcode Ärger"), "ünïcode-ärger");
+    }
+
+    #[test]
+    fn slug_of_nothing_usable_is_sequence() {
+        assert_eq!(slug(""), "sequence");
+        assert_eq!(slug("?!*"), "sequence");
+    }
+
+    #[test]
+    fn slug_is_truncated() {
+        let slug = slug(&"abc ".repeat(50));
+
+        assert_eq!(slug.chars().count(), MAX_SLUG_LENGTH - 1);
+        assert!(!slug.ends_with('-'));
+    }
+
+    #[test]
+    fn slug_avoids_windows_device_names() {
+        assert_eq!(slug("CON"), "con-sequence");
+        assert_eq!(slug("nul"), "nul-sequence");
+        assert_eq!(slug("Com1!"), "com1-sequence");
+        assert_eq!(slug("console"), "console");
+    }
+
+    fn scratch_directory(name: &str) -> String {
+        let directory =
+            std::env::temp_dir().join(format!("trs-filenames-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&directory).ok();
+        std::fs::create_dir_all(&directory).unwrap();
+
+        directory.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn taken_names_are_numbered() {
+        let directory = scratch_directory("taken");
+        let taken = vec![
+            format!("{}/raid.json", directory),
+            format!("{}/raid-2.json", directory),
+        ];
+
+        assert_eq!(
+            filename_in(&directory, "Raid", None, &taken),
+            format!("{}/raid-3.json", directory)
+        );
+    }
+
+    #[test]
+    fn existing_files_are_not_overwritten() {
+        let directory = scratch_directory("existing");
+        std::fs::write(format!("{}/raid.json", directory), "{}").unwrap();
+
+        assert_eq!(
+            filename_in(&directory, "Raid", None, &[]),
+            format!("{}/raid-2.json", directory)
+        );
+    }
+
+    #[test]
+    fn current_file_is_kept() {
+        let directory = scratch_directory("current");
+        let current = format!("{}/raid.json", directory);
+        std::fs::write(&current, "{}").unwrap();
+
+        assert_eq!(
+            filename_in(&directory, "Raid", Some(&current), &[]),
+            current
+        );
+    }
+}
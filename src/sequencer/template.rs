@@ -0,0 +1,135 @@
+use serde_json::Value;
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+
+/// How values are made safe for where the rendered template ends up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Escape {
+    None,
+    Json, // Strings escaped to sit inside a JSON string, other values inserted as JSON
+    Url,  // Everything percent-encoded so it stays within one path segment or query value
+}
+
+/// Replaces every `{{ path.to.value }}` in the template with the value found in data.
+/// Strings are inserted as they are, other values as JSON and missing values as nothing.
+pub fn render(template: &str, data: &Value) -> String {
+    render_escaped(template, data, Escape::None)
+}
+
+/// Renders like `render`, escaping each inserted value.
+/// Payload values come from viewers so must not be able to change the structure around them
+pub fn render_escaped(template: &str, data: &Value, escape: Escape) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find(OPEN) {
+        let after_open = &rest[start + OPEN.len()..];
+        let end = match after_open.find(CLOSE) {
+            Some(end) => end,
+            None => break,
+        };
+
+        rendered.push_str(&rest[..start]);
+        match (lookup(data, after_open[..end].trim()), escape) {
+            (Some(Value::Null) | None, _) => {}
+            (Some(Value::String(value)), Escape::None) => rendered.push_str(value),
+            (Some(Value::String(value)), Escape::Json) => rendered.push_str(&json_escape(value)),
+            (Some(Value::String(value)), Escape::Url) => rendered.push_str(&url_encode(value)),
+            (Some(value), Escape::Url) => rendered.push_str(&url_encode(&value.to_string())),
+            (Some(value), _) => rendered.push_str(&value.to_string()),
+        }
+
+        rest = &after_open[end + CLOSE.len()..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+pub fn lookup<'a>(data: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(data, |value, segment| match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => value.get(segment),
+        })
+}
+
+/// The inside of a JSON string literal, without the surrounding quotes
+fn json_escape(value: &str) -> String {
+    let quoted = Value::from(value).to_string();
+    String::from(&quoted[1..quoted.len() - 1])
+}
+
+/// Percent-encodes all but the unreserved characters of RFC 3986
+fn url_encode(value: &str) -> String {
+    let mut encoded = String::new();
+
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn data() -> Value {
+        json!({
+            "payload": {
+                "user_name": "a\"b&c/d?e",
+                "bits": 100,
+                "reward": { "title": "Ears" },
+                "items": ["first", "second"],
+            }
+        })
+    }
+
+    #[test]
+    fn values_are_inserted() {
+        assert_eq!(
+            render("{{payload.user_name}} cheered {{ payload.bits }}", &data()),
+            "a\"b&c/d?e cheered 100"
+        );
+        assert_eq!(render("{{payload.items.1}}", &data()), "second");
+        assert_eq!(render("[{{payload.missing}}]", &data()), "[]");
+        assert_eq!(render("{{payload.bits", &data()), "{{payload.bits");
+    }
+
+    #[test]
+    fn json_escaping_keeps_the_document_intact() {
+        let body = render_escaped(
+            r#"{"user": "{{payload.user_name}}", "bits": {{payload.bits}}, "reward": {{payload.reward}}}"#,
+            &data(),
+            Escape::Json,
+        );
+        let parsed: Value = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(
+            parsed,
+            json!({ "user": "a\"b&c/d?e", "bits": 100, "reward": { "title": "Ears" } })
+        );
+    }
+
+    #[test]
+    fn url_escaping_keeps_values_in_their_place() {
+        assert_eq!(
+            render_escaped(
+                "https://example.com/users/{{payload.user_name}}?bits={{payload.bits}}",
+                &data(),
+                Escape::Url,
+            ),
+            "https://example.com/users/a%22b%26c%2Fd%3Fe?bits=100"
+        );
+        assert_eq!(url_encode("é ~"), "%C3%A9%20~");
+    }
+}
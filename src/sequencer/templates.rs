@@ -0,0 +1,420 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::fs;
+
+use super::device::DevicesCollection;
+use super::reaction_sequence::ReactionSequence;
+use super::sequence_files;
+use crate::migrations;
+use tracing::warn;
+
+pub const TEMPLATES_DIRECTORY: &str = "./TRS/templates"; // TODO: this path should be relative to a userdata folder
+
+const BUILT_IN_TEMPLATES: [&str; 3] = [
+    include_str!("../../templates/scene_switch_on_redemption.json"),
+    include_str!("../../templates/thank_cheers.json"),
+    include_str!("../../templates/follow_alert_text.json"),
+];
+
+const PLACEHOLDER_OPEN: &str = "${";
+const PLACEHOLDER_CLOSE: &str = "}";
+
+#[derive(Debug, Clone)]
+pub enum TemplateError {
+    FileError(String),
+    FormatError(String),
+    MissingValue(String),
+    NotANumber(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::FileError(msg) => write!(f, "File error: {}", msg),
+            TemplateError::FormatError(msg) => write!(f, "Invalid template: {}", msg),
+            TemplateError::MissingValue(label) => write!(f, "{} needs a value", label),
+            TemplateError::NotANumber(label) => write!(f, "{} needs to be a number", label),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+/// A sequence with `${key}` placeholders which are asked for when it is used.
+/// `{{...}}` is left alone for the action templates rendered when the sequence plays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub placeholders: Vec<Placeholder>,
+    pub sequence: Value, // Ids and version are filled in when the template is used
+    #[serde(skip)]
+    pub built_in: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Placeholder {
+    pub key: String,
+    pub label: String,
+    #[serde(default)]
+    pub kind: PlaceholderKind,
+    pub device_type: Option<String>, // Limits device placeholders to one type of device
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlaceholderKind {
+    Text,
+    Number, // Written as a JSON number where it makes up a whole string
+    Device,
+}
+
+impl Default for PlaceholderKind {
+    fn default() -> Self {
+        PlaceholderKind::Text
+    }
+}
+
+impl Placeholder {
+    /// Ids of the local devices a device placeholder can be filled with, sorted
+    pub fn device_options(&self, devices: &DevicesCollection) -> Vec<String> {
+        let mut ids: Vec<String> = devices
+            .iter()
+            .filter(|(_, device)| match &self.device_type {
+                Some(device_type) => device_type == device.get_device_type().as_str(),
+                None => true,
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+
+        ids
+    }
+}
+
+impl Template {
+    /// Makes a template of an existing sequence, every device it uses becomes a placeholder
+    pub fn from_sequence(sequence: &ReactionSequence, devices: &DevicesCollection) -> Self {
+        let mut sequence = sequence.clone();
+        let mut placeholders: Vec<Placeholder> = vec![];
+        let mut keys: HashMap<String, String> = HashMap::new();
+
+        for step in sequence.sequence.iter_mut() {
+            let key = keys
+                .entry(step.device_id.clone())
+                .or_insert_with(|| {
+                    let device = devices.get(&step.device_id);
+                    let key = format!("device_{}", placeholders.len() + 1);
+
+                    placeholders.push(Placeholder {
+                        key: key.clone(),
+                        label: device
+                            .map(|device| device.get_name().clone())
+                            .unwrap_or_else(|| step.device_id.clone()),
+                        kind: PlaceholderKind::Device,
+                        device_type: device
+                            .map(|device| String::from(device.get_device_type().as_str())),
+                    });
+
+                    key
+                })
+                .clone();
+
+            step.device_id = format!("{}{}{}", PLACEHOLDER_OPEN, key, PLACEHOLDER_CLOSE);
+        }
+
+        let mut document = json!(sequence);
+        if let Some(document) = document.as_object_mut() {
+            document.remove("id");
+            document.remove("version");
+        }
+
+        Template {
+            name: sequence.name.clone(),
+            description: sequence.description.clone().unwrap_or_default(),
+            placeholders,
+            sequence: document,
+            built_in: false,
+        }
+    }
+
+    /// Fills in the placeholders and gives the sequence and its steps fresh ids
+    pub fn instantiate(
+        &self,
+        values: &HashMap<String, String>,
+    ) -> Result<ReactionSequence, TemplateError> {
+        for placeholder in self.placeholders.iter() {
+            if values
+                .get(&placeholder.key)
+                .map_or(true, |value| value.is_empty())
+            {
+                return Err(TemplateError::MissingValue(placeholder.label.clone()));
+            }
+        }
+
+        let mut numbers = HashMap::new();
+        for placeholder in self.placeholders.iter() {
+            if placeholder.kind == PlaceholderKind::Number {
+                let number = number(&values[&placeholder.key])
+                    .ok_or_else(|| TemplateError::NotANumber(placeholder.label.clone()))?;
+                numbers.insert(placeholder.key.clone(), number);
+            }
+        }
+
+        let mut document = fill(&self.sequence, values, &numbers);
+
+        if let Some(object) = document.as_object_mut() {
+            object.insert(String::from("id"), Value::String(new_id()));
+
+            for step in object
+                .get_mut("sequence")
+                .and_then(|steps| steps.as_array_mut())
+                .into_iter()
+                .flatten()
+            {
+                if let Some(step) = step.as_object_mut() {
+                    step.insert(String::from("id"), Value::String(new_id()));
+                }
+            }
+        }
+
+        migrations::sequence::upgrade(&mut document)
+            .map_err(|e| TemplateError::FormatError(e.to_string()))?;
+
+        serde_json::from_value(document).map_err(|e| TemplateError::FormatError(e.to_string()))
+    }
+}
+
+/// The built in templates followed by those saved in the templates directory
+pub async fn load_templates() -> Vec<Template> {
+    let mut templates: Vec<Template> = BUILT_IN_TEMPLATES
+        .iter()
+        .filter_map(|content| match serde_json::from_str::<Template>(content) {
+            Ok(template) => Some(Template {
+                built_in: true,
+                ..template
+            }),
+            Err(e) => {
+                warn!("Skipped a built in template: {}", e);
+                None
+            }
+        })
+        .collect();
+
+    if let Ok(mut entries) = fs::read_dir(TEMPLATES_DIRECTORY).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.is_dir() {
+                continue;
+            }
+
+            match fs::read(&path)
+                .await
+                .map(|content| serde_json::from_slice(&content))
+            {
+                Ok(Ok(template)) => templates.push(template),
+                Ok(Err(e)) => warn!("Skipped template {}: {}", path.display(), e),
+                Err(e) => warn!("Skipped template {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    templates
+}
+
+pub async fn save_template(template: Template) -> Result<PathBuf, TemplateError> {
+    fs::create_dir_all(TEMPLATES_DIRECTORY)
+        .await
+        .map_err(|e| TemplateError::FileError(e.to_string()))?;
+
+    let mut path = PathBuf::from(TEMPLATES_DIRECTORY);
+    path.push(format!("{}.json", sequence_files::slug(&template.name)));
+
+    let json = serde_json::to_string_pretty(&template)
+        .map_err(|e| TemplateError::FormatError(e.to_string()))?;
+    fs::write(&path, json)
+        .await
+        .map_err(|e| TemplateError::FileError(e.to_string()))?;
+
+    Ok(path)
+}
+
+/// Replaces placeholders in every string, a string which is only a number placeholder becomes
+/// that number. Text is never turned into a number, "1080" stays the name of a scene
+fn fill(
+    value: &Value,
+    values: &HashMap<String, String>,
+    numbers: &HashMap<String, Value>,
+) -> Value {
+    match value {
+        Value::String(text) => {
+            let whole = text
+                .strip_prefix(PLACEHOLDER_OPEN)
+                .and_then(|rest| rest.strip_suffix(PLACEHOLDER_CLOSE))
+                .and_then(|key| numbers.get(key));
+
+            if let Some(number) = whole {
+                return number.clone();
+            }
+
+            let mut filled = text.clone();
+            for (key, replacement) in values {
+                filled = filled.replace(
+                    &format!("{}{}{}", PLACEHOLDER_OPEN, key, PLACEHOLDER_CLOSE),
+                    replacement,
+                );
+            }
+
+            Value::String(filled)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| fill(item, values, numbers))
+                .collect(),
+        ),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), fill(value, values, numbers)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+fn number(text: &str) -> Option<Value> {
+    let text = text.trim();
+
+    match text.parse::<i64>() {
+        Ok(number) => Some(Value::from(number)),
+        Err(_) => text
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number),
+    }
+}
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().to_hyphenated().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::variable_store::VariableStore;
+
+    fn built_in(index: usize) -> Template {
+        serde_json::from_str(BUILT_IN_TEMPLATES[index]).unwrap()
+    }
+
+    fn values_for(template: &Template) -> HashMap<String, String> {
+        template
+            .placeholders
+            .iter()
+            .map(|placeholder| {
+                let value = match placeholder.kind {
+                    PlaceholderKind::Number => "5",
+                    _ => "1080",
+                };
+                (placeholder.key.clone(), String::from(value))
+            })
+            .collect()
+    }
+
+    fn template(kind: PlaceholderKind) -> Template {
+        Template {
+            name: String::from("Test"),
+            description: String::new(),
+            placeholders: vec![Placeholder {
+                key: String::from("value"),
+                label: String::from("Value"),
+                kind,
+                device_type: None,
+            }],
+            sequence: json!({
+                "name": "Test",
+                "enabled": true,
+                "trigger": { "triggerId": "twitch_event_sub", "triggerEventId": "channel.cheer" },
+                "sequence": [
+                    { "deviceId": "timer", "deviceActionId": "delay", "arguments": ["${value}"] }
+                ]
+            }),
+            built_in: false,
+        }
+    }
+
+    fn values(value: &str) -> HashMap<String, String> {
+        HashMap::from([(String::from("value"), String::from(value))])
+    }
+
+    #[test]
+    fn every_built_in_template_instantiates() {
+        for (index, content) in BUILT_IN_TEMPLATES.iter().enumerate() {
+            let template: Template = serde_json::from_str(content)
+                .unwrap_or_else(|e| panic!("built in template {} does not parse: {}", index, e));
+
+            let sequence = template
+                .instantiate(&values_for(&template))
+                .unwrap_or_else(|e| panic!("{} does not instantiate: {}", template.name, e));
+
+            assert!(!sequence.sequence.is_empty());
+            assert!(!serde_json::to_string(&sequence).unwrap().contains("${"));
+        }
+    }
+
+    #[test]
+    fn text_which_looks_like_a_number_stays_text() {
+        let sequence = template(PlaceholderKind::Text)
+            .instantiate(&values("1080"))
+            .unwrap();
+
+        assert_eq!(sequence.sequence[0].arguments, vec![json!("1080")]);
+    }
+
+    #[test]
+    fn number_placeholder_becomes_a_number() {
+        let template = template(PlaceholderKind::Number);
+
+        let sequence = template.instantiate(&values(" 2.5 ")).unwrap();
+        assert_eq!(sequence.sequence[0].arguments, vec![json!(2.5)]);
+
+        let sequence = template.instantiate(&values("3")).unwrap();
+        assert_eq!(sequence.sequence[0].arguments, vec![json!(3)]);
+
+        assert!(matches!(
+            template.instantiate(&values("three")),
+            Err(TemplateError::NotANumber(label)) if label == "Value"
+        ));
+    }
+
+    #[test]
+    fn scene_switch_only_plays_for_the_chosen_reward() {
+        let template = built_in(0);
+        let mut values = values_for(&template);
+        values.insert(String::from("reward"), String::from("Hydrate"));
+
+        let sequence = template.instantiate(&values).unwrap();
+
+        let store = VariableStore::default();
+        let holds = |title: &str| {
+            store.condition_holds(
+                &sequence.id,
+                sequence.trigger.condition.as_deref(),
+                &json!({ "user_name": "viewer", "reward": { "title": title } }),
+            )
+        };
+        assert!(holds("Hydrate"));
+        assert!(!holds("Stretch"));
+    }
+}
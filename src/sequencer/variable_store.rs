@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rhai::serde::to_dynamic;
+use rhai::{Engine, Scope};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::warn;
+
+pub const VARIABLES_FILE: &str = "./TRS/variables.json"; // TODO: this path should be relative to a userdata folder
+
+const MAX_CONDITION_OPERATIONS: u64 = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VariableScope {
+    Global,
+    Sequence,
+}
+
+impl VariableScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VariableScope::Global => "global",
+            VariableScope::Sequence => "sequence",
+        }
+    }
+
+    pub fn try_from_str(val: &str) -> Option<Self> {
+        match val {
+            "global" => Some(VariableScope::Global),
+            "sequence" => Some(VariableScope::Sequence),
+            _ => None,
+        }
+    }
+}
+
+/// Everything in the store, per sequence values are keyed by sequence id
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Variables {
+    pub global: Map<String, Value>,
+    pub sequences: HashMap<String, Map<String, Value>>,
+}
+
+/// Numbers and strings kept between runs of sequences, written to disk on every change
+#[derive(Debug, Clone, Default)]
+pub struct VariableStore {
+    path: Option<PathBuf>, // None keeps the values in memory only
+    variables: Arc<Mutex<Variables>>,
+    changes: Arc<AtomicU64>, // Numbers each change so an older one is never saved over a newer one
+    saved: Arc<Mutex<u64>>,
+}
+
+impl VariableStore {
+    pub async fn load() -> Result<Self, Box<dyn Error>> {
+        let path = PathBuf::from(VARIABLES_FILE);
+
+        let variables = match tokio::fs::read(&path).await {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Variables::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(VariableStore {
+            path: Some(path),
+            variables: Arc::new(Mutex::new(variables)),
+            ..VariableStore::default()
+        })
+    }
+
+    pub fn snapshot(&self) -> Variables {
+        self.variables.lock().unwrap().clone()
+    }
+
+    pub fn values(&self, sequence_id: &str, scope: VariableScope) -> Map<String, Value> {
+        let variables = self.variables.lock().unwrap();
+
+        match scope {
+            VariableScope::Global => variables.global.clone(),
+            VariableScope::Sequence => variables
+                .sequences
+                .get(sequence_id)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn get(&self, sequence_id: &str, scope: VariableScope, name: &str) -> Option<Value> {
+        self.values(sequence_id, scope).get(name).cloned()
+    }
+
+    pub fn set(&self, sequence_id: &str, scope: VariableScope, name: &str, value: Value) {
+        self.update(sequence_id, scope, |values| {
+            values.insert(String::from(name), value);
+        });
+    }
+
+    pub fn reset(&self, sequence_id: &str, scope: VariableScope, name: &str) {
+        self.update(sequence_id, scope, |values| {
+            values.remove(name);
+        });
+    }
+
+    /// Adds to a number, a variable which has not been set counts from 0
+    pub fn increment(
+        &self,
+        sequence_id: &str,
+        scope: VariableScope,
+        name: &str,
+        amount: &Value,
+    ) -> Result<Value, String> {
+        let mut result = Err(format!("{} is not a number", amount));
+
+        self.update(sequence_id, scope, |values| {
+            let current = values.get(name).cloned().unwrap_or_else(|| Value::from(0));
+            result = add(&current, amount);
+
+            if let Ok(sum) = &result {
+                values.insert(String::from(name), sum.clone());
+            }
+        });
+
+        result
+    }
+
+    /// Applies what a script changed between the values it was given and the ones it handed
+    /// back, so values set by others while it ran are kept
+    pub fn apply_changes(
+        &self,
+        sequence_id: &str,
+        scope: VariableScope,
+        before: &Map<String, Value>,
+        after: Map<String, Value>,
+    ) {
+        self.update(sequence_id, scope, |values| {
+            for name in before.keys().filter(|name| !after.contains_key(*name)) {
+                values.remove(name);
+            }
+            for (name, value) in after {
+                if before.get(&name) != Some(&value) {
+                    values.insert(name, value);
+                }
+            }
+        });
+    }
+
+    /// Evaluates a Rhai expression such as `sequence.count % 10 == 0` or
+    /// `payload.reward.title == "Hydrate"`, a missing condition always holds and one which fails
+    /// to evaluate never does
+    pub fn condition_holds(
+        &self,
+        sequence_id: &str,
+        condition: Option<&str>,
+        payload: &Value,
+    ) -> bool {
+        let condition = match condition.map(str::trim) {
+            None | Some("") => return true,
+            Some(condition) => condition,
+        };
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_CONDITION_OPERATIONS);
+
+        let mut scope = Scope::new();
+        for variable_scope in [VariableScope::Global, VariableScope::Sequence] {
+            match to_dynamic(self.values(sequence_id, variable_scope)) {
+                Ok(values) => {
+                    scope.push_dynamic(variable_scope.as_str(), values);
+                }
+                Err(e) => warn!("Could not read variables for a condition: {}", e),
+            }
+        }
+        match to_dynamic(payload) {
+            Ok(payload) => {
+                scope.push_dynamic("payload", payload);
+            }
+            Err(e) => warn!("Could not read the payload for a condition: {}", e),
+        }
+
+        match engine.eval_expression_with_scope::<bool>(&mut scope, condition) {
+            Ok(holds) => holds,
+            Err(e) => {
+                warn!("Condition \"{}\" failed: {}", condition, e);
+                false
+            }
+        }
+    }
+
+    fn update<F>(&self, sequence_id: &str, scope: VariableScope, change: F)
+    where
+        F: FnOnce(&mut Map<String, Value>),
+    {
+        let (snapshot, change) = {
+            let mut variables = self.variables.lock().unwrap();
+
+            match scope {
+                VariableScope::Global => change(&mut variables.global),
+                VariableScope::Sequence => {
+                    let values = variables
+                        .sequences
+                        .entry(String::from(sequence_id))
+                        .or_default();
+                    change(values);
+
+                    if values.is_empty() {
+                        variables.sequences.remove(sequence_id);
+                    }
+                }
+            }
+
+            (
+                variables.clone(),
+                self.changes.fetch_add(1, Ordering::Relaxed) + 1,
+            )
+        };
+
+        self.persist(snapshot, change);
+    }
+
+    /// Saves away from the async workers when called from one, writes from different
+    /// changes may finish in any order so only the newest one is kept
+    fn persist(&self, variables: Variables, change: u64) {
+        let path = match &self.path {
+            Some(path) => path.clone(),
+            None => return,
+        };
+        let saved = self.saved.clone();
+
+        let save = move || {
+            let mut saved = saved.lock().unwrap();
+            if change <= *saved {
+                return;
+            }
+
+            match write_atomically(&path, &variables) {
+                Ok(_) => *saved = change,
+                Err(e) => warn!("Could not save variables: {}", e),
+            }
+        };
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(save);
+            }
+            Err(_) => save(),
+        }
+    }
+}
+
+/// Writes next to the file and renames over it, so a crash mid write keeps the old values
+fn write_atomically(path: &Path, variables: &Variables) -> Result<(), Box<dyn Error>> {
+    let json = serde_json::to_string_pretty(variables)?;
+    let temporary = path.with_extension("json.tmp");
+
+    std::fs::write(&temporary, json)?;
+    std::fs::rename(&temporary, path)?;
+
+    Ok(())
+}
+
+fn add(current: &Value, amount: &Value) -> Result<Value, String> {
+    match (current, amount) {
+        (Value::Number(current), Value::Number(amount)) => {
+            match (current.as_i64(), amount.as_i64()) {
+                (Some(current), Some(amount)) => current
+                    .checked_add(amount)
+                    .map(Value::from)
+                    .ok_or_else(|| format!("{} + {} is out of range", current, amount)),
+                _ => Ok(Value::from(
+                    current.as_f64().unwrap_or_default() + amount.as_f64().unwrap_or_default(),
+                )),
+            }
+        }
+        (Value::Number(_), amount) => Err(format!("{} is not a number", amount)),
+        (current, _) => Err(format!("{} is not a number", current)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn increment_refuses_to_overflow() {
+        let store = VariableStore::default();
+        store.set("sequence", VariableScope::Global, "count", json!(i64::MAX));
+
+        let result = store.increment("sequence", VariableScope::Global, "count", &json!(1));
+
+        assert!(result.is_err());
+        assert_eq!(
+            store.get("sequence", VariableScope::Global, "count"),
+            Some(json!(i64::MAX))
+        );
+    }
+
+    /// Outside a runtime the change is saved before returning
+    #[test]
+    fn changes_replace_the_saved_file() {
+        let directory = std::env::temp_dir().join(format!("trs-variables-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("variables.json");
+
+        let store = VariableStore {
+            path: Some(path.clone()),
+            ..VariableStore::default()
+        };
+        store.set("sequence", VariableScope::Global, "count", json!(1));
+        store.set("sequence", VariableScope::Sequence, "count", json!(2));
+
+        let saved: Variables = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved.global["count"], json!(1));
+        assert_eq!(saved.sequences["sequence"]["count"], json!(2));
+        assert!(!path.with_extension("json.tmp").exists());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
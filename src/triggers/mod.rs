@@ -1,58 +1,138 @@
+pub mod replay;
+pub mod schedule;
+pub mod simulator;
 pub mod triggers;
+pub mod twitch_auth;
 pub mod twitch_chat;
+pub mod twitch_event_sub;
 pub mod twitch_pub_sub;
+pub mod webhook;
 
+use crate::sequencer::progress::{Progress, ProgressSender, Status};
 use crate::sequencer::QueueEvent;
+use crate::settings::Settings;
+use crate::triggers::replay::recorder::Recorder;
 use futures_util::future::{self};
 use futures_util::{select, FutureExt};
 use std::collections::HashMap;
 use std::error::Error;
 
 use tokio::sync::{mpsc, watch};
+use tracing::{debug, error, info, info_span, warn, Instrument};
+use twitch_api2::twitch_oauth2::{Scope, UserToken};
 
+/// Forwards events to the sequencer, recording them unless they are being replayed
 pub async fn watch_for_events(
     mut rx: mpsc::Receiver<QueueEvent>,
-    trigger_sequence_stream: watch::Sender<QueueEvent>,
+    mut replayed_rx: mpsc::Receiver<QueueEvent>,
+    trigger_sequence_stream: mpsc::Sender<QueueEvent>,
+    mut recorder: Option<Recorder>,
+    progress: ProgressSender,
 ) {
-    while let Some(i) = rx.recv().await {
-        println!("rx join handler: {:?}", i);
-        trigger_sequence_stream.send(i).unwrap();
+    loop {
+        let (i, replayed) = select!(
+            i = rx.recv().fuse() => (i, false),
+            i = replayed_rx.recv().fuse() => (i, true),
+        );
+        let i = match i {
+            Some(i) => i,
+            None => break,
+        };
+
+        debug!(
+            source = i.trigger_source.as_str(),
+            event = %i.trigger_event_id,
+            replayed,
+            "Received event"
+        );
+        if let (Some(recorder), false) = (recorder.as_mut(), replayed) {
+            if let Err(e) = recorder.record(&i).await {
+                warn!("Failed to record event: {}", e);
+            }
+        }
+
+        progress.publish(Progress::EventReceived(i.clone()));
+        if trigger_sequence_stream.send(i).await.is_err() {
+            break; // The sequencer has stopped
+        }
     }
 }
 
 //TODO: this shit is a mess
 async fn race(
     rx: mpsc::Receiver<QueueEvent>,
-    trigger_sequence_stream: watch::Sender<QueueEvent>,
+    replayed_rx: mpsc::Receiver<QueueEvent>,
+    trigger_sequence_stream: mpsc::Sender<QueueEvent>,
+    recorder: Option<Recorder>,
     mut task_handler_reciever: watch::Receiver<()>,
+    progress: ProgressSender,
 ) {
     select!(
-       _x = watch_for_events(rx, trigger_sequence_stream).fuse() => println!("handler finished"),
-        _v = task_handler_reciever.changed().fuse() => println!("dropped rx join_handle"),
+       _x = watch_for_events(rx, replayed_rx, trigger_sequence_stream, recorder, progress).fuse() => debug!("Event handler finished"),
+        _v = task_handler_reciever.changed().fuse() => debug!("Event handler stopped by controll flow"),
     )
 }
 
 pub async fn watch_trigger_sources(
     trigger_sources_map: TriggerCollection,
-    trigger_sequence_stream: watch::Sender<QueueEvent>,
+    trigger_sequence_stream: mpsc::Sender<QueueEvent>,
     task_handler_reciever: watch::Receiver<()>,
+    record_events: bool,
+    progress: ProgressSender,
 ) -> Result<(), Box<dyn Error>> {
     let mut watchers = Vec::new();
 
+    let recorder = if record_events {
+        let (recorder, path) = Recorder::create().await?;
+        info!("Recording events to {}", path.display());
+        Some(recorder)
+    } else {
+        None
+    };
+
     let (tx, rx): (mpsc::Sender<QueueEvent>, mpsc::Receiver<QueueEvent>) = mpsc::channel(10);
+    // Replayed events keep their original source, so they arrive separately to not be recorded twice
+    let (replayed_tx, replayed_rx) = mpsc::channel(10);
 
-    for trigger in trigger_sources_map.into_values() {
-        let moveable_tx = tx.clone();
+    for (trigger_id, trigger) in trigger_sources_map.into_iter() {
+        let moveable_tx = if trigger_id == TriggerSource::Replay.as_str() {
+            replayed_tx.clone()
+        } else {
+            tx.clone()
+        };
         let movable_watcher = task_handler_reciever.clone();
-        watchers.push(async move {
-            trigger.watch(moveable_tx, movable_watcher).await.unwrap();
-        })
+        let progress = progress.clone();
+        let span = info_span!("trigger_source", id = %trigger_id);
+        watchers.push(
+            async move {
+                info!("Watching");
+                progress.publish(Progress::TriggerSource(
+                    trigger_id.clone(),
+                    Status::Watching,
+                ));
+                let status = match trigger.watch(moveable_tx, movable_watcher).await {
+                    Ok(()) => {
+                        info!("Stopped");
+                        Status::Stopped
+                    }
+                    Err(e) => {
+                        error!("Failed: {}", e);
+                        Status::Failed(e.to_string())
+                    }
+                };
+                progress.publish(Progress::TriggerSource(trigger_id, status));
+            }
+            .instrument(span),
+        )
     }
 
     let rx_join_handle = tokio::spawn(race(
         rx,
+        replayed_rx,
         trigger_sequence_stream,
+        recorder,
         task_handler_reciever.clone(),
+        progress,
     ));
 
     let _ = future::join(future::join_all(watchers), rx_join_handle).await;
@@ -60,45 +140,238 @@ pub async fn watch_trigger_sources(
 }
 const TARGET_CHANNEL: &str = "scootscoot2000";
 
-pub async fn get_available_trigger_sources() -> Result<TriggerCollection, Box<dyn Error>> {
+/// Handles the UI uses to drive the offline trigger sources
+#[derive(Debug, Clone)]
+pub struct TriggerHandles {
+    pub simulator: simulator::SimulatorHandle,
+    pub replay: replay::ReplayHandle,
+}
+
+/// The Twitch scopes the configured trigger sources need from the user token
+pub fn required_scopes(settings: &Settings) -> Vec<Scope> {
+    if is_trigger_source_configured(settings, &TriggerSource::TwitchEventSub) {
+        twitch_event_sub::required_scopes()
+    } else if is_trigger_source_configured(settings, &TriggerSource::TwitchPubSub) {
+        vec![Scope::ChannelReadRedemptions]
+    } else {
+        vec![]
+    }
+}
+
+pub async fn get_available_trigger_sources(
+    settings: &Settings,
+    user_token: Option<&UserToken>,
+) -> Result<(TriggerCollection, TriggerHandles), Box<dyn Error>> {
     let mut trigger_sources: TriggerCollection = HashMap::new();
 
+    if let Some(user_token) = user_token {
+        if is_trigger_source_configured(settings, &TriggerSource::TwitchPubSub) {
+            trigger_sources.insert(
+                String::from(TriggerSource::TwitchPubSub.as_str()),
+                Box::new(twitch_pub_sub::TwitchPubSub::new(
+                    TARGET_CHANNEL,
+                    user_token.clone(),
+                )),
+            );
+        }
+
+        if is_trigger_source_configured(settings, &TriggerSource::TwitchEventSub) {
+            trigger_sources.insert(
+                String::from(TriggerSource::TwitchEventSub.as_str()),
+                Box::new(create_event_sub(settings, user_token)),
+            );
+        }
+    }
+
+    if is_trigger_source_configured(settings, &TriggerSource::TwitchChat) {
+        trigger_sources.insert(
+            String::from(TriggerSource::TwitchChat.as_str()),
+            Box::new(twitch_chat::TwitchChat::new(String::from(TARGET_CHANNEL))),
+        );
+    }
+
+    if let Some(webhook_settings) = &settings.webhook {
+        if is_trigger_source_configured(settings, &TriggerSource::Webhook) {
+            let port = u16::try_from(webhook_settings.port).map_err(|_| {
+                format!(
+                    "The webhook port {} is not a valid port",
+                    webhook_settings.port
+                )
+            })?;
+            let mut webhook = webhook::Webhook::new(port, webhook_settings.secret.clone());
+            for event in webhook_settings.events.iter() {
+                webhook.add_event(
+                    event.id.clone(),
+                    event.name.clone(),
+                    event.payload_fields.clone().unwrap_or_default(),
+                );
+            }
+
+            trigger_sources.insert(
+                String::from(TriggerSource::Webhook.as_str()),
+                Box::new(webhook),
+            );
+        }
+    }
+
+    if let Some(schedule_settings) = &settings.schedules {
+        if is_trigger_source_configured(settings, &TriggerSource::Schedule) {
+            let mut schedule = schedule::Schedule::new();
+            for configured in schedule_settings.iter() {
+                schedule.add_schedule(
+                    configured.id.clone(),
+                    configured.name.clone(),
+                    schedule::ScheduleKind::parse(
+                        configured.interval_seconds,
+                        configured.cron.as_deref(),
+                        configured.time_of_day.as_deref(),
+                    )?,
+                );
+            }
+
+            trigger_sources.insert(
+                String::from(TriggerSource::Schedule.as_str()),
+                Box::new(schedule),
+            );
+        }
+    }
+
+    // The simulator and replay need no connection so are always available
+    let simulator = match &settings.simulator {
+        Some(simulator_settings) => {
+            let port = simulator_settings
+                .port
+                .map(|port| {
+                    u16::try_from(port)
+                        .map_err(|_| format!("The simulator port {} is not a valid port", port))
+                })
+                .transpose()?;
+            let mut simulator =
+                simulator::Simulator::new(port, simulator_settings.stdin.unwrap_or(false));
+            for event in simulator_settings.events.iter().flatten() {
+                simulator.add_event(
+                    event.id.clone(),
+                    event.name.clone(),
+                    event.payload_fields.clone().unwrap_or_default(),
+                );
+            }
+
+            simulator
+        }
+        None => simulator::Simulator::new(None, false),
+    };
+    let replay = replay::Replay::new();
+
+    let handles = TriggerHandles {
+        simulator: simulator.get_handle(),
+        replay: replay.get_handle(),
+    };
+
     trigger_sources.insert(
-        String::from(TriggerSource::TwitchPubSub.as_str()),
-        Box::new(twitch_pub_sub::TwitchPubSub::new(TARGET_CHANNEL).await?),
+        String::from(TriggerSource::Simulator.as_str()),
+        Box::new(simulator),
     );
 
     trigger_sources.insert(
-        String::from(TriggerSource::TwitchChat.as_str()),
-        Box::new(twitch_chat::TwitchChat::new(String::from(TARGET_CHANNEL))),
+        String::from(TriggerSource::Replay.as_str()),
+        Box::new(replay),
+    );
+
+    Ok((trigger_sources, handles))
+}
+
+fn create_event_sub(
+    settings: &Settings,
+    user_token: &UserToken,
+) -> twitch_event_sub::TwitchEventSub {
+    let (websocket_url, helix_url, broadcaster_user_id) = match &settings.eventsub {
+        Some(event_sub_settings) => (
+            event_sub_settings.websocket_url.clone(),
+            event_sub_settings.helix_url.clone(),
+            event_sub_settings.broadcaster_user_id.clone(),
+        ),
+        None => (None, None, None),
+    };
+
+    let mut event_sub = twitch_event_sub::TwitchEventSub::from_user_token(
+        websocket_url
+            .unwrap_or_else(|| String::from(twitch_event_sub::TWITCH_EVENTSUB_WEBSOCKET_URL)),
+        helix_url.unwrap_or_else(|| String::from(twitch_event_sub::TWITCH_HELIX_URL)),
+        user_token,
     );
 
-    Ok(trigger_sources)
+    if let Some(broadcaster_user_id) = broadcaster_user_id {
+        event_sub.set_broadcaster_user_id(broadcaster_user_id);
+    }
+
+    event_sub
+}
+
+/// Every trigger source is used unless settings narrows them down with `configured_trigger_sources`
+fn is_trigger_source_configured(settings: &Settings, trigger_source: &TriggerSource) -> bool {
+    match &settings.configured_trigger_sources {
+        Some(configured) => configured
+            .iter()
+            .any(|configured| configured == trigger_source.as_str()),
+        None => true,
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum TriggerSource {
     TwitchPubSub,
     TwitchChat,
+    Simulator,
+    Replay,
+    Webhook,
+    TwitchEventSub,
+    Schedule,
 }
 
 const TWITCH_CHAT: &str = "twitch_chat";
 const TWITCH_PUB_SUB: &str = "twitch_pub_sub";
+const SIMULATOR: &str = "simulator";
+const REPLAY: &str = "replay";
+const WEBHOOK: &str = "webhook";
+const TWITCH_EVENT_SUB: &str = "twitch_event_sub";
+const SCHEDULE: &str = "schedule";
 impl TriggerSource {
     pub fn as_str(&self) -> &'static str {
         match self {
             TriggerSource::TwitchChat => TWITCH_CHAT,
             TriggerSource::TwitchPubSub => TWITCH_PUB_SUB,
+            TriggerSource::Simulator => SIMULATOR,
+            TriggerSource::Replay => REPLAY,
+            TriggerSource::Webhook => WEBHOOK,
+            TriggerSource::TwitchEventSub => TWITCH_EVENT_SUB,
+            TriggerSource::Schedule => SCHEDULE,
         }
     }
 
     pub fn from_str(val: &str) -> Self {
+        match TriggerSource::try_from_str(val) {
+            Some(trigger_source) => trigger_source,
+            None => panic!("Tried to construct an TriggerSource using an invalid string"),
+        }
+    }
+
+    pub fn try_from_str(val: &str) -> Option<Self> {
         if val == TWITCH_CHAT {
-            TriggerSource::TwitchChat
+            Some(TriggerSource::TwitchChat)
         } else if val == TWITCH_PUB_SUB {
-            return TriggerSource::TwitchPubSub;
+            Some(TriggerSource::TwitchPubSub)
+        } else if val == SIMULATOR {
+            Some(TriggerSource::Simulator)
+        } else if val == REPLAY {
+            Some(TriggerSource::Replay)
+        } else if val == WEBHOOK {
+            Some(TriggerSource::Webhook)
+        } else if val == TWITCH_EVENT_SUB {
+            Some(TriggerSource::TwitchEventSub)
+        } else if val == SCHEDULE {
+            Some(TriggerSource::Schedule)
         } else {
-            panic!("Tried to construct an TriggerSource using an invalid string");
+            None
         }
     }
 }
@@ -0,0 +1,277 @@
+pub mod recorder;
+
+use std::path::PathBuf;
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+use futures_util::future;
+use futures_util::select;
+use futures_util::FutureExt;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::{self, Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::sequencer::QueueEvent;
+
+use super::{triggers::TriggerEvent, triggers::TriggerSource};
+use recorder::{read_recording, RecordedEvent};
+
+/// Timed replays play somewhere between ten times slower and a hundred times faster
+pub const MIN_SPEED: f64 = 0.1;
+pub const MAX_SPEED: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayMode {
+    Timed(f64), // Speed multiplier, 1.0 plays at the original speed
+    Step,
+}
+
+#[derive(Debug, Clone)]
+pub enum ReplayCommand {
+    Start(PathBuf, ReplayMode),
+    Step,
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayHandle {
+    sender: broadcast::Sender<ReplayCommand>,
+}
+
+impl ReplayHandle {
+    /// Returns false when the listeners are not running
+    pub fn send(&self, command: ReplayCommand) -> bool {
+        self.sender.send(command).is_ok()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    sender: broadcast::Sender<ReplayCommand>,
+    trigger_events: HashMap<String, Box<dyn TriggerEvent>>,
+}
+
+impl Replay {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(10);
+        Replay {
+            sender,
+            trigger_events: HashMap::new(),
+        }
+    }
+
+    pub fn get_handle(&self) -> ReplayHandle {
+        ReplayHandle {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+struct Playback {
+    events: Vec<RecordedEvent>,
+    position: usize,
+    mode: ReplayMode,
+    started: Instant,
+}
+
+impl Playback {
+    /// When the next event is due, or None if it should wait for a step
+    fn next_due(&self) -> Option<Instant> {
+        let speed = match self.mode {
+            ReplayMode::Timed(speed) => speed.clamp(MIN_SPEED, MAX_SPEED),
+            ReplayMode::Step => return None,
+        };
+
+        let first = self.events.first()?;
+        let next = self.events.get(self.position)?;
+        let offset = next.timestamp.saturating_sub(first.timestamp) as f64 / speed;
+
+        // A recording spanning longer than an Instant can hold never comes due
+        self.started
+            .checked_add(Duration::from_millis(offset as u64))
+    }
+
+    fn next_event(&mut self) -> Option<RecordedEvent> {
+        let event = self.events.get(self.position)?.clone();
+        self.position += 1;
+        Some(event)
+    }
+}
+
+#[async_trait]
+impl TriggerSource for Replay {
+    async fn watch(
+        &self,
+        send_trigger: mpsc::Sender<QueueEvent>,
+        mut watcher: watch::Receiver<()>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut commands = self.sender.subscribe();
+
+        let fut = async {
+            let mut playback: Option<Playback> = None;
+
+            loop {
+                let next_due = playback.as_ref().and_then(|p| p.next_due());
+                let timer = async {
+                    match next_due {
+                        Some(due) => time::sleep_until(due).await,
+                        None => future::pending().await,
+                    }
+                };
+
+                let event = select!(
+                    command = commands.recv().fuse() => match command {
+                        Ok(ReplayCommand::Start(path, mode)) => {
+                            match read_recording(&path).await {
+                                Ok(events) => {
+                                    info!("Replaying {} events from {}", events.len(), path.display());
+                                    playback = Some(Playback {
+                                        events,
+                                        position: 0,
+                                        mode,
+                                        started: Instant::now(),
+                                    });
+                                }
+                                Err(e) => warn!("Could not read recording {}: {}", path.display(), e),
+                            }
+                            continue;
+                        }
+                        Ok(ReplayCommand::Step) => match playback.as_mut() {
+                            Some(p) if p.mode == ReplayMode::Step => p.next_event(),
+                            _ => continue,
+                        },
+                        Ok(ReplayCommand::Stop) => {
+                            playback = None;
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _x = timer.fuse() => playback.as_mut().and_then(|p| p.next_event()),
+                );
+
+                if let Some(queue_event) = event.and_then(|e| e.to_queue_event()) {
+                    if send_trigger.send(queue_event).await.is_err() {
+                        warn!("Replay could not queue an event as the sequencer has stopped");
+                        break;
+                    }
+                }
+            }
+        };
+
+        select!(
+            _x = fut.fuse() => info!("Replay stopped"),
+            _y = watcher.changed().fuse() => debug!("Replay stopped by controll flow")
+        );
+
+        return Ok(());
+    }
+
+    fn get_events(&self) -> &HashMap<String, Box<dyn TriggerEvent>> {
+        &self.trigger_events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::triggers::TriggerSource as Source;
+
+    fn recorded(timestamp: u128, trigger_event_id: &str) -> RecordedEvent {
+        RecordedEvent {
+            timestamp,
+            trigger_source: String::from(Source::Simulator.as_str()),
+            trigger_event_id: String::from(trigger_event_id),
+            payload: json!({ "user_name": trigger_event_id }),
+        }
+    }
+
+    fn scratch_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("trs-replay-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[tokio::test]
+    async fn recording_reads_back_in_order() {
+        let directory = scratch_directory("recording");
+        let (mut recorder, path) = recorder::Recorder::create_in(&directory).await.unwrap();
+
+        for id in ["first", "second"] {
+            recorder
+                .record(&recorded(0, id).to_queue_event().unwrap())
+                .await
+                .unwrap();
+        }
+
+        let events = read_recording(&path).await.unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let ids: Vec<&str> = events.iter().map(|e| e.trigger_event_id.as_str()).collect();
+        assert_eq!(ids, ["first", "second"]);
+        assert!(events[0].timestamp <= events[1].timestamp);
+
+        let replayed = events[1].to_queue_event().unwrap();
+        assert_eq!(replayed.trigger_source.as_str(), Source::Simulator.as_str());
+        assert_eq!(replayed.payload, json!({ "user_name": "second" }));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timed_replay_waits_for_each_event() {
+        let directory = scratch_directory("timed");
+        let path = directory.join("recording.jsonl");
+        let lines: Vec<String> = [recorded(1_000, "first"), recorded(2_000, "second")]
+            .iter()
+            .map(|event| serde_json::to_string(event).unwrap())
+            .collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let replay = Replay::new();
+        let handle = replay.get_handle();
+        let (sender, mut receiver) = mpsc::channel(10);
+        let (_stop, watcher) = watch::channel(());
+        tokio::spawn(async move { replay.watch(sender, watcher).await.unwrap() });
+
+        // Sending fails until the replay has subscribed
+        while !handle.send(ReplayCommand::Start(path.clone(), ReplayMode::Timed(2.0))) {
+            tokio::task::yield_now().await;
+        }
+
+        let first = receiver.recv().await.unwrap();
+        assert_eq!(first.trigger_event_id, "first");
+
+        // A second apart when recorded, half that at double speed
+        time::advance(Duration::from_millis(499)).await;
+        tokio::task::yield_now().await;
+        assert!(receiver.try_recv().is_err());
+
+        time::advance(Duration::from_millis(1)).await;
+        let second = receiver.recv().await.unwrap();
+        assert_eq!(second.trigger_event_id, "second");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn speed_is_clamped() {
+        let started = Instant::now();
+        let playback = |speed| Playback {
+            events: vec![recorded(0, "first"), recorded(1_000, "second")],
+            position: 1,
+            mode: ReplayMode::Timed(speed),
+            started,
+        };
+
+        assert_eq!(
+            playback(f64::MIN_POSITIVE).next_due(),
+            Some(started + Duration::from_secs(10))
+        );
+        assert_eq!(
+            playback(f64::MAX).next_due(),
+            Some(started + Duration::from_millis(10))
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+use crate::sequencer::QueueEvent;
+use crate::triggers::TriggerSource;
+
+pub const RECORDINGS_DIRECTORY: &str = "./TRS/recordings"; // TODO: this path should be relative to a userdata folder
+
+/// One line of a JSONL recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedEvent {
+    pub timestamp: u128, // Milliseconds since the unix epoch
+    pub trigger_source: String,
+    pub trigger_event_id: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
+
+impl RecordedEvent {
+    pub fn from_queue_event(event: &QueueEvent) -> Self {
+        RecordedEvent {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis(),
+            trigger_source: String::from(event.trigger_source.as_str()),
+            trigger_event_id: event.trigger_event_id.clone(),
+            payload: event.payload.clone(),
+        }
+    }
+
+    pub fn to_queue_event(&self) -> Option<QueueEvent> {
+        Some(QueueEvent {
+            trigger_source: TriggerSource::try_from_str(&self.trigger_source)?,
+            trigger_event_id: self.trigger_event_id.clone(),
+            payload: self.payload.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    /// Starts a new recording named after the current time
+    pub async fn create() -> Result<(Recorder, PathBuf), Box<dyn Error>> {
+        Recorder::create_in(Path::new(RECORDINGS_DIRECTORY)).await
+    }
+
+    pub async fn create_in(directory: &Path) -> Result<(Recorder, PathBuf), Box<dyn Error>> {
+        fs::create_dir_all(directory).await?;
+
+        let started = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = directory.join(format!("{}.jsonl", started));
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+
+        Ok((Recorder { file }, path))
+    }
+
+    pub async fn record(&mut self, event: &QueueEvent) -> Result<(), Box<dyn Error>> {
+        let mut line = serde_json::to_string(&RecordedEvent::from_queue_event(event))?;
+        line.push('\n');
+
+        self.file.write_all(line.as_bytes()).await?;
+        self.file.flush().await?;
+
+        Ok(())
+    }
+}
+
+pub async fn read_recording(path: &Path) -> Result<Vec<RecordedEvent>, Box<dyn Error>> {
+    let content = fs::read_to_string(path).await?;
+    let mut events = Vec::new();
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        events.push(serde_json::from_str::<RecordedEvent>(line)?);
+    }
+
+    Ok(events)
+}
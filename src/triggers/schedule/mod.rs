@@ -0,0 +1,241 @@
+use std::str::FromStr;
+use std::time::Duration;
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+use chrono::{Local, NaiveTime};
+use futures_util::future;
+use futures_util::select;
+use futures_util::FutureExt;
+use serde_json::json;
+use tokio::sync::{mpsc::Sender, watch};
+use tokio::time::{self, Instant, MissedTickBehavior};
+use tracing::{debug, info, warn};
+
+use crate::sequencer::QueueEvent;
+
+use super::triggers::{NamedTriggerEvent, TriggerEvent, TriggerSource};
+
+#[derive(Debug, Clone)]
+pub enum ScheduleKind {
+    Interval(Duration),
+    Cron(cron::Schedule),
+    TimeOfDay(NaiveTime), // Fires once at the next occurrence
+}
+
+impl ScheduleKind {
+    pub fn parse(
+        interval_seconds: Option<i64>,
+        cron: Option<&str>,
+        time_of_day: Option<&str>,
+    ) -> Result<Self, Box<dyn Error>> {
+        match (interval_seconds, cron, time_of_day) {
+            (Some(seconds), None, None) if seconds > 0 => {
+                Ok(ScheduleKind::Interval(Duration::from_secs(seconds as u64)))
+            }
+            (None, Some(expression), None) => {
+                Ok(ScheduleKind::Cron(cron::Schedule::from_str(expression)?))
+            }
+            (None, None, Some(time)) => Ok(ScheduleKind::TimeOfDay(NaiveTime::parse_from_str(
+                time, "%H:%M",
+            )?)),
+            _ => Err(
+                "A schedule needs exactly one of a positive interval, cron or time of day".into(),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    schedules: HashMap<String, ScheduleKind>,
+    trigger_events: HashMap<String, Box<dyn TriggerEvent>>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Schedule {
+            schedules: HashMap::new(),
+            trigger_events: HashMap::new(),
+        }
+    }
+
+    pub fn add_schedule(&mut self, id: String, name: String, kind: ScheduleKind) {
+        self.trigger_events.insert(
+            id.clone(),
+            Box::new(NamedTriggerEvent::new(
+                name,
+                vec![String::from("scheduled_at")],
+            )),
+        );
+        self.schedules.insert(id, kind);
+    }
+}
+
+/// How long until the schedule next fires, None once it never will again
+fn next_delay(kind: &ScheduleKind) -> Option<Duration> {
+    let now = Local::now();
+
+    let next = match kind {
+        ScheduleKind::Interval(period) => return Some(*period),
+        ScheduleKind::Cron(schedule) => schedule.after(&now).next()?,
+        ScheduleKind::TimeOfDay(time) => {
+            let mut next = now.date_naive().and_time(*time);
+            if next <= now.naive_local() {
+                next += chrono::Duration::days(1);
+            }
+            next.and_local_timezone(Local).earliest()?
+        }
+    };
+
+    (next - now).to_std().ok()
+}
+
+/// Stops once the sequencer is no longer taking events
+async fn run_schedule(id: String, kind: ScheduleKind, send_trigger: Sender<QueueEvent>) {
+    let fire = |send_trigger: Sender<QueueEvent>, id: String| async move {
+        let sent = send_trigger
+            .send(QueueEvent {
+                trigger_source: crate::triggers::TriggerSource::Schedule,
+                trigger_event_id: id,
+                payload: json!({ "scheduled_at": Local::now().to_rfc3339() }),
+            })
+            .await;
+
+        if sent.is_err() {
+            warn!("Schedule could not queue an event as the sequencer has stopped");
+        }
+        sent.is_ok()
+    };
+
+    match kind {
+        ScheduleKind::Interval(period) => {
+            let mut interval = time::interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                if !fire(send_trigger.clone(), id.clone()).await {
+                    return;
+                }
+            }
+        }
+        ScheduleKind::Cron(_) => {
+            while let Some(delay) = next_delay(&kind) {
+                time::sleep(delay).await;
+                if !fire(send_trigger.clone(), id.clone()).await {
+                    return;
+                }
+            }
+        }
+        ScheduleKind::TimeOfDay(_) => {
+            if let Some(delay) = next_delay(&kind) {
+                time::sleep(delay).await;
+                fire(send_trigger.clone(), id.clone()).await;
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TriggerSource for Schedule {
+    async fn watch(
+        &self,
+        send_trigger: Sender<QueueEvent>,
+        mut watcher: watch::Receiver<()>,
+    ) -> Result<(), Box<dyn Error>> {
+        let schedules = self
+            .schedules
+            .clone()
+            .into_iter()
+            .map(|(id, kind)| run_schedule(id, kind, send_trigger.clone()));
+
+        select!(
+            _x = future::join_all(schedules).fuse() => info!("Schedules finished"),
+            _y = watcher.changed().fuse() => debug!("Schedules stopped by controll flow")
+        );
+
+        return Ok(());
+    }
+
+    fn get_events(&self) -> &HashMap<String, Box<dyn TriggerEvent>> {
+        &self.trigger_events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn start(kind: ScheduleKind) -> mpsc::Receiver<QueueEvent> {
+        let (sender, reciever) = mpsc::channel(10);
+        tokio::spawn(run_schedule(String::from("schedule"), kind, sender));
+        reciever
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn interval_fires_after_each_period() {
+        let mut reciever = start(ScheduleKind::Interval(Duration::from_secs(60)));
+        tokio::task::yield_now().await;
+
+        time::advance(Duration::from_secs(59)).await;
+        assert!(reciever.try_recv().is_err());
+
+        time::advance(Duration::from_secs(1)).await;
+        let first = reciever.recv().await.unwrap();
+        assert_eq!(first.trigger_event_id, "schedule");
+        assert!(first.payload["scheduled_at"].is_string());
+
+        time::advance(Duration::from_secs(60)).await;
+        assert!(reciever.recv().await.is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cron_fires_at_each_match() {
+        let kind = ScheduleKind::parse(None, Some("* * * * * *"), None).unwrap();
+        let mut reciever = start(kind);
+        tokio::task::yield_now().await;
+
+        for _ in 0..3 {
+            time::advance(Duration::from_secs(1)).await;
+            let event = reciever.recv().await.unwrap();
+            assert_eq!(event.trigger_event_id, "schedule");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn time_of_day_fires_once() {
+        // Between one and two minutes away, whatever the second is now
+        let at = (Local::now() + chrono::Duration::minutes(2))
+            .format("%H:%M")
+            .to_string();
+        let mut reciever = start(ScheduleKind::parse(None, None, Some(&at)).unwrap());
+        tokio::task::yield_now().await;
+
+        time::advance(Duration::from_secs(59)).await;
+        assert!(reciever.try_recv().is_err());
+
+        time::advance(Duration::from_secs(61)).await;
+        assert!(reciever.recv().await.is_some());
+
+        // The schedule has finished, so nothing is left to send
+        assert!(reciever.recv().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stops_when_the_queue_closes() {
+        let (sender, reciever) = mpsc::channel(10);
+        drop(reciever);
+        let schedule = tokio::spawn(run_schedule(
+            String::from("schedule"),
+            ScheduleKind::Interval(Duration::from_secs(60)),
+            sender,
+        ));
+
+        time::advance(Duration::from_secs(60)).await;
+
+        assert!(schedule.await.is_ok());
+    }
+}
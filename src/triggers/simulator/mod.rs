@@ -0,0 +1,280 @@
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+use futures_util::select;
+use futures_util::FutureExt;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc, watch};
+use tracing::{debug, info, warn};
+
+use crate::sequencer::QueueEvent;
+
+use super::triggers::{NamedTriggerEvent, TriggerEvent, TriggerSource};
+
+const LOCALHOST: &str = "127.0.0.1";
+
+/// An event fired by hand, either from the fire panel or as a JSON line on stdin / the local socket.
+/// `triggerSource` may name any trigger source so a show can be rehearsed without a connection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedEvent {
+    pub trigger_source: String,
+    pub trigger_event_id: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatorHandle {
+    sender: broadcast::Sender<SimulatedEvent>,
+}
+
+impl SimulatorHandle {
+    /// Returns false when no simulator is currently listening
+    pub fn fire(&self, event: SimulatedEvent) -> bool {
+        self.sender.send(event).is_ok()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    sender: broadcast::Sender<SimulatedEvent>,
+    port: Option<u16>,
+    read_stdin: bool,
+    trigger_events: HashMap<String, Box<dyn TriggerEvent>>,
+}
+
+impl Simulator {
+    pub fn new(port: Option<u16>, read_stdin: bool) -> Self {
+        let (sender, _) = broadcast::channel(10);
+        Simulator {
+            sender,
+            port,
+            read_stdin,
+            trigger_events: HashMap::new(),
+        }
+    }
+
+    /// Lists an event for sequences to be triggered by, any other id can still be fired
+    pub fn add_event(&mut self, id: String, name: String, payload_fields: Vec<String>) {
+        self.trigger_events
+            .insert(id, Box::new(NamedTriggerEvent::new(name, payload_fields)));
+    }
+
+    pub fn get_handle(&self) -> SimulatorHandle {
+        SimulatorHandle {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl TriggerSource for Simulator {
+    async fn watch(
+        &self,
+        send_trigger: mpsc::Sender<QueueEvent>,
+        mut watcher: watch::Receiver<()>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut fired_events = self.sender.subscribe();
+        let (line_sender, mut line_reciever) = mpsc::channel::<String>(10);
+
+        let mut line_readers = Vec::new();
+        if self.read_stdin {
+            line_readers.push(tokio::spawn(read_lines(
+                tokio::io::stdin(),
+                line_sender.clone(),
+            )));
+        }
+
+        if let Some(port) = self.port {
+            let listener = TcpListener::bind((LOCALHOST, port)).await?;
+            info!("Simulator listening on {}:{}", LOCALHOST, port);
+            line_readers.push(tokio::spawn(accept_connections(
+                listener,
+                line_sender.clone(),
+            )));
+        }
+
+        let fut = async {
+            loop {
+                let event = select!(
+                    fired = fired_events.recv().fuse() => match fired {
+                        Ok(event) => event,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    line = line_reciever.recv().fuse() => match line {
+                        Some(line) => match serde_json::from_str::<SimulatedEvent>(&line) {
+                            Ok(event) => event,
+                            Err(e) => {
+                                warn!("Simulator could not parse {:?}: {}", line, e);
+                                continue;
+                            }
+                        },
+                        None => break,
+                    },
+                );
+
+                match to_queue_event(event) {
+                    Some(queue_event) => {
+                        if send_trigger.send(queue_event).await.is_err() {
+                            warn!(
+                                "Simulator could not queue an event as the sequencer has stopped"
+                            );
+                            break;
+                        }
+                    }
+                    None => warn!("Simulator was given an unknown trigger source"),
+                }
+            }
+        };
+
+        select!(
+            _x = fut.fuse() => info!("Simulator stopped"),
+            _y = watcher.changed().fuse() => debug!("Simulator stopped by controll flow")
+        );
+
+        for reader in line_readers {
+            reader.abort();
+        }
+
+        return Ok(());
+    }
+
+    fn get_events(&self) -> &HashMap<String, Box<dyn TriggerEvent>> {
+        &self.trigger_events
+    }
+}
+
+fn to_queue_event(event: SimulatedEvent) -> Option<QueueEvent> {
+    Some(QueueEvent {
+        trigger_source: crate::triggers::TriggerSource::try_from_str(&event.trigger_source)?,
+        trigger_event_id: event.trigger_event_id,
+        payload: event.payload,
+    })
+}
+
+async fn read_lines<R: AsyncRead + Unpin>(reader: R, line_sender: mpsc::Sender<String>) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if line_sender.send(line).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn accept_connections(listener: TcpListener, line_sender: mpsc::Sender<String>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(read_lines(stream, line_sender.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures_util::future;
+    use serde_json::json;
+
+    use super::*;
+    use crate::migrations;
+    use crate::sequencer::devices::variables;
+    use crate::sequencer::progress::{Progress, ProgressSender};
+    use crate::sequencer::reaction_sequence::ReactionSequence;
+    use crate::sequencer::variable_store::{VariableScope, VariableStore};
+    use crate::triggers::{watch_trigger_sources, TriggerCollection};
+
+    /// Fires an event through the simulator and lets the queue play the sequence it triggers
+    #[tokio::test]
+    async fn fired_event_plays_its_sequence() {
+        let simulator = Simulator::new(None, false);
+        let handle = simulator.get_handle();
+        let mut triggers: TriggerCollection = HashMap::new();
+        triggers.insert(String::from("simulator"), Box::new(simulator));
+
+        let sequence: ReactionSequence = serde_json::from_value(json!({
+            "version": migrations::sequence::VERSION,
+            "id": "counter",
+            "name": "Counter",
+            "enabled": true,
+            "trigger": { "triggerId": "simulator", "triggerEventId": "tick" },
+            "sequence": [{
+                "id": "step",
+                "deviceId": "variables",
+                "deviceActionId": "increment",
+                "arguments": ["count"]
+            }]
+        }))
+        .unwrap();
+
+        let devices = variables::setup(HashMap::new());
+        let store = VariableStore::default();
+        let progress = ProgressSender::new();
+        let mut updates = progress.subscribe();
+
+        let (queue_sender, queue_reciever) = mpsc::channel(10);
+        let (stop_sender, stop_reciever) = watch::channel(());
+        let (_paused_sender, paused_reciever) = watch::channel(false);
+
+        let fire = async {
+            let event = SimulatedEvent {
+                trigger_source: String::from("simulator"),
+                trigger_event_id: String::from("tick"),
+                payload: json!({ "user_name": "viewer" }),
+            };
+
+            // Fails until the simulator is watching
+            while !handle.fire(event.clone()) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+
+            loop {
+                match updates.recv().await {
+                    Ok(Progress::SequenceFinished(id)) if id == "counter" => break,
+                    Ok(_) => {}
+                    Err(e) => panic!("Missed progress: {}", e),
+                }
+            }
+
+            stop_sender.send(()).unwrap();
+        };
+
+        let listeners = future::join(
+            watch_trigger_sources(
+                triggers,
+                queue_sender,
+                stop_reciever.clone(),
+                false,
+                progress.clone(),
+            ),
+            crate::sequencer::watch_queue(
+                devices,
+                vec![sequence],
+                store.clone(),
+                queue_reciever,
+                stop_reciever,
+                paused_reciever,
+                progress.clone(),
+                crate::sequencer::PlayLock::default(),
+            ),
+        );
+
+        let (_, (sources, queue)) =
+            tokio::time::timeout(Duration::from_secs(5), future::join(fire, listeners))
+                .await
+                .expect("Listeners did not stop");
+
+        assert!(sources.is_ok());
+        assert!(queue.is_ok());
+        assert_eq!(
+            store.get("counter", VariableScope::Sequence, "count"),
+            Some(json!(1))
+        );
+    }
+}
@@ -4,10 +4,39 @@ use core::fmt;
 use std::{collections::HashMap, error::Error};
 use tokio::sync::{mpsc, watch};
 
-pub trait TriggerEvent: fmt::Debug + Send + Sync + dyn_clone::DynClone {}
+pub trait TriggerEvent: fmt::Debug + Send + Sync + dyn_clone::DynClone {
+    fn get_name(&self) -> &String;
+    fn get_payload_fields(&self) -> &Vec<String>;
+}
 
 dyn_clone::clone_trait_object!(TriggerEvent);
 
+/// A trigger event which is only described by its name and the fields it carries in its payload
+#[derive(Debug, Clone)]
+pub struct NamedTriggerEvent {
+    name: String,
+    payload_fields: Vec<String>,
+}
+
+impl NamedTriggerEvent {
+    pub fn new(name: String, payload_fields: Vec<String>) -> Self {
+        NamedTriggerEvent {
+            name,
+            payload_fields,
+        }
+    }
+}
+
+impl TriggerEvent for NamedTriggerEvent {
+    fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    fn get_payload_fields(&self) -> &Vec<String> {
+        &self.payload_fields
+    }
+}
+
 #[async_trait]
 pub trait TriggerSource: fmt::Debug + Send + Sync + dyn_clone::DynClone {
     async fn watch(
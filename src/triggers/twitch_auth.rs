@@ -0,0 +1,147 @@
+use std::error::Error;
+use tracing::info;
+use twitch_api2::twitch_oauth2::{
+    url, ClientId, ClientSecret, RefreshToken, Scope, TwitchToken, UserToken,
+};
+
+const ENV_FILE: &str = "./.env";
+
+/// Refreshes the token saved in .env, logging in again when there is none
+/// or when it was granted before some of the scopes were needed
+pub async fn get_user_token(scopes: Vec<Scope>) -> Result<UserToken, Box<dyn Error>> {
+    // Only the refresh token is used, a fresh access token comes with it
+    let refresh_token = match (
+        std::env::var("TWITCH_TOKEN"),
+        std::env::var("TWITCH_REFRESH_TOKEN"),
+    ) {
+        (Ok(_), Ok(refresh_token)) => refresh_token,
+        _ => return auth_flow(scopes).await,
+    };
+    let secret = env_var("TWITCH_SECRET")?;
+    let client_id = env_var("TWITCH_CLIENT_ID")?;
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+
+    let (twitch_token, _, refresh_token) = twitch_api2::twitch_oauth2::refresh_token(
+        &client,
+        &RefreshToken::new(refresh_token),
+        &ClientId::new(client_id),
+        &ClientSecret::new(secret.clone()),
+    )
+    .await?;
+
+    let user_token = UserToken::from_existing(
+        &client,
+        twitch_token,
+        refresh_token.ok_or("Twitch did not return a refresh token")?,
+        ClientSecret::new(secret),
+    )
+    .await?;
+
+    let missing: Vec<&Scope> = scopes
+        .iter()
+        .filter(|scope| !user_token.scopes().contains(scope))
+        .collect();
+    if !missing.is_empty() {
+        info!(
+            user = %user_token.login,
+            "The saved Twitch token is missing scopes {:?}, logging in again", missing
+        );
+        return auth_flow(scopes).await;
+    }
+
+    info!(user = %user_token.login, "Refreshed the Twitch token");
+    Ok(user_token)
+}
+
+fn env_var(name: &str) -> Result<String, Box<dyn Error>> {
+    std::env::var(name)
+        .map_err(|_| format!("{} is not set in the environment or .env", name).into())
+}
+
+async fn auth_flow(scopes: Vec<Scope>) -> Result<UserToken, Box<dyn Error>> {
+    let mut builder = UserToken::builder(
+        ClientId::from(env_var("TWITCH_CLIENT_ID")?),
+        ClientSecret::from(env_var("TWITCH_SECRET")?),
+        url::Url::parse("http://localhost")?,
+    );
+
+    for scope in scopes {
+        builder.add_scope(scope);
+    }
+
+    let (url, _) = builder.generate_url();
+
+    println!("Go to this page: {}", url);
+
+    let input = rpassword::prompt_password(
+        "Paste in the resulting adress after authenticating (input hidden): ",
+    )?;
+
+    let u = url::Url::parse(&input)?;
+
+    let map: std::collections::HashMap<_, _> = u.query_pairs().collect();
+
+    let user_token;
+    match (map.get("state"), map.get("code")) {
+        (Some(state), Some(code)) => {
+            user_token = builder
+                .get_user_token(
+                    &reqwest::Client::builder()
+                        .redirect(reqwest::redirect::Policy::none())
+                        .build()?,
+                    state,
+                    code,
+                )
+                .await?;
+            info!(user = %user_token.login, "Logged in to Twitch");
+        }
+        _ => match (map.get("error"), map.get("error_description")) {
+            (std::option::Option::Some(error), std::option::Option::Some(error_description)) => {
+                return Err(format!(
+                    "twitch errored with error: {} - {}",
+                    error, error_description
+                )
+                .into());
+            }
+            _ => return Err("invalid url passed".into()),
+        },
+    };
+
+    save_tokens(
+        user_token.token().secret(),
+        user_token
+            .refresh_token
+            .as_ref()
+            .ok_or("Twitch did not return a refresh token")?
+            .secret(),
+    )?;
+
+    Ok(user_token)
+}
+
+/// TODO: Be Naughty and save straight into .env.
+/// Earlier tokens are replaced, dotenv keeps the first value it finds for a name
+fn save_tokens(twitch_token: &str, refresh_token: &str) -> Result<(), Box<dyn Error>> {
+    let existing = match std::fs::read_to_string(ENV_FILE) {
+        Ok(existing) => existing,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut content: String = existing
+        .lines()
+        .filter(|line| {
+            !line.starts_with("TWITCH_TOKEN=") && !line.starts_with("TWITCH_REFRESH_TOKEN=")
+        })
+        .map(|line| format!("{}\n", line))
+        .collect();
+    content.push_str(&format!("TWITCH_TOKEN=\"{}\"\n", twitch_token));
+    content.push_str(&format!("TWITCH_REFRESH_TOKEN=\"{}\"\n", refresh_token));
+
+    std::fs::write(ENV_FILE, content)?;
+
+    Ok(())
+}
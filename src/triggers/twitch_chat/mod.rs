@@ -1,11 +1,13 @@
-use std::time::SystemTime;
 use std::{collections::HashMap, error::Error};
 
 use async_trait::async_trait;
 use futures_util::select;
 use futures_util::FutureExt;
+use serde_json::json;
 use tokio::sync::{mpsc::Sender, watch};
+use tracing::{debug, error, trace, Instrument};
 use twitch_irc::login::StaticLoginCredentials;
+use twitch_irc::message::ServerMessage;
 use twitch_irc::ClientConfig;
 use twitch_irc::SecureTCPTransport;
 use twitch_irc::TwitchIRCClient;
@@ -29,6 +31,19 @@ impl TwitchChat {
     }
 }
 
+/// Chat messages carry their id so a sequence can reply to the message that triggered it
+fn chat_payload(message: &ServerMessage) -> serde_json::Value {
+    match message {
+        ServerMessage::Privmsg(privmsg) => json!({
+            "message_id": privmsg.message_id,
+            "user_name": privmsg.sender.login,
+            "user_display_name": privmsg.sender.name,
+            "message": privmsg.message_text,
+        }),
+        _ => serde_json::Value::Null,
+    }
+}
+
 #[async_trait]
 impl TriggerSource for TwitchChat {
     async fn watch(
@@ -40,28 +55,28 @@ impl TriggerSource for TwitchChat {
         let (mut incoming_messages, client) =
             TwitchIRCClient::<SecureTCPTransport, StaticLoginCredentials>::new(config);
 
-        let fut = tokio::spawn(async move {
-            let fut = async {
-                while let Some(message) = incoming_messages.recv().await {
-                    println!(
-                        "Received twitch chat msg: {:?} {:?} ",
-                        SystemTime::now(),
-                        message
-                    );
-                    send_trigger
-                        .send(QueueEvent {
-                            trigger_source: crate::triggers::TriggerSource::TwitchChat,
-                            trigger_event_id: String::from(""),
-                        })
-                        .await
-                        .unwrap()
-                }
-            };
-            select!(
-            _x = fut.fuse()  => println!("Listener Crashed dafk"), //TODO: Should be a panic
-            _y = watcher.changed().fuse() => println!("Stopped by controll flow")
-            )
-        });
+        let fut = tokio::spawn(
+            async move {
+                let fut = async {
+                    while let Some(message) = incoming_messages.recv().await {
+                        trace!(?message, "Received chat message");
+                        send_trigger
+                            .send(QueueEvent {
+                                trigger_source: crate::triggers::TriggerSource::TwitchChat,
+                                trigger_event_id: String::from(""),
+                                payload: chat_payload(&message),
+                            })
+                            .await
+                            .unwrap()
+                    }
+                };
+                select!(
+                _x = fut.fuse()  => error!("Listener Crashed dafk"), //TODO: Should be a panic
+                _y = watcher.changed().fuse() => debug!("Stopped by controll flow")
+                )
+            }
+            .in_current_span(),
+        );
 
         client.join(self.target_channel.to_owned()).unwrap();
 
@@ -0,0 +1,578 @@
+mod subscriptions;
+
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+use futures_util::{select, FutureExt, SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc::Sender, watch};
+use tokio::time;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{debug, info, trace, warn};
+use twitch_api2::twitch_oauth2::{TwitchToken, UserToken};
+
+use crate::sequencer::QueueEvent;
+
+use super::triggers::{NamedTriggerEvent, TriggerEvent, TriggerSource};
+pub use subscriptions::required_scopes;
+use subscriptions::{SubscriptionType, SUBSCRIPTION_TYPES};
+
+pub const TWITCH_EVENTSUB_WEBSOCKET_URL: &str = "wss://eventsub.wss.twitch.tv/ws";
+pub const TWITCH_HELIX_URL: &str = "https://api.twitch.tv/helix";
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const KEEPALIVE_GRACE: Duration = Duration::from_secs(5);
+const RECENT_MESSAGE_IDS: usize = 100;
+
+type EventSubResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+type EventSubStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Debug, Clone)]
+pub struct TwitchEventSub {
+    websocket_url: String,
+    helix_url: String,
+    access_token: String,
+    client_id: String,
+    broadcaster_user_id: String,
+    trigger_events: HashMap<String, Box<dyn TriggerEvent>>,
+}
+
+struct Session {
+    id: String,
+    keepalive_timeout: Duration,
+}
+
+/// Ids of the latest notifications, Twitch may send one again e.g. around a reconnect
+#[derive(Default)]
+struct RecentMessages {
+    ids: VecDeque<String>,
+}
+
+impl RecentMessages {
+    /// False when the id was seen recently
+    fn insert(&mut self, id: &str) -> bool {
+        if self.ids.iter().any(|seen| seen == id) {
+            return false;
+        }
+
+        if self.ids.len() == RECENT_MESSAGE_IDS {
+            self.ids.pop_front();
+        }
+        self.ids.push_back(String::from(id));
+
+        true
+    }
+}
+
+enum SessionEnd {
+    Reconnect(String),
+    Lost,
+    Stopped, // The sequencer is no longer taking events
+}
+
+impl TwitchEventSub {
+    /// `websocket_url` and `helix_url` can point at a local mock such as `twitch event websocket start-server`
+    pub fn new(
+        websocket_url: String,
+        helix_url: String,
+        access_token: String,
+        client_id: String,
+        broadcaster_user_id: String,
+    ) -> Self {
+        let mut trigger_events: HashMap<String, Box<dyn TriggerEvent>> = HashMap::new();
+
+        for subscription in SUBSCRIPTION_TYPES.iter() {
+            trigger_events.insert(
+                String::from(subscription.name),
+                Box::new(NamedTriggerEvent::new(
+                    String::from(subscription.friendly_name),
+                    subscription
+                        .payload_fields
+                        .iter()
+                        .map(|field| String::from(*field))
+                        .collect(),
+                )),
+            );
+        }
+
+        TwitchEventSub {
+            websocket_url,
+            helix_url,
+            access_token,
+            client_id,
+            broadcaster_user_id,
+            trigger_events,
+        }
+    }
+
+    pub fn from_user_token(
+        websocket_url: String,
+        helix_url: String,
+        user_token: &UserToken,
+    ) -> Self {
+        TwitchEventSub::new(
+            websocket_url,
+            helix_url,
+            String::from(user_token.token().secret()),
+            String::from(user_token.client_id().as_str()),
+            String::from(user_token.user_id.as_str()),
+        )
+    }
+
+    /// Overrides the broadcaster from the user token, the mock server uses made up user ids
+    pub fn set_broadcaster_user_id(&mut self, broadcaster_user_id: String) {
+        self.broadcaster_user_id = broadcaster_user_id;
+    }
+
+    async fn run(&self, send_trigger: &Sender<QueueEvent>) {
+        let http = reqwest::Client::new();
+        let mut recent = RecentMessages::default();
+        let (mut ws_stream, mut session) = self.connect_until_welcomed().await;
+        self.create_subscriptions(&http, &session.id).await;
+
+        loop {
+            match read_session(&mut ws_stream, &session, send_trigger, &mut recent).await {
+                SessionEnd::Reconnect(reconnect_url) => {
+                    info!("EventSub asked to reconnect to {}", reconnect_url);
+
+                    // Subscriptions move to the new session, the old one keeps delivering
+                    // notifications until the new one is welcomed and is closed after
+                    let welcomed = {
+                        let mut new_session = Box::pin(welcome(&reconnect_url).fuse());
+                        let mut old_session = Box::pin(
+                            read_session(&mut ws_stream, &session, send_trigger, &mut recent)
+                                .fuse(),
+                        );
+
+                        select! {
+                            welcomed = new_session => Some(welcomed),
+                            end = old_session => match end {
+                                SessionEnd::Stopped => None,
+                                _ => Some((&mut new_session).await),
+                            },
+                        }
+                    };
+
+                    let welcomed = match welcomed {
+                        Some(welcomed) => welcomed,
+                        None => {
+                            warn!("EventSub could not queue an event as the sequencer has stopped");
+                            ws_stream.close(None).await.ok();
+                            return;
+                        }
+                    };
+
+                    match welcomed {
+                        Ok((new_stream, new_session)) => {
+                            ws_stream.close(None).await.ok();
+                            ws_stream = new_stream;
+                            session = new_session;
+                        }
+                        Err(e) => {
+                            warn!("EventSub reconnect failed: {}", e);
+                            let (new_stream, new_session) = self.connect_until_welcomed().await;
+                            ws_stream = new_stream;
+                            session = new_session;
+                            self.create_subscriptions(&http, &session.id).await;
+                        }
+                    }
+                }
+                SessionEnd::Lost => {
+                    warn!("EventSub session lost, reconnecting");
+                    let (new_stream, new_session) = self.connect_until_welcomed().await;
+                    ws_stream = new_stream;
+                    session = new_session;
+                    self.create_subscriptions(&http, &session.id).await;
+                }
+                SessionEnd::Stopped => {
+                    warn!("EventSub could not queue an event as the sequencer has stopped");
+                    ws_stream.close(None).await.ok();
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn connect_until_welcomed(&self) -> (EventSubStream, Session) {
+        loop {
+            match welcome(&self.websocket_url).await {
+                Ok(connection) => return connection,
+                Err(e) => warn!("EventSub could not connect: {}", e),
+            }
+
+            time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn create_subscriptions(&self, http: &reqwest::Client, session_id: &str) {
+        for subscription in SUBSCRIPTION_TYPES.iter() {
+            if let Err(e) = self
+                .create_subscription(http, session_id, subscription)
+                .await
+            {
+                warn!(
+                    "EventSub could not subscribe to {}: {}",
+                    subscription.name, e
+                );
+            }
+        }
+    }
+
+    async fn create_subscription(
+        &self,
+        http: &reqwest::Client,
+        session_id: &str,
+        subscription: &SubscriptionType,
+    ) -> EventSubResult<()> {
+        let body = json!({
+            "type": subscription.name,
+            "version": subscription.version,
+            "condition": subscription.condition(&self.broadcaster_user_id),
+            "transport": {
+                "method": "websocket",
+                "session_id": session_id,
+            },
+        });
+
+        let response = http
+            .post(format!("{}/eventsub/subscriptions", self.helix_url))
+            .bearer_auth(&self.access_token)
+            .header("Client-Id", &self.client_id)
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(format!("{} {}", response.status(), response.text().await?).into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Connects and waits for the session_welcome message
+async fn welcome(url: &str) -> EventSubResult<(EventSubStream, Session)> {
+    let (mut ws_stream, _) = connect_async(url).await?;
+
+    while let Some(msg) = ws_stream.next().await {
+        if let Message::Text(text) = msg? {
+            let message: Value = serde_json::from_str(&text)?;
+
+            if message_type(&message) == "session_welcome" {
+                let session = &message["payload"]["session"];
+                let keepalive = session["keepalive_timeout_seconds"].as_u64().unwrap_or(10);
+
+                return Ok((
+                    ws_stream,
+                    Session {
+                        id: String::from(
+                            session["id"].as_str().ok_or("Welcome had no session id")?,
+                        ),
+                        keepalive_timeout: Duration::from_secs(keepalive),
+                    },
+                ));
+            }
+        }
+    }
+
+    Err("EventSub closed before welcoming".into())
+}
+
+/// Forwards notifications until the session has to be replaced
+async fn read_session(
+    ws_stream: &mut EventSubStream,
+    session: &Session,
+    send_trigger: &Sender<QueueEvent>,
+    recent: &mut RecentMessages,
+) -> SessionEnd {
+    loop {
+        // Twitch sends a keepalive whenever it has had nothing else to send
+        let msg = match time::timeout(
+            session.keepalive_timeout + KEEPALIVE_GRACE,
+            ws_stream.next(),
+        )
+        .await
+        {
+            Ok(Some(Ok(msg))) => msg,
+            Ok(Some(Err(e))) => {
+                warn!("EventSub errored: {}", e);
+                return SessionEnd::Lost;
+            }
+            Ok(None) | Err(_) => return SessionEnd::Lost,
+        };
+
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Ping(data) => {
+                ws_stream.send(Message::Pong(data)).await.ok();
+                continue;
+            }
+            Message::Close(_) => return SessionEnd::Lost,
+            _ => continue,
+        };
+        trace!(%text, "Received EventSub message");
+
+        let message: Value = match serde_json::from_str(&text) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("EventSub sent invalid JSON: {}", e);
+                continue;
+            }
+        };
+
+        match message_type(&message) {
+            "notification" => {
+                if let Some(id) = message["metadata"]["message_id"].as_str() {
+                    if !recent.insert(id) {
+                        debug!("EventSub sent notification {} again", id);
+                        continue;
+                    }
+                }
+
+                let subscription_type = message["payload"]["subscription"]["type"]
+                    .as_str()
+                    .unwrap_or_default();
+
+                let sent = send_trigger
+                    .send(QueueEvent {
+                        trigger_source: crate::triggers::TriggerSource::TwitchEventSub,
+                        trigger_event_id: String::from(subscription_type),
+                        payload: message["payload"]["event"].clone(),
+                    })
+                    .await;
+
+                if sent.is_err() {
+                    return SessionEnd::Stopped;
+                }
+            }
+            "session_reconnect" => {
+                if let Some(url) = message["payload"]["session"]["reconnect_url"].as_str() {
+                    return SessionEnd::Reconnect(String::from(url));
+                }
+            }
+            "revocation" => warn!(
+                "EventSub revoked {}",
+                message["payload"]["subscription"]["type"]
+            ),
+            _ => {} // session_keepalive only resets the timeout
+        }
+    }
+}
+
+fn message_type(message: &Value) -> &str {
+    message["metadata"]["message_type"]
+        .as_str()
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl TriggerSource for TwitchEventSub {
+    async fn watch(
+        &self,
+        send_trigger: Sender<QueueEvent>,
+        mut watcher: watch::Receiver<()>,
+    ) -> Result<(), Box<dyn Error>> {
+        select!(
+            _x = self.run(&send_trigger).fuse() => info!("EventSub stopped"),
+            _y = watcher.changed().fuse() => debug!("EventSub stopped by controll flow")
+        );
+
+        return Ok(());
+    }
+
+    fn get_events(&self) -> &HashMap<String, Box<dyn TriggerEvent>> {
+        &self.trigger_events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::accept_async;
+
+    use super::*;
+
+    fn welcome_message(session_id: &str) -> Message {
+        Message::text(
+            json!({
+                "metadata": { "message_type": "session_welcome" },
+                "payload": {
+                    "session": { "id": session_id, "keepalive_timeout_seconds": 10 }
+                }
+            })
+            .to_string(),
+        )
+    }
+
+    fn follow_notification(message_id: &str, user_name: &str) -> Message {
+        Message::text(
+            json!({
+                "metadata": { "message_type": "notification", "message_id": message_id },
+                "payload": {
+                    "subscription": { "type": "channel.follow" },
+                    "event": { "user_name": user_name }
+                }
+            })
+            .to_string(),
+        )
+    }
+
+    fn reconnect_message(url: &str) -> Message {
+        Message::text(
+            json!({
+                "metadata": { "message_type": "session_reconnect" },
+                "payload": { "session": { "id": "first", "reconnect_url": url } }
+            })
+            .to_string(),
+        )
+    }
+
+    /// A local stand in for EventSub which welcomes, notifies, then moves the session to a second connection
+    #[tokio::test]
+    async fn notifications_reach_the_queue_across_a_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let reconnect_url = url.clone();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut first = accept_async(stream).await.unwrap();
+            first.send(welcome_message("first")).await.unwrap();
+            first
+                .send(follow_notification("1", "before"))
+                .await
+                .unwrap();
+            first.send(reconnect_message(&reconnect_url)).await.unwrap();
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut second = accept_async(stream).await.unwrap();
+            second.send(welcome_message("second")).await.unwrap();
+            second
+                .send(follow_notification("2", "after"))
+                .await
+                .unwrap();
+
+            // Both stay open until the test ends
+            future::pending::<()>().await;
+            drop((first, second));
+        });
+
+        // Nothing listens on the helix url so creating subscriptions fails straight away
+        let event_sub = TwitchEventSub::new(
+            url,
+            String::from("http://127.0.0.1:1"),
+            String::from("token"),
+            String::from("client"),
+            String::from("1234"),
+        );
+        let (sender, mut reciever) = mpsc::channel(10);
+        let (stop_sender, stop_reciever) = watch::channel(());
+
+        let collect = async {
+            let before = reciever.recv().await.unwrap();
+            let after = reciever.recv().await.unwrap();
+            stop_sender.send(()).unwrap();
+            (before, after)
+        };
+
+        let (watched, (before, after)) = time::timeout(
+            Duration::from_secs(5),
+            future::join(event_sub.watch(sender, stop_reciever), collect),
+        )
+        .await
+        .expect("EventSub did not deliver both notifications");
+        server.abort();
+
+        assert!(watched.is_ok());
+        assert!(matches!(
+            before.trigger_source,
+            crate::triggers::TriggerSource::TwitchEventSub
+        ));
+        assert_eq!(before.trigger_event_id, "channel.follow");
+        assert_eq!(before.payload["user_name"], "before");
+        assert_eq!(after.trigger_event_id, "channel.follow");
+        assert_eq!(after.payload["user_name"], "after");
+    }
+
+    /// Notifications sent on the old session while the new one connects still arrive,
+    /// and one Twitch sends again on the new session only plays once
+    #[tokio::test]
+    async fn old_session_is_read_until_the_new_one_is_welcomed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let reconnect_url = url.clone();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut first = accept_async(stream).await.unwrap();
+            first.send(welcome_message("first")).await.unwrap();
+            first.send(reconnect_message(&reconnect_url)).await.unwrap();
+            first
+                .send(follow_notification("1", "during"))
+                .await
+                .unwrap();
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut second = accept_async(stream).await.unwrap();
+            second.send(welcome_message("second")).await.unwrap();
+            second
+                .send(follow_notification("1", "during"))
+                .await
+                .unwrap();
+            second
+                .send(follow_notification("2", "after"))
+                .await
+                .unwrap();
+
+            future::pending::<()>().await;
+            drop((first, second));
+        });
+
+        let event_sub = TwitchEventSub::new(
+            url,
+            String::from("http://127.0.0.1:1"),
+            String::from("token"),
+            String::from("client"),
+            String::from("1234"),
+        );
+        let (sender, mut reciever) = mpsc::channel(10);
+        let (stop_sender, stop_reciever) = watch::channel(());
+
+        let collect = async {
+            let during = reciever.recv().await.unwrap();
+            let after = reciever.recv().await.unwrap();
+            stop_sender.send(()).unwrap();
+            (during, after)
+        };
+
+        let (_, (during, after)) = time::timeout(
+            Duration::from_secs(5),
+            future::join(event_sub.watch(sender, stop_reciever), collect),
+        )
+        .await
+        .expect("EventSub did not deliver both notifications");
+        server.abort();
+
+        assert_eq!(during.payload["user_name"], "during");
+        assert_eq!(after.payload["user_name"], "after");
+    }
+
+    #[test]
+    fn recent_messages_forget_the_oldest() {
+        let mut recent = RecentMessages::default();
+
+        assert!(recent.insert("0"));
+        assert!(!recent.insert("0"));
+
+        for id in 1..=RECENT_MESSAGE_IDS {
+            assert!(recent.insert(&id.to_string()));
+        }
+        assert!(recent.insert("0"));
+    }
+}
@@ -0,0 +1,157 @@
+use serde_json::{json, Value};
+use twitch_api2::twitch_oauth2::Scope;
+
+pub struct SubscriptionType {
+    pub name: &'static str,
+    pub friendly_name: &'static str,
+    pub version: &'static str,
+    pub condition: Condition,
+    pub payload_fields: &'static [&'static str],
+}
+
+pub enum Condition {
+    Broadcaster,
+    BroadcasterAndModerator,
+    ToBroadcaster,
+}
+
+impl SubscriptionType {
+    pub fn condition(&self, broadcaster_user_id: &str) -> Value {
+        match self.condition {
+            Condition::Broadcaster => json!({ "broadcaster_user_id": broadcaster_user_id }),
+            Condition::BroadcasterAndModerator => json!({
+                "broadcaster_user_id": broadcaster_user_id,
+                "moderator_user_id": broadcaster_user_id,
+            }),
+            Condition::ToBroadcaster => json!({ "to_broadcaster_user_id": broadcaster_user_id }),
+        }
+    }
+}
+
+pub static SUBSCRIPTION_TYPES: [SubscriptionType; 16] = [
+    SubscriptionType {
+        name: "channel.channel_points_custom_reward_redemption.add",
+        friendly_name: "Channel Point Redemption",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["user_name", "user_input", "reward"],
+    },
+    SubscriptionType {
+        name: "channel.cheer",
+        friendly_name: "Cheer",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["user_name", "bits", "message", "is_anonymous"],
+    },
+    SubscriptionType {
+        name: "channel.follow",
+        friendly_name: "Follow",
+        version: "2",
+        condition: Condition::BroadcasterAndModerator,
+        payload_fields: &["user_name"],
+    },
+    SubscriptionType {
+        name: "channel.subscribe",
+        friendly_name: "Subscription",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["user_name", "tier", "is_gift"],
+    },
+    SubscriptionType {
+        name: "channel.subscription.gift",
+        friendly_name: "Gift Subscriptions",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["user_name", "total", "tier", "is_anonymous"],
+    },
+    SubscriptionType {
+        name: "channel.raid",
+        friendly_name: "Raid",
+        version: "1",
+        condition: Condition::ToBroadcaster,
+        payload_fields: &["from_broadcaster_user_name", "viewers"],
+    },
+    SubscriptionType {
+        name: "channel.hype_train.begin",
+        friendly_name: "Hype Train Begin",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["level", "total", "goal"],
+    },
+    SubscriptionType {
+        name: "channel.hype_train.progress",
+        friendly_name: "Hype Train Progress",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["level", "total", "goal"],
+    },
+    SubscriptionType {
+        name: "channel.hype_train.end",
+        friendly_name: "Hype Train End",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["level", "total"],
+    },
+    SubscriptionType {
+        name: "channel.poll.begin",
+        friendly_name: "Poll Begin",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["title", "choices"],
+    },
+    SubscriptionType {
+        name: "channel.poll.progress",
+        friendly_name: "Poll Progress",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["title", "choices"],
+    },
+    SubscriptionType {
+        name: "channel.poll.end",
+        friendly_name: "Poll End",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["title", "choices", "status"],
+    },
+    SubscriptionType {
+        name: "channel.prediction.begin",
+        friendly_name: "Prediction Begin",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["title", "outcomes"],
+    },
+    SubscriptionType {
+        name: "channel.prediction.progress",
+        friendly_name: "Prediction Progress",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["title", "outcomes"],
+    },
+    SubscriptionType {
+        name: "channel.prediction.lock",
+        friendly_name: "Prediction Lock",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["title", "outcomes"],
+    },
+    SubscriptionType {
+        name: "channel.prediction.end",
+        friendly_name: "Prediction End",
+        version: "1",
+        condition: Condition::Broadcaster,
+        payload_fields: &["title", "outcomes", "winning_outcome_id", "status"],
+    },
+];
+
+/// Scopes the user token needs for every subscription type
+pub fn required_scopes() -> Vec<Scope> {
+    vec![
+        Scope::ChannelReadRedemptions,
+        Scope::BitsRead,
+        Scope::parse("moderator:read:followers"),
+        Scope::ChannelReadSubscriptions,
+        Scope::ChannelReadHypeTrain,
+        Scope::ChannelReadPolls,
+        Scope::ChannelReadPredictions,
+    ]
+}
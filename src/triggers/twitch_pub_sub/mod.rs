@@ -12,9 +12,8 @@ use tokio::sync::mpsc::Sender;
 use tokio::sync::watch;
 use tokio_tungstenite::tungstenite::protocol::{Message, WebSocketConfig};
 use tokio_tungstenite::{connect_async_tls_with_config, Connector};
-use twitch_api2::twitch_oauth2::{
-    url, AccessToken, ClientId, ClientSecret, RefreshToken, Scope, TwitchToken, UserToken,
-};
+use tracing::{debug, info, trace};
+use twitch_api2::twitch_oauth2::{TwitchToken, UserToken};
 use twitch_api2::{pubsub, TWITCH_PUBSUB_URL};
 
 #[derive(Debug, Clone)]
@@ -23,135 +22,16 @@ pub struct TwitchPubSub {
     user_token: UserToken,
     trigger_events: HashMap<String, Box<dyn TriggerEvent>>,
 }
-use std::fs::OpenOptions;
-use std::io::prelude::*;
 
 use super::triggers::TriggerEvent;
 
 impl TwitchPubSub {
-    pub async fn new(target_channel: &'static str) -> Result<TwitchPubSub, Box<dyn Error>> {
-        let user_token = TwitchPubSub::get_user_token().await?;
-        Ok(TwitchPubSub {
+    pub fn new(target_channel: &'static str, user_token: UserToken) -> TwitchPubSub {
+        TwitchPubSub {
             target_channel,
             user_token,
             trigger_events: HashMap::new(),
-        })
-    }
-
-    async fn get_user_token() -> Result<UserToken, Box<dyn Error>> {
-        let twitch_token = std::env::var_os("TWITCH_TOKEN");
-        let refresh_token = std::env::var_os("TWITCH_REFRESH_TOKEN");
-        let secret = std::env::var_os("TWITCH_SECRET")
-            .unwrap()
-            .into_string()
-            .unwrap();
-        let client_id = std::env::var_os("TWITCH_CLIENT_ID");
-
-        if twitch_token.is_none() || refresh_token.is_none() {
-            return Ok(TwitchPubSub::auth_flow().await.unwrap());
         }
-
-        let (twitch_token, _, refresh_token) = twitch_api2::twitch_oauth2::refresh_token(
-            &reqwest::Client::builder()
-                .redirect(reqwest::redirect::Policy::none())
-                .build()?,
-            &RefreshToken::new(refresh_token.unwrap().into_string().unwrap()),
-            &ClientId::new(client_id.unwrap().into_string().unwrap()),
-            &ClientSecret::new(secret.clone()),
-        )
-        .await?;
-
-        let user_token = UserToken::from_existing(
-            &reqwest::Client::builder()
-                .redirect(reqwest::redirect::Policy::none())
-                .build()?,
-            AccessToken::new(twitch_token.into_string()),
-            RefreshToken::new(refresh_token.unwrap().into_string()),
-            ClientSecret::new(secret),
-        )
-        .await;
-
-        let user_token = user_token.unwrap();
-
-        println!("{:?}", user_token.is_elapsed());
-        Ok(user_token)
-    }
-
-    async fn auth_flow() -> Result<UserToken, Box<dyn Error>> {
-        let mut builder = UserToken::builder(
-            ClientId::from(std::env::var("TWITCH_CLIENT_ID").unwrap()),
-            ClientSecret::from(std::env::var("TWITCH_SECRET").unwrap()),
-            url::Url::parse("http://localhost")?,
-        );
-
-        builder.add_scope(Scope::ChannelReadRedemptions);
-
-        let (url, _) = builder.generate_url();
-
-        println!("Go to this page: {}", url);
-
-        let input = rpassword::prompt_password(
-            "Paste in the resulting adress after authenticating (input hidden): ",
-        )?;
-
-        println!("{}", input);
-
-        let u = url::Url::parse(&input)?;
-
-        let map: std::collections::HashMap<_, _> = u.query_pairs().collect();
-
-        let user_token;
-        match (map.get("state"), map.get("code")) {
-            (Some(state), Some(code)) => {
-                user_token = builder
-                    .get_user_token(
-                        &reqwest::Client::builder()
-                            .redirect(reqwest::redirect::Policy::none())
-                            .build()?,
-                        state,
-                        code,
-                    )
-                    .await?;
-                println!("Got token: {:?}", user_token);
-            }
-            _ => match (map.get("error"), map.get("error_description")) {
-                (
-                    std::option::Option::Some(error),
-                    std::option::Option::Some(error_description),
-                ) => {
-                    panic!(
-                        "twitch errored with error: {} - {}",
-                        error, error_description
-                    );
-                }
-                _ => panic!("invalid url passed"),
-            },
-        };
-
-        // TODO: Be Naughty and save straight into .env
-        let mut file = OpenOptions::new()
-            .write(true)
-            .append(true)
-            .create(true)
-            .open("./.env")
-            .unwrap();
-
-        writeln!(
-            file,
-            "{}",
-            format!("TWITCH_TOKEN=\"{}\"", user_token.token().secret())
-        )?;
-
-        writeln!(
-            file,
-            "{}",
-            format!(
-                "TWITCH_REFRESH_TOKEN=\"{}\"",
-                user_token.clone().refresh_token.unwrap().into_string()
-            )
-        )?;
-
-        Ok(user_token)
     }
 }
 
@@ -182,20 +62,20 @@ impl TriggerSource for TwitchPubSub {
         )
         .await
         .expect("Failed to connect");
-        println!("WebSocket handshake has been successfully completed");
+        info!("Connected to PubSub");
 
         ws_stream.send(Message::text(&command)).await?;
 
         let fut = ws_stream.for_each(|msg| {
             let msg = msg.unwrap();
-            println!("{}", msg);
+            trace!(%msg, "Received PubSub message");
             future::ready(())
         });
 
         select!(
-            _x = fut.fuse() => println!("ws_stream stopped"),
+            _x = fut.fuse() => info!("PubSub connection closed"),
             _y = watcher.changed().fuse() => {
-                println!("ws_stream_killed by watcher");
+                debug!("PubSub stopped by controll flow");
             }
         );
 
@@ -0,0 +1,207 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::{collections::HashMap, error::Error};
+
+use async_trait::async_trait;
+use tokio::sync::{mpsc::Sender, watch};
+use tracing::{debug, info};
+use warp::http::StatusCode;
+use warp::hyper::body::Bytes;
+use warp::Filter;
+
+use crate::sequencer::QueueEvent;
+
+use super::triggers::{NamedTriggerEvent, TriggerEvent, TriggerSource};
+
+const SECRET_HEADER: &str = "x-webhook-secret";
+
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    port: u16,
+    secret: Option<String>,
+    trigger_events: HashMap<String, Box<dyn TriggerEvent>>,
+}
+
+impl Webhook {
+    pub fn new(port: u16, secret: Option<String>) -> Self {
+        Webhook {
+            port,
+            secret,
+            trigger_events: HashMap::new(),
+        }
+    }
+
+    pub fn add_event(&mut self, id: String, name: String, payload_fields: Vec<String>) {
+        self.trigger_events
+            .insert(id, Box::new(NamedTriggerEvent::new(name, payload_fields)));
+    }
+}
+
+struct RequestContext {
+    send_trigger: Sender<QueueEvent>,
+    secret: Option<String>,
+    event_ids: Vec<String>,
+}
+
+async fn handle_trigger(
+    event_id: String,
+    secret: Option<String>,
+    body: Bytes,
+    context: Arc<RequestContext>,
+) -> Result<StatusCode, warp::Rejection> {
+    if let Some(expected) = &context.secret {
+        if !secret.map_or(false, |secret| crate::secret::matches(&secret, expected)) {
+            return Ok(StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    if !context.event_ids.contains(&event_id) {
+        return Ok(StatusCode::NOT_FOUND);
+    }
+
+    let payload = if body.is_empty() {
+        serde_json::Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(payload) => payload,
+            Err(_) => return Ok(StatusCode::BAD_REQUEST),
+        }
+    };
+
+    let event = QueueEvent {
+        trigger_source: crate::triggers::TriggerSource::Webhook,
+        trigger_event_id: event_id,
+        payload,
+    };
+
+    match context.send_trigger.send(event).await {
+        Ok(_) => Ok(StatusCode::ACCEPTED),
+        Err(_) => Ok(StatusCode::SERVICE_UNAVAILABLE),
+    }
+}
+
+/// `POST /trigger/{event id}` with the JSON payload as the body
+fn routes(
+    context: Arc<RequestContext>,
+) -> impl Filter<Extract = (StatusCode,), Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path!("trigger" / String))
+        .and(warp::header::optional::<String>(SECRET_HEADER))
+        .and(warp::body::bytes())
+        .and(warp::any().map(move || context.clone()))
+        .and_then(handle_trigger)
+}
+
+#[async_trait]
+impl TriggerSource for Webhook {
+    async fn watch(
+        &self,
+        send_trigger: Sender<QueueEvent>,
+        mut watcher: watch::Receiver<()>,
+    ) -> Result<(), Box<dyn Error>> {
+        let context = Arc::new(RequestContext {
+            send_trigger,
+            secret: self.secret.clone(),
+            event_ids: self.trigger_events.keys().cloned().collect(),
+        });
+
+        let address = SocketAddr::from(([127, 0, 0, 1], self.port));
+        let (address, server) =
+            warp::serve(routes(context)).try_bind_with_graceful_shutdown(address, async move {
+                watcher.changed().await.ok();
+            })?;
+
+        info!("Webhook listening on {}", address);
+        server.await;
+        debug!("Webhook stopped by controll flow");
+
+        return Ok(());
+    }
+
+    fn get_events(&self) -> &HashMap<String, Box<dyn TriggerEvent>> {
+        &self.trigger_events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::sync::mpsc::{self, Receiver};
+
+    use super::*;
+
+    fn context(secret: Option<&str>) -> (Arc<RequestContext>, Receiver<QueueEvent>) {
+        let (send_trigger, receiver) = mpsc::channel(10);
+        let context = Arc::new(RequestContext {
+            send_trigger,
+            secret: secret.map(String::from),
+            event_ids: vec![String::from("doorbell")],
+        });
+
+        (context, receiver)
+    }
+
+    async fn post(
+        context: Arc<RequestContext>,
+        path: &str,
+        secret: Option<&str>,
+        body: &str,
+    ) -> StatusCode {
+        let mut request = warp::test::request().method("POST").path(path).body(body);
+        if let Some(secret) = secret {
+            request = request.header(SECRET_HEADER, secret);
+        }
+
+        request.reply(&routes(context)).await.status()
+    }
+
+    #[tokio::test]
+    async fn missing_or_wrong_secret_is_unauthorized() {
+        let (context, mut receiver) = context(Some("hunter2"));
+
+        let missing = post(context.clone(), "/trigger/doorbell", None, "{}").await;
+        let wrong = post(context, "/trigger/doorbell", Some("hunter3"), "{}").await;
+
+        assert_eq!(missing, StatusCode::UNAUTHORIZED);
+        assert_eq!(wrong, StatusCode::UNAUTHORIZED);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn unknown_event_is_not_found() {
+        let (context, mut receiver) = context(None);
+
+        let status = post(context, "/trigger/mailbox", None, "{}").await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn bad_json_is_a_bad_request() {
+        let (context, mut receiver) = context(None);
+
+        let status = post(context, "/trigger/doorbell", None, "{\"user\":").await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn valid_request_reaches_the_queue() {
+        let (context, mut receiver) = context(Some("hunter2"));
+
+        let status = post(
+            context,
+            "/trigger/doorbell",
+            Some("hunter2"),
+            r#"{"user_name": "viewer"}"#,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(event.trigger_event_id, "doorbell");
+        assert_eq!(event.payload, json!({ "user_name": "viewer" }));
+    }
+}
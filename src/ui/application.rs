@@ -1,9 +1,17 @@
 use super::panes::{
+    logs::{LogsMessage, LogsPane},
     sequences::{Sequences, SequencesMessage},
     settings::{Component, SettingsMessage, SettingsPane},
+    simulator::{SimulatorMessage, SimulatorPane},
+    variables::{VariablesMessage, VariablesPane},
 };
+use super::unsaved_dialog::{UnsavedDialog, UnsavedMessage};
+use crate::custom_widgets::modal::Modal;
 use crate::{
-    sequencer::device::DevicesCollection, settings::Settings, triggers::TriggerCollection,
+    control::Control,
+    sequencer::{device::DevicesCollection, variable_store::VariableStore},
+    settings::Settings,
+    triggers::{TriggerCollection, TriggerHandles},
 };
 use iced::{button, Button, Column, Command, Row, Text};
 use iced_native::{window, Event};
@@ -13,6 +21,9 @@ pub enum Application {
     Loading,
     Sequences(State),
     Settings(State),
+    Simulator(State),
+    Variables(State),
+    Logs(State),
     ShouldExit,
 }
 
@@ -20,45 +31,81 @@ pub enum Application {
 pub struct Buttons {
     sequences: button::State,
     settings: button::State,
+    simulator: button::State,
+    variables: button::State,
+    logs: button::State,
 }
 
 #[derive(Debug, Clone)]
 pub struct State {
     sequences: Sequences,
     settings: SettingsPane,
+    simulator: SimulatorPane,
+    variables: VariablesPane,
+    logs: LogsPane,
     buttons: Buttons,
+    unsaved_dialog: UnsavedDialog,
+    closing: Closing,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum Pane {
     Sequences,
+    Settings,
+    Simulator,
+    Variables,
+    Logs,
+}
+
+/// Progress of closing the window while there are unsaved changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Closing {
+    No,
+    Asking,
+    Saving, // Exits once every pane has saved
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum ChangePane {
     MoveToSequences,
     MoveToSettings,
+    MoveToSimulator,
+    MoveToVariables,
+    MoveToLogs,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     SequencesMessage(SequencesMessage),
     SettingsMessage(SettingsMessage),
+    SimulatorMessage(SimulatorMessage),
+    VariablesMessage(VariablesMessage),
+    LogsMessage(LogsMessage),
     EventOccurred(iced_native::Event),
     ChangePane(ChangePane),
+    UnsavedMessage(UnsavedMessage),
 }
 
 fn init(
     devices: DevicesCollection,
     triggers: TriggerCollection,
+    variable_store: VariableStore,
+    control: Control,
 ) -> (Sequences, Command<SequencesMessage>) {
-    Sequences::new((devices, triggers))
+    Sequences::new((devices, triggers, variable_store, control))
 }
 
 impl iced::Application for Application {
     type Executor = iced::executor::Default;
     type Message = Message;
-    type Flags = (DevicesCollection, TriggerCollection, Settings);
+    type Flags = (
+        DevicesCollection,
+        TriggerCollection,
+        Settings,
+        TriggerHandles,
+        VariableStore,
+        Control,
+    );
 
     type Theme = iced::Theme;
 
@@ -74,18 +121,35 @@ impl iced::Application for Application {
     }
 
     fn new(
-        flags: (DevicesCollection, TriggerCollection, Settings),
+        flags: (
+            DevicesCollection,
+            TriggerCollection,
+            Settings,
+            TriggerHandles,
+            VariableStore,
+            Control,
+        ),
     ) -> (Application, Command<Message>) {
-        let sequences = init(flags.0, flags.1);
+        let simulator = SimulatorPane::new(&flags.1, flags.3);
+        let variables = VariablesPane::new(flags.4.clone());
+        let sequences = init(flags.0, flags.1, flags.4, flags.5);
         let settings = SettingsPane::new(flags.2);
         (
             Application::Sequences(State {
                 sequences: sequences.0,
                 settings: settings.0,
+                simulator,
+                variables,
+                logs: LogsPane::new(),
                 buttons: Buttons {
                     sequences: button::State::new(),
                     settings: button::State::new(),
+                    simulator: button::State::new(),
+                    variables: button::State::new(),
+                    logs: button::State::new(),
                 },
+                unsaved_dialog: UnsavedDialog::default(),
+                closing: Closing::No,
             }),
             Command::batch(vec![
                 sequences.1.map(Message::SequencesMessage),
@@ -96,21 +160,41 @@ impl iced::Application for Application {
 
     fn should_exit(&self) -> bool {
         match self {
-            Application::Sequences(state) => state.sequences.should_exit(),
             Application::ShouldExit => true,
             _ => false,
         }
     }
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
-        // TODO: UI Event stream
-        iced_native::subscription::events().map(Message::EventOccurred)
+        let sequences = match self.state() {
+            Some(state) => state
+                .sequences
+                .subscription()
+                .map(Message::SequencesMessage),
+            None => iced::Subscription::none(),
+        };
+
+        // The log file is only read while it is shown
+        let logs = match self {
+            Application::Logs(state) => state.logs.subscription().map(Message::LogsMessage),
+            _ => iced::Subscription::none(),
+        };
+
+        iced::Subscription::batch(vec![
+            iced_native::subscription::events().map(Message::EventOccurred),
+            sequences,
+            logs,
+        ])
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::ChangePane(change_pane) => match self {
-                Application::Sequences(state) | Application::Settings(state) => match change_pane {
+                Application::Sequences(state)
+                | Application::Settings(state)
+                | Application::Simulator(state)
+                | Application::Variables(state)
+                | Application::Logs(state) => match change_pane {
                     ChangePane::MoveToSettings => {
                         *self = Application::Settings(state.clone());
                         return Command::none();
@@ -119,12 +203,36 @@ impl iced::Application for Application {
                         *self = Application::Sequences(state.clone());
                         return Command::none();
                     }
+                    ChangePane::MoveToSimulator => {
+                        let mut state = state.clone();
+                        state
+                            .simulator
+                            .add_configured_triggers(state.sequences.configured_triggers());
+                        *self = Application::Simulator(state);
+                        return Command::none();
+                    }
+                    ChangePane::MoveToVariables => {
+                        let mut state = state.clone();
+                        state.variables.refresh(state.sequences.sequence_names());
+                        *self = Application::Variables(state);
+                        return Command::none();
+                    }
+                    ChangePane::MoveToLogs => {
+                        let mut state = state.clone();
+                        let command = state.logs.tail().map(Message::LogsMessage);
+                        *self = Application::Logs(state);
+                        return command;
+                    }
                 },
                 _ => {}
             },
             _ => {}
         }
-        match self {
+        if let Some(command) = self.close(&message) {
+            return command;
+        }
+
+        let command = match self {
             Application::Loading => Command::none(),
 
             Application::Sequences(state) => match message {
@@ -138,43 +246,208 @@ impl iced::Application for Application {
                 Message::SettingsMessage(settings_message) => {
                     { state.settings.update(settings_message) }.map(Message::SettingsMessage)
                 }
-                Message::ChangePane(_) => Command::none(),
+                Message::SimulatorMessage(_)
+                | Message::VariablesMessage(_)
+                | Message::LogsMessage(_)
+                | Message::ChangePane(_)
+                | Message::UnsavedMessage(_) => Command::none(),
+            },
+
+            Application::Simulator(state) => match message {
+                Message::SimulatorMessage(simulator_message) => {
+                    state.simulator.update(simulator_message);
+                    Command::none()
+                }
+                Message::SequencesMessage(sequences_message) => {
+                    { state.sequences.update(sequences_message) }.map(Message::SequencesMessage)
+                }
+                Message::SettingsMessage(settings_message) => {
+                    { state.settings.update(settings_message) }.map(Message::SettingsMessage)
+                }
+                Message::EventOccurred(_)
+                | Message::VariablesMessage(_)
+                | Message::LogsMessage(_)
+                | Message::ChangePane(_)
+                | Message::UnsavedMessage(_) => Command::none(),
             },
 
+            Application::Variables(state) => match message {
+                Message::VariablesMessage(variables_message) => {
+                    state.variables.update(variables_message);
+                    Command::none()
+                }
+                Message::SequencesMessage(sequences_message) => {
+                    { state.sequences.update(sequences_message) }.map(Message::SequencesMessage)
+                }
+                Message::SettingsMessage(settings_message) => {
+                    { state.settings.update(settings_message) }.map(Message::SettingsMessage)
+                }
+                Message::EventOccurred(_)
+                | Message::SimulatorMessage(_)
+                | Message::LogsMessage(_)
+                | Message::ChangePane(_)
+                | Message::UnsavedMessage(_) => Command::none(),
+            },
+
+            // Sequences keep saving in the background when closing from here
             Application::Settings(state) => match message {
                 Message::SettingsMessage(settings_message) => {
-                    state.settings.update(settings_message)
+                    { state.settings.update(settings_message) }.map(Message::SettingsMessage)
                 }
-                Message::EventOccurred(event) => {
-                    if Event::Window(window::Event::CloseRequested) == event {
-                        *self = Application::ShouldExit
-                    }
-                    return Command::none();
+                Message::SequencesMessage(sequences_message) => {
+                    { state.sequences.update(sequences_message) }.map(Message::SequencesMessage)
                 }
-                _ => Command::none(),
-            }
-            .map(Message::SettingsMessage),
+                Message::EventOccurred(_)
+                | Message::SimulatorMessage(_)
+                | Message::VariablesMessage(_)
+                | Message::LogsMessage(_)
+                | Message::ChangePane(_)
+                | Message::UnsavedMessage(_) => Command::none(),
+            },
+            Application::Logs(state) => match message {
+                Message::LogsMessage(logs_message) => {
+                    state.logs.update(logs_message).map(Message::LogsMessage)
+                }
+                Message::SequencesMessage(sequences_message) => {
+                    { state.sequences.update(sequences_message) }.map(Message::SequencesMessage)
+                }
+                Message::SettingsMessage(settings_message) => {
+                    { state.settings.update(settings_message) }.map(Message::SettingsMessage)
+                }
+                Message::EventOccurred(_)
+                | Message::SimulatorMessage(_)
+                | Message::VariablesMessage(_)
+                | Message::ChangePane(_)
+                | Message::UnsavedMessage(_) => Command::none(),
+            },
             _ => Command::none(),
+        };
+
+        if let Some(state) = self.state() {
+            if state.closing == Closing::Saving && state.unsaved().is_empty() {
+                *self = Application::ShouldExit;
+            }
         }
+
+        command
     }
 
     fn view(&mut self) -> iced::Element<'_, Self::Message, iced::Renderer<Self::Theme>> {
-        let mut page: Column<_> = Column::new();
+        let (pane, state) = match self {
+            Application::Sequences(state) => (Pane::Sequences, state),
+            Application::Settings(state) => (Pane::Settings, state),
+            Application::Simulator(state) => (Pane::Simulator, state),
+            Application::Variables(state) => (Pane::Variables, state),
+            Application::Logs(state) => (Pane::Logs, state),
+            Application::Loading | Application::ShouldExit => return Column::new().into(),
+        };
+
+        let unsaved = state.unsaved();
+        let closing = state.closing != Closing::No;
+
+        let content = match pane {
+            Pane::Sequences => state.sequences.view().map(Message::SequencesMessage),
+            Pane::Settings => state.settings.view().map(Message::SettingsMessage),
+            Pane::Simulator => state.simulator.view().map(Message::SimulatorMessage),
+            Pane::Variables => state.variables.view().map(Message::VariablesMessage),
+            Pane::Logs => state.logs.view().map(Message::LogsMessage),
+        };
+
+        let page = Column::new().push(header(&mut state.buttons)).push(content);
+
+        let dialog = if closing {
+            Some(
+                state
+                    .unsaved_dialog
+                    .view(&unsaved)
+                    .map(Message::UnsavedMessage),
+            )
+        } else {
+            None
+        };
 
+        Modal::new(page).content(dialog).into()
+    }
+}
+
+impl Application {
+    fn state(&self) -> Option<&State> {
         match self {
-            Application::Loading => {}
-            Application::Sequences(state) => {
-                page = page.push(header(&mut state.buttons));
-                page = page.push(state.sequences.view().map(Message::SequencesMessage))
+            Application::Sequences(state)
+            | Application::Settings(state)
+            | Application::Simulator(state)
+            | Application::Variables(state)
+            | Application::Logs(state) => Some(state),
+            Application::Loading | Application::ShouldExit => None,
+        }
+    }
+
+    /// Handles the window closing and the unsaved changes dialog, from any pane
+    fn close(&mut self, message: &Message) -> Option<Command<Message>> {
+        let state = match self {
+            Application::Sequences(state)
+            | Application::Settings(state)
+            | Application::Simulator(state)
+            | Application::Variables(state)
+            | Application::Logs(state) => state,
+            Application::Loading | Application::ShouldExit => return None,
+        };
+
+        match message {
+            Message::EventOccurred(Event::Window(window::Event::CloseRequested)) => {
+                if state.unsaved().is_empty() {
+                    *self = Application::ShouldExit;
+                } else {
+                    state.closing = Closing::Asking;
+                }
+                Some(Command::none())
             }
-            Application::Settings(state) => {
-                page = page.push(header(&mut state.buttons));
-                page = page.push(state.settings.view().map(Message::SettingsMessage));
+            Message::UnsavedMessage(UnsavedMessage::Save) => {
+                state.closing = Closing::Saving;
+                state.unsaved_dialog.set_error(None);
+                Some(Command::batch(vec![
+                    state.sequences.save().map(Message::SequencesMessage),
+                    state.settings.save().map(Message::SettingsMessage),
+                ]))
             }
-            Application::ShouldExit => {}
+            Message::UnsavedMessage(UnsavedMessage::Discard) => {
+                *self = Application::ShouldExit;
+                Some(Command::none())
+            }
+            Message::UnsavedMessage(UnsavedMessage::Cancel) => {
+                state.closing = Closing::No;
+                state.unsaved_dialog.set_error(None);
+                Some(Command::none())
+            }
+            // Asks again rather than waiting on a save which is never coming, the pane still
+            // takes the message so it keeps its changes
+            Message::SequencesMessage(SequencesMessage::Saved(Err(error)))
+            | Message::SettingsMessage(SettingsMessage::Saved(Err(error)))
+                if state.closing == Closing::Saving =>
+            {
+                state.closing = Closing::Asking;
+                state
+                    .unsaved_dialog
+                    .set_error(Some(format!("Saving failed, {}", error)));
+                None
+            }
+            _ => None,
         }
+    }
+}
 
-        page.into()
+impl State {
+    /// Names of the panes with unsaved changes
+    fn unsaved(&self) -> Vec<&'static str> {
+        let mut unsaved = vec![];
+        if self.sequences.is_tainted() {
+            unsaved.push("Sequences");
+        }
+        if self.settings.is_tainted() {
+            unsaved.push("Settings");
+        }
+
+        unsaved
     }
 }
 
@@ -187,5 +460,17 @@ fn header(buttons: &mut Buttons) -> Row<Message> {
         .push(
             Button::new(&mut buttons.settings, Text::new("Settings"))
                 .on_press(Message::ChangePane(ChangePane::MoveToSettings)),
+        )
+        .push(
+            Button::new(&mut buttons.simulator, Text::new("Simulator"))
+                .on_press(Message::ChangePane(ChangePane::MoveToSimulator)),
+        )
+        .push(
+            Button::new(&mut buttons.variables, Text::new("Variables"))
+                .on_press(Message::ChangePane(ChangePane::MoveToVariables)),
+        )
+        .push(
+            Button::new(&mut buttons.logs, Text::new("Logs"))
+                .on_press(Message::ChangePane(ChangePane::MoveToLogs)),
         );
 }
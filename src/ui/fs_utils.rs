@@ -1,3 +1,5 @@
+use core::fmt;
+
 #[derive(Debug, Clone)]
 pub enum LoadError {
     FileError,
@@ -6,5 +8,15 @@ pub enum LoadError {
 
 #[derive(Debug, Clone)]
 pub enum SaveError {
+    FileError(String),
     FormatError(String),
 }
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::FileError(msg) => write!(f, "File error: {}", msg),
+            SaveError::FormatError(msg) => write!(f, "Format error: {}", msg),
+        }
+    }
+}
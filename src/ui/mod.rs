@@ -2,18 +2,36 @@ mod application;
 pub mod fs_utils;
 mod panes;
 mod sequence;
+mod unsaved_dialog;
 
 use iced::{Application, Settings as IcedSettings};
 
+use crate::control::Control;
 use crate::sequencer::device::DevicesCollection;
+use crate::sequencer::variable_store::VariableStore;
 use crate::settings::Settings;
 
 use crate::triggers::TriggerCollection;
+use crate::triggers::TriggerHandles;
 
-pub fn ui(devices: DevicesCollection, triggers: TriggerCollection, settings: Settings) {
+pub fn ui(
+    devices: DevicesCollection,
+    triggers: TriggerCollection,
+    settings: Settings,
+    trigger_handles: TriggerHandles,
+    variable_store: VariableStore,
+    control: Control,
+) {
     application::Application::run(IcedSettings {
         exit_on_close_request: false,
-        ..IcedSettings::with_flags((devices, triggers, settings))
+        ..IcedSettings::with_flags((
+            devices,
+            triggers,
+            settings,
+            trigger_handles,
+            variable_store,
+            control,
+        ))
     })
     .unwrap();
 }
@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use iced::{
+    scrollable, text_input, Checkbox, Column, Command, Element, Length, Row, Scrollable,
+    Subscription, Text, TextInput,
+};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::logging;
+
+const MAX_LINES: usize = 1000;
+const MAX_FIRST_READ: u64 = 256 * 1024; // Older lines are skipped when opening a large file
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Tails the log file, following it to the next one when it rolls over
+#[derive(Debug, Clone)]
+pub struct LogsPane {
+    file: Option<PathBuf>,
+    offset: u64, // How much of the file has been read
+    reading: bool,
+    lines: VecDeque<String>,
+    error: Option<String>,
+    filter: String,
+    filter_input: text_input::State,
+    follow: bool,
+    scroll: scrollable::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum LogsMessage {
+    Tick,
+    Read(Result<Tail, String>),
+    FilterChanged(String),
+    FollowToggled(bool),
+}
+
+#[derive(Debug, Clone)]
+pub struct Tail {
+    file: PathBuf,
+    offset: u64,
+    lines: Vec<String>,
+    restarted: bool, // A different file, or the same one truncated
+}
+
+impl LogsPane {
+    pub fn new() -> Self {
+        LogsPane {
+            file: None,
+            offset: 0,
+            reading: false,
+            lines: VecDeque::new(),
+            error: None,
+            filter: String::new(),
+            filter_input: text_input::State::new(),
+            follow: true,
+            scroll: scrollable::State::new(),
+        }
+    }
+
+    /// Reads whatever was logged since the last read
+    pub fn tail(&mut self) -> Command<LogsMessage> {
+        if self.reading {
+            return Command::none();
+        }
+
+        self.reading = true;
+        Command::perform(read_tail(self.file.clone(), self.offset), LogsMessage::Read)
+    }
+
+    pub fn subscription(&self) -> Subscription<LogsMessage> {
+        iced::time::every(POLL_INTERVAL).map(|_| LogsMessage::Tick)
+    }
+
+    pub fn update(&mut self, message: LogsMessage) -> Command<LogsMessage> {
+        match message {
+            LogsMessage::Tick => return self.tail(),
+            LogsMessage::Read(result) => {
+                self.reading = false;
+                match result {
+                    Ok(tail) => {
+                        if tail.restarted {
+                            self.lines.clear();
+                        }
+                        self.file = Some(tail.file);
+                        self.offset = tail.offset;
+                        self.error = None;
+
+                        let added = !tail.lines.is_empty();
+                        self.lines.extend(tail.lines);
+                        while self.lines.len() > MAX_LINES {
+                            self.lines.pop_front();
+                        }
+
+                        if added && self.follow {
+                            self.scroll.snap_to(1.0);
+                        }
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            LogsMessage::FilterChanged(filter) => self.filter = filter,
+            LogsMessage::FollowToggled(follow) => {
+                self.follow = follow;
+                if follow {
+                    self.scroll.snap_to(1.0);
+                }
+            }
+        }
+
+        Command::none()
+    }
+
+    pub fn view(&mut self) -> Element<LogsMessage> {
+        let filter = self.filter.to_lowercase();
+        let lines = self
+            .lines
+            .iter()
+            .filter(|line| filter.is_empty() || line.to_lowercase().contains(&filter))
+            .fold(
+                Scrollable::new(&mut self.scroll)
+                    .spacing(2)
+                    .height(Length::Fill),
+                |column, line| column.push(Text::new(line.clone()).size(16)),
+            );
+
+        let file = match &self.file {
+            Some(file) => file.display().to_string(),
+            None => String::from("No log file yet"),
+        };
+
+        let mut column = Column::new()
+            .spacing(10)
+            .padding(10)
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .push(Text::new("Logs").size(24))
+                    .push(
+                        TextInput::new(
+                            &mut self.filter_input,
+                            "Filter",
+                            &self.filter,
+                            LogsMessage::FilterChanged,
+                        )
+                        .padding(5)
+                        .width(Length::Units(300)),
+                    )
+                    .push(Checkbox::new(
+                        self.follow,
+                        "Follow",
+                        LogsMessage::FollowToggled,
+                    )),
+            )
+            .push(Text::new(file).size(16));
+
+        if let Some(error) = &self.error {
+            column = column.push(Text::new(format!("Could not read the log: {}", error)));
+        }
+
+        column.push(lines).into()
+    }
+}
+
+/// Reads the complete lines added to the newest log file after `offset` in `file`
+async fn read_tail(file: Option<PathBuf>, offset: u64) -> Result<Tail, String> {
+    let newest = logging::current_log_file().ok_or_else(|| String::from("No log file yet"))?;
+    let mut handle = File::open(&newest).await.map_err(|e| e.to_string())?;
+    let length = handle.metadata().await.map_err(|e| e.to_string())?.len();
+
+    let restarted = file.as_ref() != Some(&newest) || length < offset;
+    let start = if restarted {
+        length.saturating_sub(MAX_FIRST_READ)
+    } else {
+        offset
+    };
+
+    handle
+        .seek(SeekFrom::Start(start))
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut bytes = vec![];
+    handle
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // A partly written line is read again next time
+    let complete = bytes
+        .iter()
+        .rposition(|byte| *byte == b'\n')
+        .map_or(0, |i| i + 1);
+    let text = String::from_utf8_lossy(&bytes[..complete]);
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    if restarted && start > 0 && !lines.is_empty() {
+        lines.remove(0); // Started part way through a line
+    }
+
+    Ok(Tail {
+        file: newest,
+        offset: start + complete as u64,
+        lines,
+        restarted,
+    })
+}
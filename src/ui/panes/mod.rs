@@ -1,2 +1,5 @@
+pub mod logs;
 pub mod sequences;
 pub mod settings;
+pub mod simulator;
+pub mod variables;
@@ -0,0 +1,199 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::Local;
+use iced::{scrollable, Column, Element, Length, Row, Rule, Scrollable, Text};
+
+use crate::sequencer::progress::{Progress, Status};
+use crate::sequencer::reaction_sequence::ReactionSequence;
+use crate::sequencer::QueueEvent;
+
+const MAX_EVENTS: usize = 100;
+const MAX_ERRORS: usize = 50;
+
+/// What the listeners are doing, built from the progress the sequencer publishes
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+    sequences: Vec<ReactionSequence>,
+    trigger_sources: BTreeMap<String, Status>,
+    devices: BTreeMap<String, Status>,
+    events: VecDeque<(String, QueueEvent)>, // Newest first, with the time it arrived
+    pending: VecDeque<QueueEvent>,
+    playing: Vec<(String, Option<usize>)>, // Sequence id and active step
+    errors: VecDeque<(String, String)>,
+    paused: bool,
+    missed: u64,
+    events_scroll: scrollable::State,
+    errors_scroll: scrollable::State,
+}
+
+impl Dashboard {
+    pub fn new(
+        trigger_sources: Vec<String>,
+        devices: Vec<String>,
+        sequences: Vec<ReactionSequence>,
+    ) -> Self {
+        Dashboard {
+            sequences,
+            trigger_sources: trigger_sources
+                .into_iter()
+                .map(|id| (id, Status::Stopped))
+                .collect(),
+            devices: devices.into_iter().map(|id| (id, Status::Ready)).collect(),
+            events: VecDeque::new(),
+            pending: VecDeque::new(),
+            playing: vec![],
+            errors: VecDeque::new(),
+            paused: false,
+            missed: 0,
+            events_scroll: scrollable::State::new(),
+            errors_scroll: scrollable::State::new(),
+        }
+    }
+
+    pub fn update(&mut self, progress: Progress) {
+        match progress {
+            Progress::TriggerSource(id, status) => {
+                self.trigger_sources.insert(id, status);
+            }
+            Progress::Device(id, status) => {
+                self.devices.insert(id, status);
+            }
+            Progress::EventReceived(event) => {
+                self.pending.push_back(event.clone());
+                self.events.push_front((now(), event));
+                self.events.truncate(MAX_EVENTS);
+            }
+            Progress::EventTaken => {
+                self.pending.pop_front();
+            }
+            Progress::SequenceStarted(id, _, _) => self.playing.push((id, None)),
+            Progress::StepStarted(id, step) => {
+                if let Some(playing) = self.playing.iter_mut().find(|(playing, _)| playing == &id) {
+                    playing.1 = Some(step);
+                }
+            }
+            Progress::SequenceFinished(id) => self.playing.retain(|(playing, _)| playing != &id),
+            Progress::Paused(paused) => self.paused = paused,
+            Progress::Overlay(_) => {}
+            Progress::Error(error) => {
+                self.errors.push_front((now(), error));
+                self.errors.truncate(MAX_ERRORS);
+            }
+        }
+    }
+
+    /// Called when the subscription fell behind and updates were dropped
+    pub fn missed(&mut self, count: u64) {
+        self.missed += count;
+    }
+
+    pub fn view<'a, Message: 'a>(&'a mut self) -> Element<'a, Message> {
+        let sources = statuses("Trigger sources", &self.trigger_sources);
+        let devices = statuses("Devices", &self.devices);
+
+        let playing = self.playing.iter().fold(
+            Column::new()
+                .spacing(10)
+                .push(Text::new("Playing").size(24)),
+            |column, (id, active)| match self.sequences.iter().find(|sequence| &sequence.id == id) {
+                Some(sequence) => column.push(playing_sequence(sequence, *active)),
+                None => column.push(Text::new(id.clone())),
+            },
+        );
+
+        let pending = self.pending.iter().fold(
+            Column::new().spacing(5).push(
+                Text::new(format!(
+                    "Queue ({}){}",
+                    self.pending.len(),
+                    if self.paused { ", paused" } else { "" }
+                ))
+                .size(24),
+            ),
+            |column, event| column.push(Text::new(describe(event))),
+        );
+
+        let events = self.events.iter().fold(
+            Scrollable::new(&mut self.events_scroll)
+                .spacing(5)
+                .height(Length::Units(300))
+                .push(Text::new("Events").size(24)),
+            |column, (time, event)| column.push(Text::new(format!("{} {}", time, describe(event)))),
+        );
+
+        let mut errors = self.errors.iter().fold(
+            Scrollable::new(&mut self.errors_scroll)
+                .spacing(5)
+                .height(Length::Units(300))
+                .push(Text::new("Errors").size(24)),
+            |column, (time, error)| column.push(Text::new(format!("{} {}", time, error))),
+        );
+        if self.missed > 0 {
+            errors = errors.push(Text::new(format!(
+                "{} updates were missed while the dashboard was busy",
+                self.missed
+            )));
+        }
+
+        Column::new()
+            .spacing(20)
+            .push(
+                Row::new()
+                    .spacing(40)
+                    .push(sources)
+                    .push(devices)
+                    .push(pending),
+            )
+            .push(Rule::horizontal(5))
+            .push(playing)
+            .push(Rule::horizontal(5))
+            .push(
+                Row::new()
+                    .spacing(40)
+                    .push(Column::new().width(Length::FillPortion(2)).push(events))
+                    .push(Column::new().width(Length::FillPortion(1)).push(errors)),
+            )
+            .into()
+    }
+}
+
+fn statuses<'a, Message: 'a>(
+    title: &str,
+    statuses: &BTreeMap<String, Status>,
+) -> Column<'a, Message> {
+    statuses.iter().fold(
+        Column::new().spacing(5).push(Text::new(title).size(24)),
+        |column, (id, status)| column.push(Text::new(format!("{}: {}", id, status))),
+    )
+}
+
+/// The sequence's steps in a row, the active one marked with ">"
+fn playing_sequence<'a, Message: 'a>(
+    sequence: &ReactionSequence,
+    active: Option<usize>,
+) -> Row<'a, Message> {
+    sequence.sequence.iter().enumerate().fold(
+        Row::new()
+            .spacing(20)
+            .push(Text::new(sequence.name.clone()).width(Length::Units(200))),
+        |row, (i, step)| {
+            let marker = if active == Some(i) { "> " } else { "" };
+            row.push(Text::new(format!(
+                "{}{}.{}",
+                marker, step.device_id, step.device_action_id
+            )))
+        },
+    )
+}
+
+fn describe(event: &QueueEvent) -> String {
+    format!(
+        "{} {}",
+        event.trigger_source.as_str(),
+        event.trigger_event_id
+    )
+}
+
+fn now() -> String {
+    Local::now().format("%H:%M:%S").to_string()
+}
@@ -0,0 +1,167 @@
+use iced::{
+    button, pick_list, text_input, Button, Element, Length, PickList, Row, Text, TextInput,
+};
+
+use super::sequence::Sequence;
+
+const ANY: &str = "Any";
+const ENABLED: &str = "Enabled";
+const DISABLED: &str = "Disabled";
+
+/// Search box and filters narrowing which sequences the pane lists
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    search: String,
+    search_input: text_input::State,
+    trigger: Option<String>,
+    trigger_pick_list: pick_list::State<String>,
+    device: Option<String>,
+    device_pick_list: pick_list::State<String>,
+    tag: Option<String>,
+    tag_pick_list: pick_list::State<String>,
+    enabled: Option<bool>,
+    enabled_pick_list: pick_list::State<String>,
+    clear_button: button::State,
+}
+
+/// The values each filter can be set to, gathered from the pane
+#[derive(Debug, Clone, Default)]
+pub struct FilterOptions {
+    pub triggers: Vec<String>,
+    pub devices: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum FilterMessage {
+    SearchChanged(String),
+    TriggerSelected(String),
+    DeviceSelected(String),
+    TagSelected(String),
+    EnabledSelected(String),
+    Clear,
+}
+
+impl Filter {
+    pub fn is_active(&self) -> bool {
+        !self.search.is_empty()
+            || self.trigger.is_some()
+            || self.device.is_some()
+            || self.tag.is_some()
+            || self.enabled.is_some()
+    }
+
+    /// Search matches the name, description or a tag, ignoring case
+    pub fn matches(&self, sequence: &Sequence) -> bool {
+        let tags = sequence.tags();
+        let search = self.search.trim().to_lowercase();
+
+        let found = search.is_empty()
+            || sequence.name().to_lowercase().contains(&search)
+            || sequence.description().to_lowercase().contains(&search)
+            || tags.iter().any(|tag| tag.to_lowercase().contains(&search));
+
+        found
+            && self
+                .trigger
+                .as_ref()
+                .map_or(true, |trigger| &sequence.trigger_id() == trigger)
+            && self
+                .device
+                .as_ref()
+                .map_or(true, |device| sequence.uses_device(device))
+            && self.tag.as_ref().map_or(true, |tag| tags.contains(tag))
+            && self
+                .enabled
+                .map_or(true, |enabled| sequence.is_enabled() == enabled)
+    }
+
+    pub fn update(&mut self, message: FilterMessage) {
+        match message {
+            FilterMessage::SearchChanged(search) => self.search = search,
+            FilterMessage::TriggerSelected(trigger) => self.trigger = any_to_none(trigger),
+            FilterMessage::DeviceSelected(device) => self.device = any_to_none(device),
+            FilterMessage::TagSelected(tag) => self.tag = any_to_none(tag),
+            FilterMessage::EnabledSelected(enabled) => {
+                self.enabled = if enabled == ENABLED {
+                    Some(true)
+                } else if enabled == DISABLED {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            FilterMessage::Clear => {
+                self.search.clear();
+                self.trigger = None;
+                self.device = None;
+                self.tag = None;
+                self.enabled = None;
+            }
+        }
+    }
+
+    pub fn view(&mut self, options: FilterOptions) -> Element<FilterMessage> {
+        let enabled = self.enabled.map(|enabled| {
+            if enabled {
+                String::from(ENABLED)
+            } else {
+                String::from(DISABLED)
+            }
+        });
+
+        Row::new()
+            .spacing(20)
+            .push(
+                TextInput::new(
+                    &mut self.search_input,
+                    "Search",
+                    &self.search,
+                    FilterMessage::SearchChanged,
+                )
+                .padding(5)
+                .width(Length::Units(250)),
+            )
+            .push(Text::new("Trigger"))
+            .push(PickList::new(
+                &mut self.trigger_pick_list,
+                with_any(options.triggers),
+                self.trigger.clone(),
+                FilterMessage::TriggerSelected,
+            ))
+            .push(Text::new("Device"))
+            .push(PickList::new(
+                &mut self.device_pick_list,
+                with_any(options.devices),
+                self.device.clone(),
+                FilterMessage::DeviceSelected,
+            ))
+            .push(Text::new("Tag"))
+            .push(PickList::new(
+                &mut self.tag_pick_list,
+                with_any(options.tags),
+                self.tag.clone(),
+                FilterMessage::TagSelected,
+            ))
+            .push(PickList::new(
+                &mut self.enabled_pick_list,
+                with_any(vec![String::from(ENABLED), String::from(DISABLED)]),
+                enabled,
+                FilterMessage::EnabledSelected,
+            ))
+            .push(
+                Button::new(&mut self.clear_button, Text::new("Clear"))
+                    .on_press(FilterMessage::Clear),
+            )
+            .into()
+    }
+}
+
+fn with_any(mut options: Vec<String>) -> Vec<String> {
+    options.insert(0, String::from(ANY));
+    options
+}
+
+fn any_to_none(value: String) -> Option<String> {
+    Some(value).filter(|value| value != ANY)
+}
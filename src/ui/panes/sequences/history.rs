@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+
+use super::sequence::action::ActionMessage;
+use super::sequence::trigger::TriggerMessage;
+use super::sequence::SequenceMessage;
+
+const MAX_ENTRIES: usize = 100;
+
+/// Snapshots of the editor taken before each operation, for Ctrl+Z and Ctrl+Shift+Z
+#[derive(Debug, Clone)]
+pub struct History<T: Clone> {
+    undo: VecDeque<T>, // Oldest first so the oldest can be dropped cheaply
+    redo: Vec<T>,
+}
+
+impl<T: Clone> Default for History<T> {
+    fn default() -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: vec![],
+        }
+    }
+}
+
+impl<T: Clone> History<T> {
+    /// Call with the state as it is before an operation changes it
+    pub fn record(&mut self, current: &T) {
+        self.undo.push_back(current.clone());
+        if self.undo.len() > MAX_ENTRIES {
+            self.undo.pop_front();
+        }
+
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(current);
+
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(current);
+
+        Some(next)
+    }
+}
+
+/// Editor operations which can be undone, typing into text inputs is not one of them
+pub fn is_recorded(message: &SequenceMessage) -> bool {
+    match message {
+        SequenceMessage::TriggerMessage(trigger_message) => matches!(
+            trigger_message,
+            TriggerMessage::TriggerSelected(_) | TriggerMessage::TriggerEventSelected(_)
+        ),
+        SequenceMessage::ActionMessage(_, action_message) => matches!(
+            action_message,
+            ActionMessage::DeviceSelected(_)
+                | ActionMessage::DeviceActionSelected(_)
+                | ActionMessage::Duplicate
+                | ActionMessage::Delete
+        ),
+        SequenceMessage::ActionMoved(from, to) => from != to,
+        SequenceMessage::EnabledToggled(_)
+        | SequenceMessage::AddAction
+        | SequenceMessage::Paste
+        | SequenceMessage::Delete => true,
+        _ => false,
+    }
+}
+
+/// Whether a message changes what would be saved, rather than only what is selected
+pub fn is_edit(message: &SequenceMessage) -> bool {
+    match message {
+        SequenceMessage::SelectedToggled(_) => false,
+        SequenceMessage::ActionMoved(from, to) => from != to,
+        SequenceMessage::ActionMessage(_, ActionMessage::Copy) => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_step_through_recorded_states() {
+        let mut history = History::default();
+        history.record(&1);
+        history.record(&2);
+
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), None);
+        assert_eq!(history.redo(1), Some(2));
+        assert_eq!(history.redo(2), Some(3));
+        assert_eq!(history.redo(3), None);
+    }
+
+    #[test]
+    fn recording_forgets_what_could_be_redone() {
+        let mut history = History::default();
+        history.record(&1);
+        assert_eq!(history.undo(2), Some(1));
+
+        history.record(&1);
+
+        assert_eq!(history.redo(5), None);
+        assert_eq!(history.undo(5), Some(1));
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped() {
+        let mut history = History::default();
+        for state in 0..MAX_ENTRIES + 10 {
+            history.record(&state);
+        }
+
+        let mut current = MAX_ENTRIES + 10;
+        let mut undone = 0;
+        while let Some(previous) = history.undo(current) {
+            current = previous;
+            undone += 1;
+        }
+
+        assert_eq!(undone, MAX_ENTRIES);
+        assert_eq!(current, 10);
+    }
+}
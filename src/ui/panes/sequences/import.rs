@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use iced::{button, pick_list, Button, Column, Element, Length, PickList, Row, Text};
+
+use crate::sequencer::bundle::{Bundle, BundleDevice};
+use crate::sequencer::device::DevicesCollection;
+
+/// The mapping step of an import, choosing local devices for those the bundle expects
+#[derive(Debug, Clone)]
+pub struct Import {
+    bundle: Bundle,
+    mappings: Vec<DeviceMapping>,
+    confirm_button: button::State,
+    cancel_button: button::State,
+}
+
+#[derive(Debug, Clone)]
+struct DeviceMapping {
+    device: BundleDevice,
+    candidates: Vec<String>,
+    selected: Option<String>,
+    pick_list: pick_list::State<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ImportMessage {
+    DeviceSelected(usize, String),
+    Confirm,
+    Cancel,
+}
+
+impl Import {
+    pub fn new(bundle: Bundle, devices: &DevicesCollection) -> Self {
+        let defaults = bundle.default_mapping(devices);
+
+        Import {
+            mappings: bundle
+                .devices
+                .iter()
+                .map(|device| DeviceMapping {
+                    device: device.clone(),
+                    candidates: device.compatible_devices(devices),
+                    selected: defaults.get(&device.id).cloned(),
+                    pick_list: pick_list::State::new(),
+                })
+                .collect(),
+            bundle,
+            confirm_button: button::State::new(),
+            cancel_button: button::State::new(),
+        }
+    }
+
+    pub fn bundle(&self) -> &Bundle {
+        &self.bundle
+    }
+
+    /// Bundle device ids to local ones, None until every device has been mapped
+    pub fn mapping(&self) -> Option<HashMap<String, String>> {
+        self.mappings
+            .iter()
+            .map(|mapping| {
+                mapping
+                    .selected
+                    .clone()
+                    .map(|selected| (mapping.device.id.clone(), selected))
+            })
+            .collect()
+    }
+
+    pub fn update(&mut self, message: ImportMessage) {
+        if let ImportMessage::DeviceSelected(i, device_id) = message {
+            if let Some(mapping) = self.mappings.get_mut(i) {
+                mapping.selected = Some(device_id);
+            }
+        }
+    }
+
+    pub fn view(&mut self) -> Element<ImportMessage> {
+        let complete = self.mapping().is_some();
+
+        let mappings = self.mappings.iter_mut().enumerate().fold(
+            Column::new().spacing(10),
+            |column, (i, mapping)| {
+                let target: Element<_> = if mapping.candidates.is_empty() {
+                    Text::new("No compatible local device").into()
+                } else {
+                    PickList::new(
+                        &mut mapping.pick_list,
+                        mapping.candidates.clone(),
+                        mapping.selected.clone(),
+                        move |device_id| ImportMessage::DeviceSelected(i, device_id),
+                    )
+                    .into()
+                };
+
+                column.push(
+                    Row::new()
+                        .spacing(20)
+                        .push(
+                            Text::new(format!(
+                                "{} ({})",
+                                mapping.device.name,
+                                mapping.device.device_type.as_deref().unwrap_or("unknown")
+                            ))
+                            .width(Length::Units(250)),
+                        )
+                        .push(target),
+                )
+            },
+        );
+
+        let mut confirm_button = Button::new(&mut self.confirm_button, Text::new("Import"));
+        if complete {
+            confirm_button = confirm_button.on_press(ImportMessage::Confirm);
+        }
+
+        Column::new()
+            .spacing(20)
+            .padding(10)
+            .push(Text::new(format!(
+                "Importing {} sequences, choose the devices they should use",
+                self.bundle.sequences.len()
+            )))
+            .push(mappings)
+            .push(
+                Row::new().spacing(20).push(confirm_button).push(
+                    Button::new(&mut self.cancel_button, Text::new("Cancel"))
+                        .on_press(ImportMessage::Cancel),
+                ),
+            )
+            .into()
+    }
+}
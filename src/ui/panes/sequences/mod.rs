@@ -1,22 +1,45 @@
+pub mod dashboard;
+pub mod filter;
+pub mod history;
+pub mod import;
 pub mod sequence;
+pub mod templates;
+use crate::migrations::{self, MigrationError};
+use crate::sequencer::bundle::{self, Bundle, BundleError};
 use crate::sequencer::device::DevicesCollection;
-use crate::sequencer::reaction_sequence::ReactionSequence;
+use crate::sequencer::progress::Progress;
+use crate::sequencer::reaction_sequence::{
+    ReactionSequence, ReactionSequenceItemSequence, ReactionSequenceTrigger,
+};
+use crate::sequencer::sequence_files::{self, SEQUENCES_DIRECTORY};
+use crate::sequencer::templates::{self as sequence_templates, Template, TemplateError};
+use crate::sequencer::variable_store::VariableStore;
 
+use crate::control::{Control, ControlRequest};
 use crate::triggers::TriggerCollection;
 use crate::ui::fs_utils::LoadError;
 use crate::ui::fs_utils::SaveError;
-use crate::{sequencer, triggers, ThreadActions};
-use futures_util::select;
-use futures_util::{future, FutureExt};
+use crate::{sequencer, ThreadActions};
+use futures_util::future;
 
+use dashboard::Dashboard;
+use filter::{Filter, FilterMessage, FilterOptions};
+use history::History;
 use iced::{
-    self, button, keyboard, scrollable, Button, Column, Length, Row, Rule, Scrollable, Text,
+    self, button, keyboard, scrollable, text_input, Button, Checkbox, Column, Length, Row, Rule,
+    Scrollable, Text, TextInput,
 };
-use iced::{Command, Element};
-use iced_native::{window, Event};
+use iced::{Command, Element, Subscription};
+use iced_native::Event;
+use import::{Import, ImportMessage};
+use sequence::action::ActionMessage;
 use sequence::{Sequence, SequenceMessage};
-use tokio::sync::watch;
+use templates::{Gallery, GalleryMessage};
+use tokio::sync::broadcast;
+use tracing::{debug, error, info};
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use tokio::fs;
 
 #[derive(Debug, Clone)]
@@ -27,10 +50,30 @@ pub struct SequencesState {
     save_button: button::State,
     devices: DevicesCollection,
     triggers: TriggerCollection,
+    variable_store: VariableStore,
     tainted: bool,
+    record_events: bool,
     start_button: button::State,
     stop_button: button::State,
+    pause_button: button::State,
     listener_sender: Option<tokio::sync::mpsc::Sender<ThreadActions>>,
+    paused: bool,
+    control: Control,
+    dashboard: Option<Dashboard>,
+    runs: u64, // Identifies the progress subscription of each start
+    bundle_path: String,
+    bundle_path_input: text_input::State,
+    export_button: button::State,
+    import_button: button::State,
+    status: Option<String>,
+    import: Option<Import>,
+    templates_button: button::State,
+    gallery: Option<Gallery>,
+    filter: Filter,
+    history: History<Vec<Sequence>>,
+    focused_sequence: Option<usize>, // The sequence keyboard shortcuts act on
+    copied_action: Option<ReactionSequenceItemSequence>,
+    saved_files: HashMap<String, String>, // Where each sequence is on disk by id, undo leaves it be
 }
 
 #[derive(Debug, Clone)]
@@ -38,15 +81,34 @@ pub enum SequencesMessage {
     Loaded(Result<SequencesState, LoadError>),
     SequenceMessage(usize, SequenceMessage),
     AddSequence,
-    SequenceDeleted(Option<String>),
     SequenceCreated(Sequence),
     EventOccurred(iced_native::Event),
-    Saved(Option<SaveError>),
+    Saved(Result<HashMap<String, String>, SaveError>),
     Save,
+    Undo,
+    Redo,
     StartListeners,
+    RecordEventsToggled(bool),
     StopListeners,
     StoppedListeners(()),
+    Pause,
+    Resume,
+    PausedChanged(bool),
     TriggerComplete,
+    BundlePathChanged(String),
+    Export,
+    Exported(Result<usize, BundleError>),
+    Import,
+    BundleLoaded(Result<Bundle, BundleError>),
+    ImportMessage(ImportMessage),
+    Imported(Result<Vec<(ReactionSequence, PathBuf)>, BundleError>),
+    ShowTemplates,
+    TemplatesLoaded(Vec<Template>),
+    GalleryMessage(GalleryMessage),
+    TemplateSaved(Result<PathBuf, TemplateError>),
+    FilterMessage(FilterMessage),
+    Progress(Progress),
+    ProgressMissed(u64),
 }
 
 #[derive(Debug, Clone)]
@@ -54,8 +116,6 @@ pub enum Sequences {
     Loading,
     Error(String),
     Ready(SequencesState),
-    UnsavedCloseRequested(SequencesState),
-    ShouldExit,
     Running(SequencesState),
 }
 
@@ -63,40 +123,122 @@ pub async fn bury(v: tokio::sync::mpsc::Sender<ThreadActions>) {
     v.send(ThreadActions::Stop).await.unwrap();
 }
 
+/// Fails quietly when the listeners already finished by themselves
+pub async fn tell(v: tokio::sync::mpsc::Sender<ThreadActions>, action: ThreadActions) {
+    v.send(action).await.ok();
+}
+
 impl Sequences {
     pub fn new(
-        flags: (DevicesCollection, TriggerCollection),
+        flags: (DevicesCollection, TriggerCollection, VariableStore, Control),
     ) -> (Sequences, Command<SequencesMessage>) {
         (
             Sequences::Loading,
-            Command::perform(load_sequences(flags.0, flags.1), SequencesMessage::Loaded),
+            Command::perform(
+                load_sequences(flags.0, flags.1, flags.2, flags.3),
+                SequencesMessage::Loaded,
+            ),
         )
     }
 
-    pub fn should_exit(&self) -> bool {
+    /// Whether there are edits which have not been saved
+    pub fn is_tainted(&self) -> bool {
         match self {
-            Sequences::ShouldExit => true,
+            Sequences::Ready(state) | Sequences::Running(state) => state.tainted,
             _ => false,
         }
     }
 
-    pub fn title(&self) -> String {
-        let tainted = match self {
-            Sequences::Loading => false,
-            Sequences::Ready(state) | Sequences::UnsavedCloseRequested(state) => state.tainted,
-            _ => false,
+    /// Saves any unsaved edits, reported by a `Saved` message
+    pub fn save(&mut self) -> Command<SequencesMessage> {
+        match self {
+            Sequences::Ready(state) | Sequences::Running(state) => try_save(state),
+            _ => Command::none(),
+        }
+    }
+
+    /// Progress from the sequencer while listeners run, for the dashboard, and requests from the control API
+    pub fn subscription(&self) -> Subscription<SequencesMessage> {
+        let requests = match self {
+            Sequences::Ready(state) | Sequences::Running(state) => {
+                iced_native::subscription::unfold(
+                    "control_requests",
+                    state.control.subscribe(),
+                    |mut receiver| async move {
+                        loop {
+                            match receiver.recv().await {
+                                Ok(request) => return (Some(request_message(request)), receiver),
+                                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                                Err(broadcast::error::RecvError::Closed) => future::pending().await,
+                            }
+                        }
+                    },
+                )
+            }
+            _ => Subscription::none(),
+        };
+
+        let progress = match self {
+            Sequences::Running(state) => iced_native::subscription::unfold(
+                state.runs,
+                state.control.progress().subscribe(),
+                |mut receiver| async move {
+                    let message = match receiver.recv().await {
+                        Ok(progress) => SequencesMessage::Progress(progress),
+                        Err(broadcast::error::RecvError::Lagged(count)) => {
+                            SequencesMessage::ProgressMissed(count)
+                        }
+                        // Only when the app is exiting
+                        Err(broadcast::error::RecvError::Closed) => future::pending().await,
+                    };
+
+                    (Some(message), receiver)
+                },
+            ),
+            _ => Subscription::none(),
         };
 
+        Subscription::batch(vec![requests, progress])
+    }
+
+    pub fn title(&self) -> String {
         format!(
             "Twitch Reaction Sequencer{}",
-            if tainted { "*" } else { "" }
+            if self.is_tainted() { "*" } else { "" }
         )
     }
 
+    pub fn configured_triggers(&self) -> Vec<ReactionSequenceTrigger> {
+        match self {
+            Sequences::Ready(state) | Sequences::Running(state) => state
+                .sequences
+                .iter()
+                .map(|sequence| sequence.to_reaction_seqeunce().trigger)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Sequence names by id
+    pub fn sequence_names(&self) -> HashMap<String, String> {
+        match self {
+            Sequences::Ready(state) | Sequences::Running(state) => state
+                .sequences
+                .iter()
+                .map(|sequence| {
+                    let sequence = sequence.to_reaction_seqeunce();
+                    (sequence.id, sequence.name)
+                })
+                .collect(),
+            _ => HashMap::new(),
+        }
+    }
+
     pub fn update(&mut self, message: SequencesMessage) -> Command<SequencesMessage> {
         match self {
             Sequences::Loading => match message {
                 SequencesMessage::Loaded(Ok(state)) => {
+                    publish_sequences(&state);
                     *self = Sequences::Ready(state);
                 }
 
@@ -119,28 +261,122 @@ impl Sequences {
                 }
 
                 SequencesMessage::StoppedListeners(()) => {
-                    *self = Sequences::Ready(SequencesState { ..state.clone() });
+                    state.control.set_running(false);
+                    *self = Sequences::Ready(SequencesState {
+                        paused: false,
+                        dashboard: None,
+                        ..state.clone()
+                    });
 
                     return Command::none();
                 }
+
+                SequencesMessage::Pause | SequencesMessage::Resume => {
+                    let pause = matches!(message, SequencesMessage::Pause);
+                    if let Some(sender) = state.listener_sender.clone() {
+                        let action = if pause {
+                            ThreadActions::Pause
+                        } else {
+                            ThreadActions::Resume
+                        };
+                        return Command::perform(tell(sender, action), move |()| {
+                            SequencesMessage::PausedChanged(pause)
+                        });
+                    }
+                }
+
+                SequencesMessage::PausedChanged(paused) => {
+                    state.paused = paused;
+                    state.control.set_paused(paused);
+                }
+
+                SequencesMessage::Progress(progress) => {
+                    if let Some(dashboard) = state.dashboard.as_mut() {
+                        dashboard.update(progress);
+                    }
+                }
+
+                SequencesMessage::ProgressMissed(count) => {
+                    if let Some(dashboard) = state.dashboard.as_mut() {
+                        dashboard.missed(count);
+                    }
+                }
+
+                // Edits made before starting can still be saved when closing
+                SequencesMessage::Saved(result) => saved(state, result),
                 _ => {}
             },
 
-            Sequences::UnsavedCloseRequested(state) | Sequences::Ready(state) => match message {
-                SequencesMessage::SequenceMessage(i, sequence_message) => match sequence_message {
-                    SequenceMessage::Delete => {
-                        let removed_item = state.sequences.remove(i);
-                        return Command::perform(
-                            delete_file(removed_item.get_filename()),
-                            SequencesMessage::SequenceDeleted,
-                        );
+            Sequences::Ready(state) => match message {
+                SequencesMessage::SequenceMessage(i, sequence_message) => {
+                    if i >= state.sequences.len() {
+                        return Command::none();
                     }
-                    _ => {
-                        if let Some(sequence) = state.sequences.get_mut(i) {
-                            sequence.update(sequence_message);
+
+                    match sequence_message {
+                        SequenceMessage::ActionMessage(action, ActionMessage::Copy) => {
+                            state.copied_action = state.sequences[i].action_item(action);
+                            state.focused_sequence = Some(i);
+                            return Command::none();
+                        }
+                        SequenceMessage::Paste => {
+                            if let Some(item) = state.copied_action.clone() {
+                                state.history.record(&state.sequences);
+                                state.tainted = true;
+                                state.sequences[i].paste_action(item);
+                                state.focused_sequence = Some(i);
+                            }
+                            return Command::none();
                         }
+                        SequenceMessage::Duplicate => {
+                            state.history.record(&state.sequences);
+                            state.tainted = true;
+                            let copy = state.sequences[i].duplicate();
+                            state.sequences.insert(i + 1, copy);
+                            state.focused_sequence = None;
+                            return Command::none();
+                        }
+                        SequenceMessage::SaveAsTemplate => {
+                            let template = Template::from_sequence(
+                                &state.sequences[i].to_reaction_seqeunce(),
+                                &state.devices,
+                            );
+                            return Command::perform(
+                                sequence_templates::save_template(template),
+                                SequencesMessage::TemplateSaved,
+                            );
+                        }
+                        // Dragging the trigger or the add button moves nothing
+                        SequenceMessage::ActionMoved(from, _)
+                            if from >= state.sequences[i].action_count() =>
+                        {
+                            return Command::none();
+                        }
+                        _ => {}
+                    }
+
+                    if history::is_recorded(&sequence_message) {
+                        state.history.record(&state.sequences);
+                    }
+
+                    if history::is_edit(&sequence_message) {
+                        state.tainted = true;
                     }
-                },
+
+                    match sequence_message {
+                        SequenceMessage::Delete => {
+                            // The file goes on save, so an undone delete loses nothing
+                            state.sequences.remove(i);
+                            state.focused_sequence = None;
+                        }
+                        _ => {
+                            state.sequences[i].update(sequence_message);
+                            if state.sequences[i].selected_action().is_some() {
+                                state.focused_sequence = Some(i);
+                            }
+                        }
+                    }
+                }
 
                 SequencesMessage::AddSequence => {
                     return Command::perform(
@@ -150,6 +386,7 @@ impl Sequences {
                 }
 
                 SequencesMessage::SequenceCreated(sequence) => {
+                    state.history.record(&state.sequences);
                     state.sequences.push(sequence);
                     *self = Sequences::Ready(SequencesState {
                         tainted: true,
@@ -157,57 +394,234 @@ impl Sequences {
                     })
                 }
 
-                SequencesMessage::SequenceDeleted(msg) => {
-                    if msg.is_some() {
-                        *self = Sequences::Error(msg.unwrap())
-                    } else {
-                        *self = Sequences::Ready(SequencesState {
-                            tainted: true,
-                            ..state.clone()
-                        });
+                SequencesMessage::Undo => {
+                    if let Some(previous) = state.history.undo(state.sequences.clone()) {
+                        state.sequences = previous;
+                        state.tainted = true;
+                    }
+                }
+
+                SequencesMessage::Redo => {
+                    if let Some(next) = state.history.redo(state.sequences.clone()) {
+                        state.sequences = next;
+                        state.tainted = true;
                     }
                 }
 
                 SequencesMessage::EventOccurred(event) => {
-                    if let Event::Window(window::Event::CloseRequested) = event {
-                        if state.tainted {
-                            *self = Sequences::UnsavedCloseRequested(state.clone());
-                        } else {
-                            *self = Sequences::ShouldExit
-                        }
-                    } else if let Event::Keyboard(keyboard::Event::KeyPressed {
+                    if let Event::Keyboard(keyboard::Event::KeyPressed {
                         key_code: keyboard::KeyCode::S,
                         modifiers: keyboard::Modifiers::CTRL,
                     }) = event
                     {
                         return try_save(state);
+                    } else if let Event::Keyboard(keyboard::Event::KeyPressed {
+                        key_code: keyboard::KeyCode::Z,
+                        modifiers,
+                    }) = event
+                    {
+                        if modifiers == keyboard::Modifiers::CTRL {
+                            return self.update(SequencesMessage::Undo);
+                        } else if modifiers
+                            == keyboard::Modifiers::CTRL | keyboard::Modifiers::SHIFT
+                        {
+                            return self.update(SequencesMessage::Redo);
+                        }
+                    } else if let Event::Keyboard(keyboard::Event::KeyPressed {
+                        key_code,
+                        modifiers,
+                    }) = event
+                    {
+                        if let Some(message) = action_shortcut(state, key_code, modifiers) {
+                            return self.update(message);
+                        }
                     }
                 }
                 SequencesMessage::Save => return try_save(state),
 
-                SequencesMessage::Saved(_) => {
-                    *self = Sequences::Ready(SequencesState {
-                        tainted: false,
-                        ..state.clone()
-                    })
+                SequencesMessage::Saved(result) => saved(state, result),
+
+                SequencesMessage::RecordEventsToggled(record_events) => {
+                    state.record_events = record_events;
+                }
+
+                SequencesMessage::BundlePathChanged(bundle_path) => state.bundle_path = bundle_path,
+
+                SequencesMessage::Export => {
+                    let selected: Vec<ReactionSequence> = state
+                        .sequences
+                        .iter()
+                        .filter(|sequence| sequence.is_selected())
+                        .map(|sequence| sequence.to_reaction_seqeunce())
+                        .collect();
+
+                    // Nothing ticked exports everything
+                    let sequences = if selected.is_empty() {
+                        state
+                            .sequences
+                            .iter()
+                            .map(|sequence| sequence.to_reaction_seqeunce())
+                            .collect()
+                    } else {
+                        selected
+                    };
+
+                    return Command::perform(
+                        export_bundle(
+                            Bundle::export(sequences, &state.devices),
+                            PathBuf::from(&state.bundle_path),
+                        ),
+                        SequencesMessage::Exported,
+                    );
+                }
+
+                SequencesMessage::Exported(result) => {
+                    state.status = Some(match result {
+                        Ok(count) => {
+                            format!("Exported {} sequences to {}", count, state.bundle_path)
+                        }
+                        Err(e) => e.to_string(),
+                    });
+                }
+
+                SequencesMessage::Import => {
+                    return Command::perform(
+                        read_bundle(PathBuf::from(&state.bundle_path)),
+                        SequencesMessage::BundleLoaded,
+                    );
+                }
+
+                SequencesMessage::BundleLoaded(result) => match result {
+                    Ok(bundle) => {
+                        state.status = None;
+                        state.import = Some(Import::new(bundle, &state.devices));
+                    }
+                    Err(e) => state.status = Some(e.to_string()),
+                },
+
+                SequencesMessage::ImportMessage(import_message) => match import_message {
+                    ImportMessage::Cancel => state.import = None,
+                    ImportMessage::Confirm => {
+                        // Confirm is only enabled once every device has been mapped
+                        if let Some(import) = state.import.take() {
+                            if let Some(mapping) = import.mapping() {
+                                return Command::perform(
+                                    import_sequences(import.bundle().import(&mapping)),
+                                    SequencesMessage::Imported,
+                                );
+                            }
+                        }
+                    }
+                    _ => {
+                        if let Some(import) = state.import.as_mut() {
+                            import.update(import_message);
+                        }
+                    }
+                },
+
+                SequencesMessage::Imported(result) => match result {
+                    Ok(written) => {
+                        state.status = Some(format!("Imported {} sequences", written.len()));
+                        for (sequence, path) in written {
+                            state
+                                .saved_files
+                                .insert(sequence.id.clone(), path.to_string_lossy().into_owned());
+                            state.sequences.push(Sequence::from_existing(
+                                sequence,
+                                state.devices.clone(),
+                                state.triggers.clone(),
+                            ));
+                        }
+                    }
+                    Err(e) => state.status = Some(e.to_string()),
+                },
+
+                SequencesMessage::ShowTemplates => {
+                    return Command::perform(
+                        sequence_templates::load_templates(),
+                        SequencesMessage::TemplatesLoaded,
+                    );
+                }
+
+                SequencesMessage::TemplatesLoaded(templates) => {
+                    state.gallery = Some(Gallery::new(templates, &state.devices));
+                }
+
+                SequencesMessage::GalleryMessage(gallery_message) => match gallery_message {
+                    GalleryMessage::Close => state.gallery = None,
+                    GalleryMessage::Create => {
+                        let created = state
+                            .gallery
+                            .as_ref()
+                            .and_then(|gallery| gallery.filled_template())
+                            .map(|(template, values)| template.instantiate(&values));
+
+                        match created {
+                            Some(Ok(sequence)) => {
+                                state.history.record(&state.sequences);
+                                state.tainted = true;
+                                state.gallery = None;
+                                state.sequences.push(Sequence::from_existing(
+                                    sequence,
+                                    state.devices.clone(),
+                                    state.triggers.clone(),
+                                ));
+                            }
+                            Some(Err(e)) => {
+                                if let Some(gallery) = state.gallery.as_mut() {
+                                    gallery.set_error(e.to_string());
+                                }
+                            }
+                            None => {}
+                        }
+                    }
+                    _ => {
+                        if let Some(gallery) = state.gallery.as_mut() {
+                            gallery.update(gallery_message);
+                        }
+                    }
+                },
+
+                SequencesMessage::TemplateSaved(result) => {
+                    state.status = Some(match result {
+                        Ok(path) => format!("Saved template to {}", path.display()),
+                        Err(e) => e.to_string(),
+                    });
+                }
+
+                SequencesMessage::FilterMessage(filter_message) => {
+                    state.filter.update(filter_message)
                 }
 
                 SequencesMessage::StartListeners => {
                     let (sender, reciever) = tokio::sync::mpsc::channel(1);
-                    tokio::spawn(start_listener(
+                    let sequences: Vec<ReactionSequence> = state
+                        .sequences
+                        .iter()
+                        .map(|sequence| sequence.to_reaction_seqeunce())
+                        .collect();
+
+                    let mut trigger_ids: Vec<String> = state.triggers.keys().cloned().collect();
+                    trigger_ids.sort();
+                    let device_ids: Vec<String> = state.devices.keys().cloned().collect();
+
+                    tokio::spawn(sequencer::run_listeners(
                         state.devices.clone(),
                         state.triggers.clone(),
-                        state
-                            .sequences
-                            .clone()
-                            .into_iter()
-                            .map(|sequence| sequence.to_reaction_seqeunce())
-                            .collect(),
+                        sequences.clone(),
+                        state.variable_store.clone(),
+                        state.record_events,
                         reciever,
+                        state.control.progress().clone(),
+                        state.control.play_lock(),
                     ));
+                    state.control.set_sequences(sequences.clone());
+                    state.control.set_running(true);
 
                     *self = Sequences::Running(SequencesState {
                         listener_sender: Option::Some(sender),
+                        dashboard: Some(Dashboard::new(trigger_ids, device_ids, sequences)),
+                        runs: state.runs + 1,
                         ..state.clone()
                     });
 
@@ -227,71 +641,45 @@ impl Sequences {
             Sequences::Loading => Text::new("Loading").into(),
             Sequences::Error(msg) => Text::new(msg.clone()).into(),
             Sequences::Ready(state) => render_when_ready(state).into(),
-            Sequences::ShouldExit => Text::new("exiting").into(),
             Sequences::Running(state) => running(state),
-            Sequences::UnsavedCloseRequested(state) => {
-                let mut c = Column::new().width(Length::Fill).spacing(1);
-
-                let seqs: Element<_> = state
-                    .sequences
-                    .iter_mut()
-                    .enumerate()
-                    .fold(
-                        Column::new().spacing(20).padding(10),
-                        |column: Column<_>, (i, sequence)| {
-                            column
-                                .push(sequence.view().map(move |message| {
-                                    SequencesMessage::SequenceMessage(i, message)
-                                }))
-                                .push(Rule::horizontal(5))
-                        },
-                    )
-                    .into();
-
-                c = c.push(seqs);
-
-                c = c.push(
-                    Button::new(
-                        &mut state.add_sequence_button,
-                        Text::new("Add Sequence +").size(20),
-                    )
-                    .on_press(SequencesMessage::AddSequence),
-                ); // Add Sequence Button
-
-                let contents = Scrollable::new(&mut state.scroll).push(c);
-                return Column::new()
-                    .push(
-                        Button::new(
-                            &mut state.save_button,
-                            Row::new()
-                                .push(Text::new("You just tried to exit when unsaved"))
-                                .push(Text::new("+")),
-                        )
-                        .on_press(SequencesMessage::Save),
-                    )
-                    .push(contents)
-                    .into(); //TODO change this to a modal
-            }
         }
     }
 }
 
 fn running(state: &mut SequencesState) -> Element<SequencesMessage> {
-    return Column::new()
-        .push(Text::new("Running"))
-        .push(
-            Button::new(&mut state.stop_button, Text::new("Stop"))
-                .on_press(SequencesMessage::StopListeners),
-        )
-        .into();
+    let pause = if state.paused {
+        Button::new(&mut state.pause_button, Text::new("Resume")).on_press(SequencesMessage::Resume)
+    } else {
+        Button::new(&mut state.pause_button, Text::new("Pause")).on_press(SequencesMessage::Pause)
+    };
+
+    let mut column = Column::new().spacing(20).padding(10).push(
+        Row::new()
+            .spacing(20)
+            .push(Text::new(if state.paused { "Paused" } else { "Running" }))
+            .push(
+                Button::new(&mut state.stop_button, Text::new("Stop"))
+                    .on_press(SequencesMessage::StopListeners),
+            )
+            .push(pause),
+    );
+
+    if let Some(dashboard) = state.dashboard.as_mut() {
+        column = column.push(dashboard.view());
+    }
+
+    column.into()
 }
 
 async fn load_sequences(
     devices: DevicesCollection,
     triggers: TriggerCollection,
+    variable_store: VariableStore,
+    control: Control,
 ) -> Result<SequencesState, LoadError> {
-    let paths = fs::read_dir("./TRS/sequences").await; // TODO: this path should be relative to a userdata folder
+    let paths = fs::read_dir(SEQUENCES_DIRECTORY).await;
     let mut sequences = Vec::<Sequence>::new();
+    let mut saved_files = HashMap::new();
     if paths.is_ok() {
         let mut paths = paths.unwrap();
 
@@ -301,23 +689,17 @@ async fn load_sequences(
             }
 
             let path = entry.path();
-            if let Ok(file_content) = fs::read(&path).await {
-                let get_sequencer = serde_json::from_slice(&file_content);
-                if get_sequencer.is_ok() {
-                    let sequencer = get_sequencer.unwrap();
+            match migrations::sequence::load(&path).await {
+                Ok(sequencer) => {
+                    saved_files.insert(sequencer.id.clone(), path.to_string_lossy().into_owned());
                     sequences.push(Sequence::from_existing(
                         sequencer,
-                        path.clone(),
                         devices.clone(),
                         triggers.clone(),
-                    ));
-                } else {
-                    return Err(LoadError::FormatError(
-                        get_sequencer.err().unwrap().to_string(),
-                    ));
+                    ))
                 }
-            } else {
-                return Err(LoadError::FileError);
+                Err(MigrationError::FileError(_)) => return Err(LoadError::FileError),
+                Err(e) => return Err(LoadError::FormatError(e.to_string())),
             }
         }
     } else {
@@ -331,28 +713,126 @@ async fn load_sequences(
         save_button: button::State::new(),
         start_button: button::State::new(),
         stop_button: button::State::new(),
+        pause_button: button::State::new(),
         devices: devices.clone(),
         triggers: triggers.clone(),
+        variable_store,
         tainted: false,
+        record_events: false,
         listener_sender: Option::None,
+        paused: false,
+        control,
+        dashboard: None,
+        runs: 0,
+        bundle_path: String::from("./TRS/bundle.json"), // TODO: this path should be relative to a userdata folder
+        bundle_path_input: text_input::State::new(),
+        export_button: button::State::new(),
+        import_button: button::State::new(),
+        status: None,
+        import: None,
+        templates_button: button::State::new(),
+        gallery: None,
+        filter: Filter::default(),
+        history: History::default(),
+        focused_sequence: None,
+        copied_action: None,
+        saved_files,
     })
 }
 
-async fn save_sequences(sequences: Vec<Sequence>) -> Option<SaveError> {
-    for sequence in sequences {
-        let json = serde_json::to_string_pretty(&sequence.to_reaction_seqeunce());
+/// Writes every sequence to a file named after it, returning the filename of each sequence by id
+async fn save_sequences(
+    sequences: Vec<Sequence>,
+    saved_files: HashMap<String, String>,
+) -> Result<HashMap<String, String>, SaveError> {
+    let mut files = vec![];
+    for sequence in sequences.iter() {
+        files.push(SequenceFile {
+            id: String::from(sequence.id()),
+            name: String::from(sequence.name()),
+            json: serde_json::to_string_pretty(&sequence.to_reaction_seqeunce())
+                .map_err(|e| SaveError::FormatError(e.to_string()))?,
+        });
+    }
+
+    write_sequence_files(SEQUENCES_DIRECTORY, files, saved_files).await
+}
 
-        if json.is_err() {
-            return Some(SaveError::FormatError(json.unwrap_err().to_string()));
+/// One sequence as it is written to disk
+#[derive(Debug, Clone)]
+struct SequenceFile {
+    id: String,
+    name: String,
+    json: String,
+}
+
+/// `saved_files` is where each sequence is on disk now by id. Undo brings back older copies of
+/// sequences which may name files renamed since, so those are never trusted
+async fn write_sequence_files(
+    directory: &str,
+    files: Vec<SequenceFile>,
+    saved_files: HashMap<String, String>,
+) -> Result<HashMap<String, String>, SaveError> {
+    // Sequences which were deleted, or added and then undone, since the last save
+    for (id, filename) in saved_files.iter() {
+        if !files.iter().any(|file| &file.id == id) {
+            if let Some(e) = delete_file(filename.clone()).await {
+                return Err(SaveError::FileError(e));
+            }
         }
+    }
+
+    // Files not yet renamed stay taken until their sequence has been moved
+    let mut taken: Vec<Option<String>> = files
+        .iter()
+        .map(|file| saved_files.get(&file.id).cloned())
+        .collect();
+    let mut filenames = HashMap::new();
 
-        println!("{:?}", sequence.clone().get_filename());
-        fs::write(&sequence.get_filename(), &json.unwrap())
+    for (i, file) in files.iter().enumerate() {
+        let current = taken[i].clone();
+        let others: Vec<String> = taken
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .filter_map(|(_, filename)| filename.clone())
+            .collect();
+        let filename =
+            sequence_files::filename_in(directory, &file.name, current.as_deref(), &others);
+
+        // Write the new file before removing the old one so a failed rename loses nothing
+        debug!("Saving {}", filename);
+        fs::write(&filename, &file.json)
             .await
-            .unwrap();
+            .map_err(|e| SaveError::FileError(e.to_string()))?;
+
+        if let Some(current) = current.filter(|current| *current != filename) {
+            if let Some(e) = delete_file(current).await {
+                return Err(SaveError::FileError(e));
+            }
+        }
+
+        taken[i] = Some(filename.clone());
+        filenames.insert(file.id.clone(), filename);
     }
 
-    None
+    Ok(filenames)
+}
+
+async fn export_bundle(bundle: Bundle, path: PathBuf) -> Result<usize, BundleError> {
+    bundle.write(&path).await?;
+
+    Ok(bundle.sequences.len())
+}
+
+async fn read_bundle(path: PathBuf) -> Result<Bundle, BundleError> {
+    Bundle::read(&path).await
+}
+
+async fn import_sequences(
+    sequences: Result<Vec<ReactionSequence>, BundleError>,
+) -> Result<Vec<(ReactionSequence, PathBuf)>, BundleError> {
+    bundle::write_sequences(&sequences?).await
 }
 
 async fn delete_file(filename: String) -> Option<String> {
@@ -367,15 +847,103 @@ async fn delete_file(filename: String) -> Option<String> {
 }
 
 fn render_when_ready(state: &mut SequencesState) -> Scrollable<SequencesMessage> {
+    // Worked out before the view borrows parts of the state
+    let total = state.sequences.len();
+    let shown: Vec<bool> = state
+        .sequences
+        .iter()
+        .map(|sequence| state.filter.matches(sequence))
+        .collect();
+    let shown_count = shown.iter().filter(|shown| **shown).count();
+    let filtering = state.filter.is_active();
+
+    let options = filter_options(state);
+
     let mut c = Column::new().width(Length::Fill).spacing(1).push(
-        Button::new(&mut state.start_button, Text::new("Start"))
-            .on_press(SequencesMessage::StartListeners),
+        Row::new()
+            .spacing(20)
+            .push(
+                Button::new(&mut state.start_button, Text::new("Start"))
+                    .on_press(SequencesMessage::StartListeners),
+            )
+            .push(
+                Button::new(&mut state.save_button, Text::new("Save"))
+                    .on_press(SequencesMessage::Save),
+            )
+            .push(Checkbox::new(
+                state.record_events,
+                "Record events",
+                SequencesMessage::RecordEventsToggled,
+            )),
+    );
+
+    c = c.push(
+        Row::new()
+            .spacing(20)
+            .push(
+                TextInput::new(
+                    &mut state.bundle_path_input,
+                    "Bundle file",
+                    &state.bundle_path,
+                    SequencesMessage::BundlePathChanged,
+                )
+                .padding(5)
+                .width(Length::Units(300)),
+            )
+            .push(
+                Button::new(&mut state.export_button, Text::new("Export"))
+                    .on_press(SequencesMessage::Export),
+            )
+            .push(
+                Button::new(&mut state.import_button, Text::new("Import"))
+                    .on_press(SequencesMessage::Import),
+            )
+            .push(
+                Button::new(&mut state.templates_button, Text::new("Templates"))
+                    .on_press(SequencesMessage::ShowTemplates),
+            ),
+    );
+
+    if let Some(status) = &state.status {
+        c = c.push(Text::new(status.clone()));
+    }
+
+    if let Some(import) = state.import.as_mut() {
+        c = c
+            .push(import.view().map(SequencesMessage::ImportMessage))
+            .push(Rule::horizontal(5));
+    }
+
+    if let Some(gallery) = state.gallery.as_mut() {
+        c = c
+            .push(gallery.view().map(SequencesMessage::GalleryMessage))
+            .push(Rule::horizontal(5));
+    }
+
+    c = c.push(
+        state
+            .filter
+            .view(options)
+            .map(SequencesMessage::FilterMessage),
     );
 
-    let seqs: Element<_> = state
+    if filtering {
+        c = c.push(Text::new(format!(
+            "Showing {} of {} sequences",
+            shown_count, total
+        )));
+    }
+
+    let mut visible: Vec<(usize, &mut Sequence)> = state
         .sequences
         .iter_mut()
         .enumerate()
+        .filter(|(i, _)| shown[*i])
+        .collect();
+    visible.sort_by_key(|(_, sequence)| sequence.name().to_lowercase());
+
+    let seqs: Element<_> = visible
+        .into_iter()
         .fold(
             Column::new().spacing(20).padding(10),
             |column: Column<_>, (i, sequence)| {
@@ -403,10 +971,102 @@ fn render_when_ready(state: &mut SequencesState) -> Scrollable<SequencesMessage>
     return Scrollable::new(&mut state.scroll).push(c);
 }
 
+/// Trigger sources and devices which exist, and the tags used by any sequence, sorted
+fn filter_options(state: &SequencesState) -> FilterOptions {
+    let mut triggers: Vec<String> = state.triggers.keys().cloned().collect();
+    triggers.sort();
+
+    let mut devices: Vec<String> = state.devices.keys().cloned().collect();
+    devices.sort();
+
+    let mut tags: Vec<String> = state
+        .sequences
+        .iter()
+        .flat_map(|sequence| sequence.tags())
+        .collect();
+    tags.sort();
+    tags.dedup();
+
+    FilterOptions {
+        triggers,
+        devices,
+        tags,
+    }
+}
+
+/// Alt+Left and Alt+Right move the selected action, Ctrl+D duplicates it, Ctrl+C and Ctrl+V copy and paste it
+fn action_shortcut(
+    state: &SequencesState,
+    key_code: keyboard::KeyCode,
+    modifiers: keyboard::Modifiers,
+) -> Option<SequencesMessage> {
+    let i = state.focused_sequence?;
+    let sequence = state.sequences.get(i)?;
+    let selected = sequence.selected_action();
+
+    let message = if modifiers == keyboard::Modifiers::ALT {
+        let selected = selected?;
+        match key_code {
+            keyboard::KeyCode::Left if selected > 0 => {
+                SequenceMessage::ActionMoved(selected, selected - 1)
+            }
+            keyboard::KeyCode::Right if selected + 1 < sequence.action_count() => {
+                SequenceMessage::ActionMoved(selected, selected + 1)
+            }
+            _ => return None,
+        }
+    } else if modifiers == keyboard::Modifiers::CTRL {
+        match key_code {
+            keyboard::KeyCode::D => {
+                SequenceMessage::ActionMessage(selected?, ActionMessage::Duplicate)
+            }
+            keyboard::KeyCode::C => SequenceMessage::ActionMessage(selected?, ActionMessage::Copy),
+            keyboard::KeyCode::V => SequenceMessage::Paste,
+            _ => return None,
+        }
+    } else {
+        return None;
+    };
+
+    Some(SequencesMessage::SequenceMessage(i, message))
+}
+
+/// Takes the saved filenames, keeping the edits tainted when saving failed
+fn saved(state: &mut SequencesState, result: Result<HashMap<String, String>, SaveError>) {
+    match result {
+        Ok(filenames) => {
+            state.tainted = false;
+            state.saved_files = filenames;
+            publish_sequences(state);
+        }
+        Err(error) => error!("Failed to save sequences: {:?}", error),
+    }
+}
+
+/// Lets the control API list and fire the sequences as they are on disk
+fn publish_sequences(state: &SequencesState) {
+    state.control.set_sequences(
+        state
+            .sequences
+            .iter()
+            .map(|sequence| sequence.to_reaction_seqeunce())
+            .collect(),
+    );
+}
+
+fn request_message(request: ControlRequest) -> SequencesMessage {
+    match request {
+        ControlRequest::StartListeners => SequencesMessage::StartListeners,
+        ControlRequest::StopListeners => SequencesMessage::StopListeners,
+        ControlRequest::Pause => SequencesMessage::Pause,
+        ControlRequest::Resume => SequencesMessage::Resume,
+    }
+}
+
 fn try_save(state: &mut SequencesState) -> Command<SequencesMessage> {
     if state.tainted {
         return Command::perform(
-            save_sequences(state.sequences.clone()),
+            save_sequences(state.sequences.clone(), state.saved_files.clone()),
             SequencesMessage::Saved,
         );
     }
@@ -414,40 +1074,92 @@ fn try_save(state: &mut SequencesState) -> Command<SequencesMessage> {
     Command::none()
 }
 
-async fn start_listener(
-    device_set: DevicesCollection,
-    triggers: TriggerCollection,
-    sequences: Vec<ReactionSequence>,
-    mut listener: tokio::sync::mpsc::Receiver<ThreadActions>,
-) {
-    let (trigger_sequence, trigger_sequence_reciever) = watch::channel(sequencer::QueueEvent {
-        trigger_source: triggers::TriggerSource::TwitchChat,
-        trigger_event_id: String::from(""),
-    });
-
-    let (task_handler_sender, task_handler_reciever) = watch::channel(());
-
-    let sequencer_queue = sequencer::watch_queue(
-        device_set,
-        sequences,
-        trigger_sequence_reciever,
-        task_handler_reciever.clone(),
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let trigger_manager =
-        triggers::watch_trigger_sources(triggers, trigger_sequence, task_handler_reciever.clone());
-    let mut listeners = Box::pin(future::try_join(trigger_manager, sequencer_queue).fuse());
+    fn scratch_directory(name: &str) -> String {
+        let directory =
+            std::env::temp_dir().join(format!("trs-save-{}-{}", name, std::process::id()));
+        std::fs::remove_dir_all(&directory).ok();
+        std::fs::create_dir_all(&directory).unwrap();
 
-    let mut l = Box::pin(listener.recv().fuse());
+        directory.to_string_lossy().into_owned()
+    }
 
-    select! {
-        _x = listeners => println!("Listeners finished"),
-        _v = l => {
-            println!("Told to stop");
-            task_handler_sender.send(()).unwrap();
+    fn file(id: &str, name: &str) -> SequenceFile {
+        SequenceFile {
+            id: String::from(id),
+            name: String::from(name),
+            json: format!("{{\"id\": \"{}\"}}", id),
         }
     }
 
-    listeners.await.unwrap();
-    println!("Finished listeners")
+    fn files_in(directory: &str) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+
+        names
+    }
+
+    #[tokio::test]
+    async fn undoing_an_added_sequence_removes_its_file() {
+        let directory = scratch_directory("added");
+        let saved = write_sequence_files(&directory, vec![file("1", "Raid")], HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(files_in(&directory), vec!["raid.json"]);
+
+        let saved = write_sequence_files(&directory, vec![], saved)
+            .await
+            .unwrap();
+
+        assert!(saved.is_empty());
+        assert!(files_in(&directory).is_empty());
+    }
+
+    #[tokio::test]
+    async fn undoing_a_rename_leaves_one_file() {
+        let directory = scratch_directory("renamed");
+        let saved = write_sequence_files(&directory, vec![file("1", "Raid")], HashMap::new())
+            .await
+            .unwrap();
+        let saved = write_sequence_files(&directory, vec![file("1", "Big Raid")], saved)
+            .await
+            .unwrap();
+        assert_eq!(files_in(&directory), vec!["big-raid.json"]);
+
+        let saved = write_sequence_files(&directory, vec![file("1", "Raid")], saved)
+            .await
+            .unwrap();
+
+        assert_eq!(files_in(&directory), vec!["raid.json"]);
+        assert_eq!(saved["1"], format!("{}/raid.json", directory));
+    }
+
+    #[tokio::test]
+    async fn deleted_sequence_frees_its_name() {
+        let directory = scratch_directory("deleted");
+        let saved = write_sequence_files(
+            &directory,
+            vec![file("1", "Raid"), file("2", "Raid")],
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(files_in(&directory), vec!["raid-2.json", "raid.json"]);
+
+        let saved = write_sequence_files(&directory, vec![file("2", "Raid")], saved)
+            .await
+            .unwrap();
+
+        assert_eq!(files_in(&directory), vec!["raid.json"]);
+        assert_eq!(
+            std::fs::read_to_string(&saved["2"]).unwrap(),
+            file("2", "Raid").json
+        );
+    }
 }
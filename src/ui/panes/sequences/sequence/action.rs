@@ -1,9 +1,11 @@
-use iced::{self, button, Button, Text};
-use iced::{pick_list, Column, Element, PickList};
+use iced::{self, button, text_input, Button, Text, TextInput};
+use iced::{pick_list, Column, Element, PickList, Row};
 
 use crate::sequencer::device::DevicesCollection;
 use crate::sequencer::reaction_sequence::{self, ReactionSequenceItemSequence};
 
+use super::trigger::condition_from_text;
+
 // Drop down list of trigger sources,
 // Drop down list of actions on triggers
 
@@ -17,14 +19,24 @@ pub struct Action {
     devices_pick_list: pick_list::State<String>,
     action_pick_list: pick_list::State<String>,
     delete_button: button::State,
+    duplicate_button: button::State,
+    copy_button: button::State,
     id: String,
     arguments: Vec<serde_json::Value>,
+    arguments_text: String,
+    arguments_input: text_input::State,
+    condition: String,
+    condition_input: text_input::State,
 }
 
 #[derive(Debug, Clone)]
 pub enum ActionMessage {
     DeviceSelected(String),
     DeviceActionSelected(String),
+    ArgumentsChanged(String),
+    ConditionChanged(String),
+    Duplicate,
+    Copy,
     Delete,
 }
 
@@ -40,8 +52,14 @@ impl Action {
             devices_pick_list: pick_list::State::new(),
             action_pick_list: pick_list::State::new(),
             delete_button: button::State::new(),
+            duplicate_button: button::State::new(),
+            copy_button: button::State::new(),
             id: sequence_event.id,
+            arguments_text: arguments_to_text(&sequence_event.arguments),
             arguments: sequence_event.arguments,
+            arguments_input: text_input::State::new(),
+            condition: sequence_event.condition.unwrap_or_default(),
+            condition_input: text_input::State::new(),
         }
     }
     pub fn to_reaction_sequence_item(&self) -> reaction_sequence::ReactionSequenceItemSequence {
@@ -50,6 +68,7 @@ impl Action {
             device_id: self.selected_device.clone().unwrap_or_default(),
             id: self.id.clone(),
             arguments: self.arguments.clone(),
+            condition: condition_from_text(&self.condition),
         }
     }
 
@@ -61,8 +80,14 @@ impl Action {
             devices_pick_list: pick_list::State::new(),
             action_pick_list: pick_list::State::new(),
             delete_button: button::State::new(),
+            duplicate_button: button::State::new(),
+            copy_button: button::State::new(),
             id: uuid::Uuid::new_v4().to_hyphenated().to_string(),
             arguments: vec![],
+            arguments_text: String::from("[]"),
+            arguments_input: text_input::State::new(),
+            condition: String::new(),
+            condition_input: text_input::State::new(),
         }
     }
 
@@ -79,11 +104,19 @@ impl Action {
             ActionMessage::DeviceActionSelected(selected_action) => {
                 self.selected_action = Some(selected_action)
             }
+            ActionMessage::ArgumentsChanged(arguments_text) => {
+                // Only keep the arguments once they are a valid JSON array
+                if let Ok(arguments) = serde_json::from_str(&arguments_text) {
+                    self.arguments = arguments;
+                }
+                self.arguments_text = arguments_text;
+            }
+            ActionMessage::ConditionChanged(condition) => self.condition = condition,
             _ => {}
         }
     }
 
-    pub fn view(&mut self) -> Element<ActionMessage> {
+    pub fn view(&mut self, selected: bool) -> Element<ActionMessage> {
         let mut keys: Vec<String> = Vec::new();
 
         for key in self.devices.keys() {
@@ -115,13 +148,50 @@ impl Action {
             ActionMessage::DeviceActionSelected,
         );
 
-        Column::new()
+        let mut buttons = Row::new()
+            .spacing(5)
             .push(
                 Button::new(&mut self.delete_button, Text::new("X"))
                     .on_press(ActionMessage::Delete),
             )
+            .push(
+                Button::new(&mut self.duplicate_button, Text::new("Duplicate"))
+                    .on_press(ActionMessage::Duplicate),
+            )
+            .push(
+                Button::new(&mut self.copy_button, Text::new("Copy")).on_press(ActionMessage::Copy),
+            );
+
+        if selected {
+            buttons = buttons.push(Text::new("*"));
+        }
+
+        Column::new()
+            .push(buttons)
             .push(device_pick_list)
             .push(action_pick_list)
+            .push(
+                TextInput::new(
+                    &mut self.arguments_input,
+                    "Arguments",
+                    &self.arguments_text,
+                    ActionMessage::ArgumentsChanged,
+                )
+                .padding(5),
+            )
+            .push(
+                TextInput::new(
+                    &mut self.condition_input,
+                    "Condition",
+                    &self.condition,
+                    ActionMessage::ConditionChanged,
+                )
+                .padding(5),
+            )
             .into()
     }
 }
+
+fn arguments_to_text(arguments: &Vec<serde_json::Value>) -> String {
+    serde_json::to_string(arguments).unwrap_or_default()
+}
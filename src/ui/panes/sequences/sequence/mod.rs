@@ -1,16 +1,15 @@
 pub mod action;
 pub mod trigger;
 
-use crate::custom_widgets::horizontal_scrollable::{self};
+use crate::custom_widgets::horizontal_scrollable::{self, HorizontalScrollable};
+use crate::migrations;
 use crate::sequencer::device::DevicesCollection;
-use crate::sequencer::reaction_sequence::{self, ReactionSequence};
+use crate::sequencer::reaction_sequence::{self, ReactionSequence, ReactionSequenceItemSequence};
 
 use crate::triggers::TriggerCollection;
-use iced::{self, button, Button, Column, Text};
+use iced::{self, button, text_input, Button, Checkbox, Column, Length, Text, TextInput};
 use iced::{Element, Row};
 
-use std::path::PathBuf;
-
 use self::action::ActionMessage;
 use uuid;
 
@@ -28,10 +27,20 @@ pub struct Sequence {
     state: SequenceState,
     add_action_button: button::State,
     delete_sequence_button: button::State,
+    duplicate_sequence_button: button::State,
+    save_as_template_button: button::State,
     scroll: horizontal_scrollable::State,
-    filename: String,
     name: String,
+    name_input: text_input::State,
+    description: String,
+    description_input: text_input::State,
+    tags: String, // Comma separated while editing
+    tags_input: text_input::State,
     id: String,
+    enabled: bool,
+    selected: bool,
+    selected_action: Option<usize>, // Target of keyboard moves, duplicate and copy
+    paste_button: button::State,
 }
 
 #[derive(Debug, Clone)]
@@ -44,17 +53,87 @@ pub enum SequenceMessage {
     TriggerMessage(trigger::TriggerMessage),
     ActionMessage(usize, action::ActionMessage),
     AddAction,
+    ActionMoved(usize, usize),
+    Paste,
+    NameChanged(String),
+    DescriptionChanged(String),
+    TagsChanged(String),
+    SelectedToggled(bool),
+    EnabledToggled(bool),
+    Duplicate,
+    SaveAsTemplate,
     Delete,
 }
 
 impl Sequence {
-    pub fn get_filename(self) -> String {
-        self.filename
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn selected_action(&self) -> Option<usize> {
+        self.selected_action
+    }
+
+    pub fn action_count(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn action_item(&self, i: usize) -> Option<ReactionSequenceItemSequence> {
+        self.actions
+            .get(i)
+            .map(|action| action.to_reaction_sequence_item())
+    }
+
+    /// Inserts a copy of an action after the selected one, or at the end
+    pub fn paste_action(&mut self, mut item: ReactionSequenceItemSequence) {
+        item.id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+
+        let index = self
+            .selected_action
+            .map(|selected| selected + 1)
+            .unwrap_or(self.actions.len())
+            .min(self.actions.len());
+
+        self.actions.insert(
+            index,
+            action::Action::from_existing(self.devices.clone(), item),
+        );
+        self.selected_action = Some(index);
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        tags_from_text(&self.tags)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn trigger_id(&self) -> String {
+        self.trigger.to_reaction_sequence_trigger().trigger_id
+    }
+
+    pub fn uses_device(&self, device_id: &str) -> bool {
+        self.actions
+            .iter()
+            .any(|action| action.to_reaction_sequence_item().device_id == device_id)
+    }
+
+    /// Whether the sequence is ticked for export
+    pub fn is_selected(&self) -> bool {
+        self.selected
     }
 
     pub fn from_existing(
         sequence: reaction_sequence::ReactionSequence,
-        filename: PathBuf,
         devices: DevicesCollection,
         triggers: TriggerCollection,
     ) -> Self {
@@ -69,15 +148,43 @@ impl Sequence {
             state: SequenceState::Ready,
             add_action_button: button::State::new(),
             delete_sequence_button: button::State::new(),
+            duplicate_sequence_button: button::State::new(),
+            save_as_template_button: button::State::new(),
             scroll: horizontal_scrollable::State::new(),
-            filename: String::from(filename.to_str().unwrap()),
             name: sequence.name,
+            name_input: text_input::State::new(),
+            description: sequence.description.unwrap_or_default(),
+            description_input: text_input::State::new(),
+            tags: sequence.tags.unwrap_or_default().join(", "),
+            tags_input: text_input::State::new(),
             id: sequence.id,
+            enabled: sequence.enabled,
+            selected: false,
+            selected_action: None,
+            paste_button: button::State::new(),
         };
     }
+
+    /// A copy with fresh ids for the sequence and its actions
+    pub fn duplicate(&self) -> Self {
+        let mut sequence = self.to_reaction_seqeunce();
+        sequence.id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+        sequence.name = format!("{} copy", sequence.name);
+        for item in sequence.sequence.iter_mut() {
+            item.id = uuid::Uuid::new_v4().to_hyphenated().to_string();
+        }
+
+        Sequence::from_existing(sequence, self.devices.clone(), self.trigger.triggers())
+    }
+
     pub fn to_reaction_seqeunce(&self) -> reaction_sequence::ReactionSequence {
         ReactionSequence {
+            version: migrations::sequence::VERSION,
             name: self.name.clone(),
+            enabled: self.enabled,
+            description: Some(self.description.clone())
+                .filter(|description| !description.is_empty()),
+            tags: Some(tags_from_text(&self.tags)).filter(|tags| !tags.is_empty()),
             trigger: self.trigger.to_reaction_sequence_trigger(),
             sequence: self
                 .actions
@@ -91,7 +198,6 @@ impl Sequence {
 
     pub async fn new(devices: DevicesCollection, triggers: TriggerCollection) -> Self {
         let id = uuid::Uuid::new_v4().to_hyphenated().to_string();
-        let filename = format!("./TRS/sequences/{}.json", &id); //TODO: shouldnt be here
 
         Sequence {
             trigger: trigger::Trigger::new(triggers),
@@ -100,10 +206,20 @@ impl Sequence {
             state: SequenceState::Ready,
             add_action_button: button::State::new(),
             delete_sequence_button: button::State::new(),
+            duplicate_sequence_button: button::State::new(),
+            save_as_template_button: button::State::new(),
             scroll: horizontal_scrollable::State::new(),
-            filename,
             name: String::from("Unnamed"),
+            name_input: text_input::State::new(),
+            description: String::new(),
+            description_input: text_input::State::new(),
+            tags: String::new(),
+            tags_input: text_input::State::new(),
             id,
+            enabled: true,
+            selected: false,
+            selected_action: None,
+            paste_button: button::State::new(),
         }
     }
 
@@ -116,30 +232,62 @@ impl Sequence {
             SequenceMessage::ActionMessage(i, action_message) => match action_message {
                 ActionMessage::Delete => {
                     self.actions.remove(i);
+                    self.selected_action = None;
+                }
+                ActionMessage::Duplicate => {
+                    self.selected_action = Some(i);
+                    if let Some(item) = self.action_item(i) {
+                        self.paste_action(item);
+                    }
                 }
                 _ => {
                     if let Some(action) = self.actions.get_mut(i) {
                         action.update(action_message);
+                        self.selected_action = Some(i);
                     }
                 }
             },
 
+            SequenceMessage::ActionMoved(from, to) => {
+                if from < self.actions.len() {
+                    let to = to.min(self.actions.len() - 1);
+                    let action = self.actions.remove(from);
+                    self.actions.insert(to, action);
+                    self.selected_action = Some(to);
+                }
+            }
+
             SequenceMessage::AddAction => {
                 self.actions.push(action::Action::new(self.devices.clone()))
             }
-            _ => todo!(),
+            SequenceMessage::NameChanged(name) => self.name = name,
+            SequenceMessage::DescriptionChanged(description) => self.description = description,
+            SequenceMessage::TagsChanged(tags) => self.tags = tags,
+            SequenceMessage::SelectedToggled(selected) => self.selected = selected,
+            SequenceMessage::EnabledToggled(enabled) => self.enabled = enabled,
+            // Handled by the sequences pane, which owns the list and the copied action
+            SequenceMessage::Delete
+            | SequenceMessage::Paste
+            | SequenceMessage::Duplicate
+            | SequenceMessage::SaveAsTemplate => {}
         }
     }
 
     pub fn view(&mut self) -> Element<SequenceMessage> {
         let mut col = Column::new().spacing(20);
 
-        let mut r = Row::new().spacing(20);
+        // The trigger is the first child of the row, so action i is child i + 1
+        let mut r = HorizontalScrollable::new(&mut self.scroll)
+            .spacing(20)
+            .on_reorder(|from, to| {
+                SequenceMessage::ActionMoved(from.wrapping_sub(1), to.saturating_sub(1))
+            });
 
         let trigger: Element<_> = self.trigger.view().map(SequenceMessage::TriggerMessage);
 
         r = r.push(trigger);
 
+        let selected_action = self.selected_action;
         r = self
             .actions
             .iter_mut()
@@ -147,7 +295,7 @@ impl Sequence {
             .fold(r, |row, (i, action)| {
                 row.push(
                     action
-                        .view()
+                        .view(selected_action == Some(i))
                         .map(move |message| SequenceMessage::ActionMessage(i, message)),
                 )
             });
@@ -167,10 +315,72 @@ impl Sequence {
             Row::new()
                 .spacing(20)
                 .push(delete_button)
-                .push(Text::new(self.name.clone())),
+                .push(
+                    Button::new(&mut self.duplicate_sequence_button, Text::new("Duplicate"))
+                        .on_press(SequenceMessage::Duplicate),
+                )
+                .push(
+                    Button::new(
+                        &mut self.save_as_template_button,
+                        Text::new("Save as template"),
+                    )
+                    .on_press(SequenceMessage::SaveAsTemplate),
+                )
+                .push(
+                    Button::new(&mut self.paste_button, Text::new("Paste"))
+                        .on_press(SequenceMessage::Paste),
+                )
+                .push(Checkbox::new(
+                    self.selected,
+                    "",
+                    SequenceMessage::SelectedToggled,
+                ))
+                .push(Checkbox::new(
+                    self.enabled,
+                    "Enabled",
+                    SequenceMessage::EnabledToggled,
+                ))
+                .push(
+                    TextInput::new(
+                        &mut self.name_input,
+                        "Name",
+                        &self.name,
+                        SequenceMessage::NameChanged,
+                    )
+                    .padding(5)
+                    .width(Length::Units(250)),
+                )
+                .push(
+                    TextInput::new(
+                        &mut self.tags_input,
+                        "Tags, comma separated",
+                        &self.tags,
+                        SequenceMessage::TagsChanged,
+                    )
+                    .padding(5)
+                    .width(Length::Units(250)),
+                ),
+        );
+
+        col = col.push(
+            TextInput::new(
+                &mut self.description_input,
+                "Description",
+                &self.description,
+                SequenceMessage::DescriptionChanged,
+            )
+            .padding(5),
         );
 
         col = col.push(r);
         col.into()
     }
 }
+
+fn tags_from_text(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect()
+}
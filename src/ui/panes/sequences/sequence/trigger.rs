@@ -1,5 +1,5 @@
 use iced;
-use iced::{pick_list, Column, Element, PickList};
+use iced::{pick_list, text_input, Column, Element, PickList, TextInput};
 
 use crate::sequencer::reaction_sequence::{self, ReactionSequenceTrigger};
 use crate::triggers::triggers::TriggerSource;
@@ -17,12 +17,15 @@ pub struct Trigger {
     triggers: TriggerCollection,
     triggers_pick_list: pick_list::State<String>,
     action_pick_list: pick_list::State<String>,
+    condition: String,
+    condition_input: text_input::State,
 }
 
 #[derive(Debug, Clone)]
 pub enum TriggerMessage {
     TriggerSelected(String),
     TriggerEventSelected(String),
+    ConditionChanged(String),
 }
 
 impl Trigger {
@@ -36,13 +39,20 @@ impl Trigger {
             triggers: triggers.clone(),
             triggers_pick_list: pick_list::State::new(),
             action_pick_list: pick_list::State::new(),
+            condition: trigger.condition.unwrap_or_default(),
+            condition_input: text_input::State::new(),
         }
     }
 
+    pub fn triggers(&self) -> TriggerCollection {
+        self.triggers.clone()
+    }
+
     pub fn to_reaction_sequence_trigger(&self) -> reaction_sequence::ReactionSequenceTrigger {
         ReactionSequenceTrigger {
             trigger_event_id: self.selected_event.clone().unwrap_or_default(),
             trigger_id: self.selected_trigger.clone().unwrap_or_default(),
+            condition: condition_from_text(&self.condition),
         }
     }
     pub fn new(triggers: TriggerCollection) -> Self {
@@ -52,6 +62,8 @@ impl Trigger {
             triggers: triggers.clone(),
             triggers_pick_list: pick_list::State::new(),
             action_pick_list: pick_list::State::new(),
+            condition: String::new(),
+            condition_input: text_input::State::new(),
         }
     }
 
@@ -75,6 +87,7 @@ impl Trigger {
             TriggerMessage::TriggerEventSelected(selected_event) => {
                 self.selected_event = Some(selected_event)
             }
+            TriggerMessage::ConditionChanged(condition) => self.condition = condition,
         }
     }
 
@@ -92,15 +105,13 @@ impl Trigger {
             TriggerMessage::TriggerSelected,
         );
 
-        let device = self
-            .triggers
-            .get(&(self.selected_trigger.clone().unwrap()))
-            .unwrap();
-
         let mut trigger_events: Vec<String> = Vec::new();
 
-        for key in device.get_events().keys() {
-            trigger_events.push(key.to_string());
+        // The selected trigger source may not be configured on this machine
+        if let Some(device) = self.triggers.get(&(self.selected_trigger.clone().unwrap())) {
+            for key in device.get_events().keys() {
+                trigger_events.push(key.to_string());
+            }
         }
 
         let event_pick_list = PickList::new(
@@ -113,6 +124,23 @@ impl Trigger {
         Column::new()
             .push(device_pick_list)
             .push(event_pick_list)
+            .push(
+                TextInput::new(
+                    &mut self.condition_input,
+                    "Condition",
+                    &self.condition,
+                    TriggerMessage::ConditionChanged,
+                )
+                .padding(5),
+            )
             .into()
     }
 }
+
+/// An empty condition is left out of the file rather than saved as ""
+pub fn condition_from_text(condition: &str) -> Option<String> {
+    match condition.trim() {
+        "" => None,
+        condition => Some(String::from(condition)),
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use iced::{
+    button, pick_list, text_input, Button, Column, Element, Length, PickList, Row, Text, TextInput,
+};
+
+use crate::sequencer::device::DevicesCollection;
+use crate::sequencer::templates::{Placeholder, PlaceholderKind, Template};
+
+/// The template gallery, and the placeholder form of the template being used
+#[derive(Debug, Clone)]
+pub struct Gallery {
+    entries: Vec<Entry>,
+    form: Option<Form>,
+    devices: DevicesCollection,
+    close_button: button::State,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    template: Template,
+    use_button: button::State,
+}
+
+#[derive(Debug, Clone)]
+struct Form {
+    template: Template,
+    fields: Vec<Field>,
+    error: Option<String>,
+    create_button: button::State,
+    cancel_button: button::State,
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    placeholder: Placeholder,
+    value: String,
+    devices: Vec<String>,
+    text_input: text_input::State,
+    pick_list: pick_list::State<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum GalleryMessage {
+    Use(usize),
+    TextChanged(usize, String),
+    DeviceSelected(usize, String),
+    Create,
+    Cancel,
+    Close,
+}
+
+impl Gallery {
+    pub fn new(templates: Vec<Template>, devices: &DevicesCollection) -> Self {
+        Gallery {
+            entries: templates
+                .into_iter()
+                .map(|template| Entry {
+                    template,
+                    use_button: button::State::new(),
+                })
+                .collect(),
+            form: None,
+            devices: devices.clone(),
+            close_button: button::State::new(),
+        }
+    }
+
+    /// The template being filled in with its placeholder values by key
+    pub fn filled_template(&self) -> Option<(&Template, HashMap<String, String>)> {
+        let form = self.form.as_ref()?;
+
+        Some((
+            &form.template,
+            form.fields
+                .iter()
+                .map(|field| (field.placeholder.key.clone(), field.value.clone()))
+                .collect(),
+        ))
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        if let Some(form) = self.form.as_mut() {
+            form.error = Some(error);
+        }
+    }
+
+    pub fn update(&mut self, message: GalleryMessage) {
+        match message {
+            GalleryMessage::Use(i) => {
+                if let Some(entry) = self.entries.get(i) {
+                    self.form = Some(Form::new(entry.template.clone(), &self.devices));
+                }
+            }
+            GalleryMessage::TextChanged(i, value) | GalleryMessage::DeviceSelected(i, value) => {
+                if let Some(field) = self.form.as_mut().and_then(|form| form.fields.get_mut(i)) {
+                    field.value = value;
+                }
+            }
+            GalleryMessage::Cancel => self.form = None,
+            // Handled by the sequences pane, which creates the sequence or closes the gallery
+            GalleryMessage::Create | GalleryMessage::Close => {}
+        }
+    }
+
+    pub fn view(&mut self) -> Element<GalleryMessage> {
+        if let Some(form) = self.form.as_mut() {
+            return form.view();
+        }
+
+        let entries = self.entries.iter_mut().enumerate().fold(
+            Column::new().spacing(10),
+            |column, (i, entry)| {
+                let name = if entry.template.built_in {
+                    format!("{} (built in)", entry.template.name)
+                } else {
+                    entry.template.name.clone()
+                };
+
+                column.push(
+                    Row::new()
+                        .spacing(20)
+                        .push(
+                            Button::new(&mut entry.use_button, Text::new("Use"))
+                                .on_press(GalleryMessage::Use(i)),
+                        )
+                        .push(Text::new(name).width(Length::Units(300)))
+                        .push(Text::new(entry.template.description.clone())),
+                )
+            },
+        );
+
+        Column::new()
+            .spacing(20)
+            .padding(10)
+            .push(Text::new("Templates"))
+            .push(entries)
+            .push(
+                Button::new(&mut self.close_button, Text::new("Close"))
+                    .on_press(GalleryMessage::Close),
+            )
+            .into()
+    }
+}
+
+impl Form {
+    fn new(template: Template, devices: &DevicesCollection) -> Self {
+        Form {
+            fields: template
+                .placeholders
+                .iter()
+                .map(|placeholder| {
+                    let options = if placeholder.kind == PlaceholderKind::Device {
+                        placeholder.device_options(devices)
+                    } else {
+                        vec![]
+                    };
+
+                    Field {
+                        placeholder: placeholder.clone(),
+                        // A single compatible device is the obvious choice
+                        value: if options.len() == 1 {
+                            options[0].clone()
+                        } else {
+                            String::new()
+                        },
+                        devices: options,
+                        text_input: text_input::State::new(),
+                        pick_list: pick_list::State::new(),
+                    }
+                })
+                .collect(),
+            template,
+            error: None,
+            create_button: button::State::new(),
+            cancel_button: button::State::new(),
+        }
+    }
+
+    fn view(&mut self) -> Element<GalleryMessage> {
+        let complete = self.fields.iter().all(|field| !field.value.is_empty());
+
+        let fields = self.fields.iter_mut().enumerate().fold(
+            Column::new().spacing(10),
+            |column, (i, field)| {
+                let input: Element<_> = match field.placeholder.kind {
+                    PlaceholderKind::Text | PlaceholderKind::Number => TextInput::new(
+                        &mut field.text_input,
+                        &field.placeholder.label,
+                        &field.value,
+                        move |value| GalleryMessage::TextChanged(i, value),
+                    )
+                    .padding(5)
+                    .width(Length::Units(300))
+                    .into(),
+                    PlaceholderKind::Device if field.devices.is_empty() => {
+                        Text::new("No compatible device").into()
+                    }
+                    PlaceholderKind::Device => PickList::new(
+                        &mut field.pick_list,
+                        field.devices.clone(),
+                        Some(field.value.clone()).filter(|value| !value.is_empty()),
+                        move |device_id| GalleryMessage::DeviceSelected(i, device_id),
+                    )
+                    .into(),
+                };
+
+                column.push(
+                    Row::new()
+                        .spacing(20)
+                        .push(Text::new(field.placeholder.label.clone()).width(Length::Units(250)))
+                        .push(input),
+                )
+            },
+        );
+
+        let mut create_button = Button::new(&mut self.create_button, Text::new("Create"));
+        if complete {
+            create_button = create_button.on_press(GalleryMessage::Create);
+        }
+
+        let mut column = Column::new()
+            .spacing(20)
+            .padding(10)
+            .push(Text::new(format!(
+                "New sequence from {}",
+                self.template.name
+            )))
+            .push(fields);
+
+        if let Some(error) = &self.error {
+            column = column.push(Text::new(error.clone()));
+        }
+
+        column
+            .push(
+                Row::new().spacing(20).push(create_button).push(
+                    Button::new(&mut self.cancel_button, Text::new("Cancel"))
+                        .on_press(GalleryMessage::Cancel),
+                ),
+            )
+            .into()
+    }
+}
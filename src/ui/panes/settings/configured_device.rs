@@ -1,13 +1,17 @@
 use std::collections::HashMap;
 
 use serde_json::{Map, Value};
+use tracing::debug;
 
 use crate::{
     sequencer::{
-        device::{DeviceTrait, DevicesCollection, Parameter, ParameterName},
+        device::{DeviceTrait, DevicesCollection},
         devices::{
             ble::{ble_device::BleDevice, bunny_ears::BunnyEars, util::get_ble_peripherals},
+            create_configured_device,
             timer::Timer,
+            validate_parameters,
+            variables::Variables,
             DeviceTypes,
         },
     },
@@ -23,41 +27,17 @@ pub async fn format_configured_devices(settings: Settings) -> DevicesCollection
         devices.insert(String::from(device_id), parsed_device);
     }
 
-    println!("{:?}", devices);
+    debug!(devices = ?devices.keys().collect::<Vec<_>>(), "Configured devices");
     devices
 }
 
-pub enum ValidationError {
-    MissingValue(ParameterName),
-}
-
-fn validate_parameters(
-    device: SettingsItemConfiguredDevices,
-    params: Vec<Parameter>,
-) -> Result<(), ValidationError> {
-    for parameter in params {
-        match parameter {
-            Parameter::String(name) => match name {
-                ParameterName::Address => {
-                    //TODO: This is not a scalable solution
-                    if device.address.is_none() {
-                        return Err(ValidationError::MissingValue(ParameterName::Address));
-                    }
-                }
-            },
-        }
-    }
-
-    return Ok(());
-}
-
 pub async fn parse_device(device: SettingsItemConfiguredDevices) -> Box<dyn DeviceTrait> {
     let ble_ps = get_ble_peripherals().await.unwrap(); //make lazy?
 
     // Need to handle perameters
     let device: Box<dyn DeviceTrait> = match DeviceTypes::from_string(&device.device_type) {
         DeviceTypes::BunnyEars => {
-            match validate_parameters(device.clone(), BunnyEars::get_device_parameters()) {
+            match validate_parameters(&device, BunnyEars::get_device_parameters()) {
                 Err(_v) => panic!("Invalid device"), //TODO: Correctly surface this error
                 Ok(params) => params,
             };
@@ -73,11 +53,26 @@ pub async fn parse_device(device: SettingsItemConfiguredDevices) -> Box<dyn Devi
             )
         }
         DeviceTypes::Timer => Box::new(Timer::new(device.id.clone(), device.name.clone())),
+        DeviceTypes::Variables => Box::new(Variables::new(device.id.clone(), device.name.clone())),
         DeviceTypes::BleDevice => Box::new(BleDevice::new(
             device.id.clone(),
             device.name.clone(),
             HashMap::new(),
         )),
+        DeviceTypes::Obs
+        | DeviceTypes::Http
+        | DeviceTypes::Serial
+        | DeviceTypes::Osc
+        | DeviceTypes::Mqtt
+        | DeviceTypes::TwitchChatSender
+        | DeviceTypes::Script
+        | DeviceTypes::Overlay => {
+            // The settings pane only lists devices so has no need of a Twitch login
+            match create_configured_device(&device, None) {
+                Ok(Some(configured)) => configured,
+                _ => panic!("Invalid device"), //TODO: Correctly surface this error
+            }
+        }
     };
 
     device
@@ -1,29 +1,56 @@
-use iced::{Column, Element, Row, Text};
+use iced::{text_input, Element, Length, Row, Text, TextInput};
 
 use crate::sequencer::device::DeviceImpler;
 
 #[derive(Debug, Clone)]
 pub struct DeviceUI {
     name: String,
+    name_input: text_input::State,
     id: String,
 }
 
 #[derive(Debug, Clone)]
-pub enum DeviceMessage {}
+pub enum DeviceMessage {
+    NameChanged(String),
+}
 
 impl DeviceUI {
     pub fn new(id: String, device: DeviceImpler) -> Self {
         return DeviceUI {
             name: device.get_name().clone(),
+            name_input: text_input::State::new(),
             id: id,
         };
     }
 
-    pub fn view(&self) -> Element<'_, DeviceMessage> {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn update(&mut self, message: DeviceMessage) {
+        match message {
+            DeviceMessage::NameChanged(name) => self.name = name,
+        }
+    }
+
+    pub fn view(&mut self) -> Element<'_, DeviceMessage> {
         return Row::new()
             .spacing(5)
-            .push(Text::new(self.id.clone()))
-            .push(Text::new(self.name.clone()))
+            .push(Text::new(self.id.clone()).width(Length::Units(200)))
+            .push(
+                TextInput::new(
+                    &mut self.name_input,
+                    "Name",
+                    &self.name,
+                    DeviceMessage::NameChanged,
+                )
+                .padding(5)
+                .width(Length::Units(250)),
+            )
             .into();
     }
 }
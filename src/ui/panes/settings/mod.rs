@@ -2,7 +2,10 @@ use iced::{button, Button, Column, Command, Element, Text};
 mod configured_device;
 mod device_ui;
 
-use crate::{sequencer::device::DevicesCollection, settings::Settings};
+use tokio::fs;
+use tracing::error;
+
+use crate::{sequencer::device::DevicesCollection, settings::Settings, ui::fs_utils::SaveError};
 
 use device_ui::{DeviceMessage, DeviceUI};
 
@@ -10,8 +13,11 @@ use self::configured_device::format_configured_devices;
 
 #[derive(Debug, Clone)]
 pub struct SettingsPane {
+    settings: Settings,
     devices: Vec<DeviceUI>,
     add_device_button: button::State,
+    save_button: button::State,
+    tainted: bool,
 }
 
 pub trait Component<Message>: Sized {
@@ -24,14 +30,46 @@ pub trait Component<Message>: Sized {
 pub enum SettingsMessage {
     Loaded(DevicesCollection),
     OnDeviceMessage(usize, DeviceMessage),
+    Save,
+    Saved(Result<Settings, SaveError>),
+}
+
+impl SettingsPane {
+    /// Whether there are edits which have not been saved
+    pub fn is_tainted(&self) -> bool {
+        self.tainted
+    }
+
+    /// Writes the settings with the edited device names, which take effect on the next start
+    pub fn save(&mut self) -> Command<SettingsMessage> {
+        if !self.tainted {
+            return Command::none();
+        }
+
+        let mut settings = self.settings.clone();
+        for configured_device in settings.configured_devices.iter_mut() {
+            if let Some(device) = self
+                .devices
+                .iter()
+                .find(|device| device.id() == configured_device.id)
+            {
+                configured_device.name = String::from(device.name());
+            }
+        }
+
+        Command::perform(save_settings(settings), SettingsMessage::Saved)
+    }
 }
 
 impl Component<SettingsMessage> for SettingsPane {
     fn new(settings: Settings) -> (SettingsPane, Command<SettingsMessage>) {
         (
             SettingsPane {
+                settings: settings.clone(),
                 devices: vec![],
                 add_device_button: button::State::new(),
+                save_button: button::State::new(),
+                tainted: false,
             },
             Command::perform(format_configured_devices(settings), SettingsMessage::Loaded),
         )
@@ -50,14 +88,33 @@ impl Component<SettingsMessage> for SettingsPane {
                 Command::none()
             }
 
-            SettingsMessage::OnDeviceMessage(_index, _devicemessage) => Command::none(),
+            SettingsMessage::OnDeviceMessage(index, device_message) => {
+                if let Some(device) = self.devices.get_mut(index) {
+                    device.update(device_message);
+                    self.tainted = true;
+                }
+                Command::none()
+            }
+
+            SettingsMessage::Save => self.save(),
+
+            SettingsMessage::Saved(Ok(settings)) => {
+                self.settings = settings;
+                self.tainted = false;
+                Command::none()
+            }
+
+            SettingsMessage::Saved(Err(error)) => {
+                error!("Failed to save settings: {:?}", error);
+                Command::none()
+            }
         }
     }
 
     fn view(&mut self) -> Element<'_, SettingsMessage> {
         let devices: Element<SettingsMessage> = self
             .devices
-            .iter()
+            .iter_mut()
             .enumerate()
             .fold(
                 Column::new().push(Text::new("Configured Devices")),
@@ -73,8 +130,23 @@ impl Component<SettingsMessage> for SettingsPane {
                 &mut self.add_device_button,
                 Text::new("Configure New Device +").size(20),
             ))
+            .push(
+                Button::new(&mut self.save_button, Text::new("Save"))
+                    .on_press(SettingsMessage::Save),
+            )
             .into();
 
         return devices.into();
     }
 }
+
+async fn save_settings(settings: Settings) -> Result<Settings, SaveError> {
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| SaveError::FormatError(e.to_string()))?;
+
+    fs::write("./TRS/settings.json", json) // TODO: this path should be relative to a userdata folder
+        .await
+        .map_err(|e| SaveError::FileError(e.to_string()))?;
+
+    Ok(settings)
+}
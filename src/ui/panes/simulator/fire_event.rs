@@ -0,0 +1,141 @@
+use iced::{button, text_input, Button, Column, Element, Row, Text, TextInput};
+use serde_json::{Map, Value};
+
+use crate::triggers::simulator::SimulatedEvent;
+
+#[derive(Debug, Clone)]
+pub struct FireEvent {
+    trigger_source: String,
+    trigger_event_id: String,
+    fields: Vec<PayloadField>,
+    fire_button: button::State,
+}
+
+#[derive(Debug, Clone)]
+struct PayloadField {
+    name: Option<String>, // None when the event declares no fields and takes a raw JSON payload
+    value: String,
+    input: text_input::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum FireEventMessage {
+    FieldChanged(usize, String),
+    Fire,
+}
+
+impl FireEvent {
+    pub fn new(
+        trigger_source: String,
+        trigger_event_id: String,
+        payload_fields: &[String],
+    ) -> Self {
+        let fields = if payload_fields.is_empty() {
+            vec![PayloadField {
+                name: None,
+                value: String::from("{}"),
+                input: text_input::State::new(),
+            }]
+        } else {
+            payload_fields
+                .iter()
+                .map(|name| PayloadField {
+                    name: Some(name.clone()),
+                    value: String::new(),
+                    input: text_input::State::new(),
+                })
+                .collect()
+        };
+
+        FireEvent {
+            trigger_source,
+            trigger_event_id,
+            fields,
+            fire_button: button::State::new(),
+        }
+    }
+
+    pub fn is_for(&self, trigger_source: &str, trigger_event_id: &str) -> bool {
+        self.trigger_source == trigger_source && self.trigger_event_id == trigger_event_id
+    }
+
+    pub fn update(&mut self, message: FireEventMessage) {
+        match message {
+            FireEventMessage::FieldChanged(i, value) => {
+                if let Some(field) = self.fields.get_mut(i) {
+                    field.value = value;
+                }
+            }
+            FireEventMessage::Fire => {}
+        }
+    }
+
+    pub fn to_simulated_event(&self) -> Result<SimulatedEvent, String> {
+        let mut payload = Map::new();
+
+        for field in self.fields.iter() {
+            match &field.name {
+                Some(name) => {
+                    payload.insert(name.clone(), parse_field_value(&field.value));
+                }
+                None => {
+                    return Ok(SimulatedEvent {
+                        trigger_source: self.trigger_source.clone(),
+                        trigger_event_id: self.trigger_event_id.clone(),
+                        payload: serde_json::from_str(&field.value)
+                            .map_err(|e| format!("Payload is not valid JSON: {}", e))?,
+                    })
+                }
+            }
+        }
+
+        Ok(SimulatedEvent {
+            trigger_source: self.trigger_source.clone(),
+            trigger_event_id: self.trigger_event_id.clone(),
+            payload: Value::Object(payload),
+        })
+    }
+
+    pub fn view(&mut self) -> Element<FireEventMessage> {
+        let label = format!("{} / {}", self.trigger_source, self.trigger_event_id);
+
+        let fields = self.fields.iter_mut().enumerate().fold(
+            Column::new().spacing(5),
+            |column: Column<_>, (i, field)| {
+                let placeholder = field
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| String::from("Payload (JSON)"));
+
+                column.push(
+                    Row::new()
+                        .spacing(10)
+                        .push(Text::new(placeholder.clone()))
+                        .push(
+                            TextInput::new(
+                                &mut field.input,
+                                &placeholder,
+                                &field.value,
+                                move |v| FireEventMessage::FieldChanged(i, v),
+                            )
+                            .padding(5),
+                        ),
+                )
+            },
+        );
+
+        Row::new()
+            .spacing(20)
+            .push(Text::new(label))
+            .push(fields)
+            .push(
+                Button::new(&mut self.fire_button, Text::new("Fire"))
+                    .on_press(FireEventMessage::Fire),
+            )
+            .into()
+    }
+}
+
+fn parse_field_value(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(String::from(value)))
+}
@@ -0,0 +1,133 @@
+mod fire_event;
+mod replay_controls;
+
+use iced::{scrollable, Column, Element, Length, Rule, Scrollable, Text};
+
+use crate::sequencer::reaction_sequence::ReactionSequenceTrigger;
+use crate::triggers::simulator::SimulatorHandle;
+use crate::triggers::{TriggerCollection, TriggerHandles};
+
+use fire_event::{FireEvent, FireEventMessage};
+use replay_controls::{ReplayControls, ReplayMessage};
+
+#[derive(Debug, Clone)]
+pub struct SimulatorPane {
+    handle: SimulatorHandle,
+    events: Vec<FireEvent>,
+    replay: ReplayControls,
+    scroll: scrollable::State,
+    status: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum SimulatorMessage {
+    FireEventMessage(usize, FireEventMessage),
+    ReplayMessage(ReplayMessage),
+}
+
+impl SimulatorPane {
+    pub fn new(triggers: &TriggerCollection, handles: TriggerHandles) -> Self {
+        let mut pane = SimulatorPane {
+            handle: handles.simulator,
+            events: vec![],
+            replay: ReplayControls::new(handles.replay),
+            scroll: scrollable::State::new(),
+            status: String::from("Start the listeners to fire events"),
+        };
+
+        for (trigger_id, trigger) in triggers.iter() {
+            for (event_id, event) in trigger.get_events().iter() {
+                pane.add_event(trigger_id, event_id, event.get_payload_fields());
+            }
+        }
+
+        pane
+    }
+
+    /// Adds the triggers used by sequences which their trigger source does not list itself
+    pub fn add_configured_triggers(&mut self, configured: Vec<ReactionSequenceTrigger>) {
+        for trigger in configured {
+            self.add_event(&trigger.trigger_id, &trigger.trigger_event_id, &[]);
+        }
+    }
+
+    fn add_event(&mut self, trigger_id: &str, event_id: &str, payload_fields: &[String]) {
+        if self
+            .events
+            .iter()
+            .any(|event| event.is_for(trigger_id, event_id))
+        {
+            return;
+        }
+
+        self.events.push(FireEvent::new(
+            String::from(trigger_id),
+            String::from(event_id),
+            payload_fields,
+        ));
+    }
+
+    pub fn update(&mut self, message: SimulatorMessage) {
+        match message {
+            SimulatorMessage::FireEventMessage(i, FireEventMessage::Fire) => {
+                if let Some(event) = self.events.get(i) {
+                    self.status = match event.to_simulated_event() {
+                        Ok(simulated_event) => {
+                            let description = format!(
+                                "{} / {}",
+                                simulated_event.trigger_source, simulated_event.trigger_event_id
+                            );
+                            if self.handle.fire(simulated_event) {
+                                format!("Fired {}", description)
+                            } else {
+                                String::from("Start the listeners to fire events")
+                            }
+                        }
+                        Err(msg) => msg,
+                    };
+                }
+            }
+
+            SimulatorMessage::FireEventMessage(i, fire_event_message) => {
+                if let Some(event) = self.events.get_mut(i) {
+                    event.update(fire_event_message);
+                }
+            }
+
+            SimulatorMessage::ReplayMessage(replay_message) => self.replay.update(replay_message),
+        }
+    }
+
+    pub fn view(&mut self) -> Element<SimulatorMessage> {
+        let events: Element<_> = self
+            .events
+            .iter_mut()
+            .enumerate()
+            .fold(
+                Column::new().spacing(20).padding(10),
+                |column: Column<_>, (i, event)| {
+                    column
+                        .push(
+                            event
+                                .view()
+                                .map(move |message| SimulatorMessage::FireEventMessage(i, message)),
+                        )
+                        .push(Rule::horizontal(5))
+                },
+            )
+            .into();
+
+        Scrollable::new(&mut self.scroll)
+            .push(
+                Column::new()
+                    .width(Length::Fill)
+                    .spacing(1)
+                    .push(Text::new("Simulator"))
+                    .push(Text::new(self.status.clone()))
+                    .push(events)
+                    .push(Rule::horizontal(5))
+                    .push(self.replay.view().map(SimulatorMessage::ReplayMessage)),
+            )
+            .into()
+    }
+}
@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+use iced::{button, text_input, Button, Column, Element, Row, Text, TextInput};
+
+use crate::triggers::replay::recorder::RECORDINGS_DIRECTORY;
+use crate::triggers::replay::{ReplayCommand, ReplayHandle, ReplayMode, MAX_SPEED, MIN_SPEED};
+
+#[derive(Debug, Clone)]
+pub struct ReplayControls {
+    handle: ReplayHandle,
+    path: String,
+    speed: String,
+    status: String,
+    path_input: text_input::State,
+    speed_input: text_input::State,
+    play_button: button::State,
+    start_stepping_button: button::State,
+    step_button: button::State,
+    stop_button: button::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum ReplayMessage {
+    PathChanged(String),
+    SpeedChanged(String),
+    Play,
+    StartStepping,
+    Step,
+    Stop,
+}
+
+impl ReplayControls {
+    pub fn new(handle: ReplayHandle) -> Self {
+        ReplayControls {
+            handle,
+            path: format!("{}/", RECORDINGS_DIRECTORY),
+            speed: String::from("1"),
+            status: String::new(),
+            path_input: text_input::State::new(),
+            speed_input: text_input::State::new(),
+            play_button: button::State::new(),
+            start_stepping_button: button::State::new(),
+            step_button: button::State::new(),
+            stop_button: button::State::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: ReplayMessage) {
+        let command = match message {
+            ReplayMessage::PathChanged(path) => {
+                self.path = path;
+                return;
+            }
+            ReplayMessage::SpeedChanged(speed) => {
+                self.speed = speed;
+                return;
+            }
+            ReplayMessage::Play => match self.speed.parse::<f64>() {
+                Ok(speed) if (MIN_SPEED..=MAX_SPEED).contains(&speed) => {
+                    ReplayCommand::Start(PathBuf::from(&self.path), ReplayMode::Timed(speed))
+                }
+                _ => {
+                    self.status =
+                        format!("Speed must be a number from {} to {}", MIN_SPEED, MAX_SPEED);
+                    return;
+                }
+            },
+            ReplayMessage::StartStepping => {
+                ReplayCommand::Start(PathBuf::from(&self.path), ReplayMode::Step)
+            }
+            ReplayMessage::Step => ReplayCommand::Step,
+            ReplayMessage::Stop => ReplayCommand::Stop,
+        };
+
+        self.status = if self.handle.send(command) {
+            String::new()
+        } else {
+            String::from("Start the listeners to replay a recording")
+        };
+    }
+
+    pub fn view(&mut self) -> Element<ReplayMessage> {
+        Column::new()
+            .spacing(10)
+            .push(Text::new("Replay"))
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(
+                        TextInput::new(
+                            &mut self.path_input,
+                            "Recording",
+                            &self.path,
+                            ReplayMessage::PathChanged,
+                        )
+                        .padding(5),
+                    )
+                    .push(Text::new("Speed x"))
+                    .push(
+                        TextInput::new(
+                            &mut self.speed_input,
+                            "1",
+                            &self.speed,
+                            ReplayMessage::SpeedChanged,
+                        )
+                        .padding(5),
+                    ),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(
+                        Button::new(&mut self.play_button, Text::new("Play"))
+                            .on_press(ReplayMessage::Play),
+                    )
+                    .push(
+                        Button::new(&mut self.start_stepping_button, Text::new("Step Through"))
+                            .on_press(ReplayMessage::StartStepping),
+                    )
+                    .push(
+                        Button::new(&mut self.step_button, Text::new("Next Event"))
+                            .on_press(ReplayMessage::Step),
+                    )
+                    .push(
+                        Button::new(&mut self.stop_button, Text::new("Stop"))
+                            .on_press(ReplayMessage::Stop),
+                    ),
+            )
+            .push(Text::new(self.status.clone()))
+            .into()
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use iced::{
+    button, scrollable, text_input, Button, Column, Element, Length, Row, Rule, Scrollable, Text,
+    TextInput,
+};
+use serde_json::Value;
+
+use crate::sequencer::variable_store::{VariableScope, VariableStore};
+
+#[derive(Debug, Clone)]
+pub struct VariablesPane {
+    store: VariableStore,
+    sequence_names: HashMap<String, String>,
+    rows: Vec<VariableRow>,
+    new_name: String,
+    new_name_input: text_input::State,
+    new_value: String,
+    new_value_input: text_input::State,
+    add_button: button::State,
+    refresh_button: button::State,
+    scroll: scrollable::State,
+}
+
+#[derive(Debug, Clone)]
+struct VariableRow {
+    scope: VariableScope,
+    sequence_id: String,
+    label: String,
+    name: String,
+    value: String,
+    value_input: text_input::State,
+    set_button: button::State,
+    reset_button: button::State,
+}
+
+#[derive(Debug, Clone)]
+pub enum VariablesMessage {
+    Refresh,
+    ValueChanged(usize, String),
+    Set(usize),
+    Reset(usize),
+    NewNameChanged(String),
+    NewValueChanged(String),
+    Add,
+}
+
+impl VariablesPane {
+    pub fn new(store: VariableStore) -> Self {
+        VariablesPane {
+            store,
+            sequence_names: HashMap::new(),
+            rows: vec![],
+            new_name: String::new(),
+            new_name_input: text_input::State::new(),
+            new_value: String::new(),
+            new_value_input: text_input::State::new(),
+            add_button: button::State::new(),
+            refresh_button: button::State::new(),
+            scroll: scrollable::State::new(),
+        }
+    }
+
+    /// Reads the current values, labelling per sequence variables with the sequence's name
+    pub fn refresh(&mut self, sequence_names: HashMap<String, String>) {
+        self.sequence_names = sequence_names;
+        self.reload();
+    }
+
+    fn reload(&mut self) {
+        let variables = self.store.snapshot();
+        let mut rows = vec![];
+
+        for (name, value) in variables.global.iter() {
+            rows.push(VariableRow::new(
+                VariableScope::Global,
+                String::new(),
+                String::from("global"),
+                name,
+                value,
+            ));
+        }
+
+        let mut sequence_ids: Vec<&String> = variables.sequences.keys().collect();
+        sequence_ids.sort();
+
+        for sequence_id in sequence_ids {
+            let label = self
+                .sequence_names
+                .get(sequence_id)
+                .cloned()
+                .unwrap_or_else(|| sequence_id.clone());
+
+            for (name, value) in variables.sequences[sequence_id].iter() {
+                rows.push(VariableRow::new(
+                    VariableScope::Sequence,
+                    sequence_id.clone(),
+                    label.clone(),
+                    name,
+                    value,
+                ));
+            }
+        }
+
+        self.rows = rows;
+    }
+
+    pub fn update(&mut self, message: VariablesMessage) {
+        match message {
+            VariablesMessage::Refresh => self.reload(),
+            VariablesMessage::ValueChanged(i, value) => {
+                if let Some(row) = self.rows.get_mut(i) {
+                    row.value = value;
+                }
+            }
+            VariablesMessage::Set(i) => {
+                if let Some(row) = self.rows.get(i) {
+                    self.store.set(
+                        &row.sequence_id,
+                        row.scope,
+                        &row.name,
+                        parse_value(&row.value),
+                    );
+                }
+            }
+            VariablesMessage::Reset(i) => {
+                if i < self.rows.len() {
+                    let row = self.rows.remove(i);
+                    self.store.reset(&row.sequence_id, row.scope, &row.name);
+                }
+            }
+            VariablesMessage::NewNameChanged(name) => self.new_name = name,
+            VariablesMessage::NewValueChanged(value) => self.new_value = value,
+            VariablesMessage::Add => {
+                let name = String::from(self.new_name.trim());
+                if name.is_empty() {
+                    return;
+                }
+
+                self.store.set(
+                    "",
+                    VariableScope::Global,
+                    &name,
+                    parse_value(&self.new_value),
+                );
+                self.reload();
+
+                self.new_name = String::new();
+                self.new_value = String::new();
+            }
+        }
+    }
+
+    pub fn view(&mut self) -> Element<VariablesMessage> {
+        let rows: Element<_> = self
+            .rows
+            .iter_mut()
+            .enumerate()
+            .fold(Column::new().spacing(10).padding(10), |column, (i, row)| {
+                column.push(row.view(i))
+            })
+            .into();
+
+        let add_row = Row::new()
+            .spacing(10)
+            .push(
+                TextInput::new(
+                    &mut self.new_name_input,
+                    "New global variable",
+                    &self.new_name,
+                    VariablesMessage::NewNameChanged,
+                )
+                .padding(5),
+            )
+            .push(
+                TextInput::new(
+                    &mut self.new_value_input,
+                    "Value",
+                    &self.new_value,
+                    VariablesMessage::NewValueChanged,
+                )
+                .padding(5),
+            )
+            .push(
+                Button::new(&mut self.add_button, Text::new("Add")).on_press(VariablesMessage::Add),
+            );
+
+        Scrollable::new(&mut self.scroll)
+            .push(
+                Column::new()
+                    .width(Length::Fill)
+                    .spacing(1)
+                    .push(
+                        Row::new().spacing(20).push(Text::new("Variables")).push(
+                            Button::new(&mut self.refresh_button, Text::new("Refresh"))
+                                .on_press(VariablesMessage::Refresh),
+                        ),
+                    )
+                    .push(rows)
+                    .push(Rule::horizontal(5))
+                    .push(add_row),
+            )
+            .into()
+    }
+}
+
+impl VariableRow {
+    fn new(
+        scope: VariableScope,
+        sequence_id: String,
+        label: String,
+        name: &str,
+        value: &Value,
+    ) -> Self {
+        VariableRow {
+            scope,
+            sequence_id,
+            label,
+            name: String::from(name),
+            value: match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            },
+            value_input: text_input::State::new(),
+            set_button: button::State::new(),
+            reset_button: button::State::new(),
+        }
+    }
+
+    fn view(&mut self, i: usize) -> Element<VariablesMessage> {
+        Row::new()
+            .spacing(10)
+            .push(Text::new(self.label.clone()).width(Length::Units(150)))
+            .push(Text::new(self.name.clone()).width(Length::Units(150)))
+            .push(
+                TextInput::new(&mut self.value_input, "Value", &self.value, move |value| {
+                    VariablesMessage::ValueChanged(i, value)
+                })
+                .padding(5),
+            )
+            .push(
+                Button::new(&mut self.set_button, Text::new("Set"))
+                    .on_press(VariablesMessage::Set(i)),
+            )
+            .push(
+                Button::new(&mut self.reset_button, Text::new("Reset"))
+                    .on_press(VariablesMessage::Reset(i)),
+            )
+            .into()
+    }
+}
+
+/// Text which reads as a number is stored as one, anything else as a string
+fn parse_value(text: &str) -> Value {
+    if let Ok(integer) = text.trim().parse::<i64>() {
+        return Value::from(integer);
+    }
+
+    match text.trim().parse::<f64>() {
+        Ok(float) if float.is_finite() => Value::from(float),
+        _ => Value::String(String::from(text)),
+    }
+}
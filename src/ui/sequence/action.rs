@@ -50,6 +50,7 @@ impl Action {
             device_id: self.selected_device.clone().unwrap_or_default(),
             id: self.id.clone(),
             arguments: self.arguments.clone(),
+            condition: None,
         }
     }
 
@@ -2,6 +2,7 @@ pub mod action;
 pub mod trigger;
 
 use crate::custom_widgets::horizontal_scrollable::{self};
+use crate::migrations;
 use crate::sequencer::device::DevicesCollection;
 use crate::sequencer::reaction_sequence::{self, ReactionSequence};
 
@@ -76,7 +77,11 @@ impl Sequence {
     }
     pub fn to_reaction_seqeunce(&self) -> reaction_sequence::ReactionSequence {
         ReactionSequence {
+            version: migrations::sequence::VERSION,
             name: self.name.clone(),
+            enabled: true,
+            description: None,
+            tags: None,
             trigger: self.trigger.to_reaction_sequence_trigger(),
             sequence: self
                 .actions
@@ -43,6 +43,7 @@ impl Trigger {
         ReactionSequenceTrigger {
             trigger_event_id: self.selected_event.clone().unwrap_or_default(),
             trigger_id: self.selected_trigger.clone().unwrap_or_default(),
+            condition: None,
         }
     }
     pub fn new(triggers: TriggerCollection) -> Self {
@@ -0,0 +1,59 @@
+use iced::{button, Button, Column, Element, Row, Text};
+
+/// Asks what to do with unsaved changes when the window is closed
+#[derive(Debug, Clone, Default)]
+pub struct UnsavedDialog {
+    save_button: button::State,
+    discard_button: button::State,
+    cancel_button: button::State,
+    error: Option<String>, // Why saving failed, shown until the next attempt
+}
+
+#[derive(Debug, Clone)]
+pub enum UnsavedMessage {
+    Save,
+    Discard,
+    Cancel,
+}
+
+impl UnsavedDialog {
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+    }
+
+    /// `unsaved` names the panes with changes, e.g. "Sequences"
+    pub fn view(&mut self, unsaved: &[&str]) -> Element<UnsavedMessage> {
+        let mut column = Column::new()
+            .spacing(20)
+            .padding(20)
+            .push(Text::new("Unsaved changes").size(24))
+            .push(Text::new(format!(
+                "{} {} unsaved changes. Save them before closing?",
+                unsaved.join(" and "),
+                if unsaved.len() == 1 { "has" } else { "have" }
+            )));
+
+        if let Some(error) = &self.error {
+            column = column.push(Text::new(error.clone()));
+        }
+
+        column
+            .push(
+                Row::new()
+                    .spacing(20)
+                    .push(
+                        Button::new(&mut self.save_button, Text::new("Save"))
+                            .on_press(UnsavedMessage::Save),
+                    )
+                    .push(
+                        Button::new(&mut self.discard_button, Text::new("Discard"))
+                            .on_press(UnsavedMessage::Discard),
+                    )
+                    .push(
+                        Button::new(&mut self.cancel_button, Text::new("Cancel"))
+                            .on_press(UnsavedMessage::Cancel),
+                    ),
+            )
+            .into()
+    }
+}